@@ -1,21 +1,59 @@
 //! A channel that can be kneaded into a [`Poller`].
 
+#[cfg(feature = "channel")]
 use crate::future::PollFuture;
+use crate::ping::{Notifier, Ping};
+#[cfg(feature = "channel")]
+use crate::EventSource;
 use crate::{Event, PollMode, Poller, Result, Source};
 
+#[cfg(all(feature = "channel", feature = "timer"))]
+use crate::timer::{Timer, TimerWheel};
+#[cfg(all(feature = "channel", feature = "timer"))]
+use crate::{is_secondary_key, secondary_interest};
+
+#[cfg(feature = "channel")]
+use futures_core::Stream;
+
+#[cfg(feature = "channel")]
+use std::collections::VecDeque;
+#[cfg(feature = "channel")]
+use std::fmt;
+#[cfg(feature = "channel")]
 use std::future::Future;
+use std::io;
+#[cfg(feature = "channel")]
 use std::pin::Pin;
-use std::task::Poll;
-use std::{fmt, io};
-
-type GenFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "channel")]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "channel")]
+use std::task::{Context, Poll};
+#[cfg(all(feature = "channel", feature = "timer"))]
+use std::time::Duration;
 
 /// Create a new, unbounded channel.
+#[cfg(feature = "channel")]
 pub fn unbounded<T: Send + 'static>() -> Result<(Sender<T>, Receiver<T>)> {
     let (sender, receiver) = async_channel::unbounded();
     from_channel(sender, receiver)
 }
 
+/// Create a new channel that holds at most `cap` values.
+///
+/// Sending into a full channel returns an error instead of blocking.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+#[cfg(feature = "channel")]
+pub fn bounded<T: Send + 'static>(cap: usize) -> Result<(Sender<T>, Receiver<T>)> {
+    let (sender, receiver) = async_channel::bounded(cap);
+    from_channel(sender, receiver)
+}
+
+#[cfg(feature = "channel")]
 fn from_channel<T: Send + 'static>(
     sender: async_channel::Sender<T>,
     receiver: async_channel::Receiver<T>,
@@ -23,34 +61,61 @@ fn from_channel<T: Send + 'static>(
     let sender = Sender { inner: sender };
 
     let receiver = Receiver {
-        future: PollFuture::new(Box::pin({
-            let receiver = receiver.clone();
-            async move { receiver.recv().await.ok() }
-        }) as GenFuture<Option<T>>)?,
-        inner: receiver,
+        future: PollFuture::new(Next(receiver))?,
     };
 
     Ok((sender, receiver))
 }
 
 /// The sender side of a channel.
+///
+/// Senders can be cloned to send from several places. Once every clone has been dropped, the
+/// channel is closed and the receiver is woken up so it can observe the disconnect.
+#[cfg(feature = "channel")]
 #[derive(Debug)]
 pub struct Sender<T> {
     inner: async_channel::Sender<T>,
 }
 
+#[cfg(feature = "channel")]
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
 /// The receiver side of a channel.
+#[cfg(feature = "channel")]
 pub struct Receiver<T> {
-    future: PollFuture<GenFuture<Option<T>>>,
-    inner: async_channel::Receiver<T>,
+    future: PollFuture<Next<T>>,
 }
 
+/// A future that resolves to the next value in the channel.
+///
+/// Unlike the future returned by `recv`, this can be polled again after it completes, so the
+/// same future is used for every value.
+#[cfg(feature = "channel")]
+struct Next<T>(async_channel::Receiver<T>);
+
+#[cfg(feature = "channel")]
+impl<T> Future for Next<T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+#[cfg(feature = "channel")]
 impl<T> fmt::Debug for Receiver<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Receiver").finish_non_exhaustive()
     }
 }
 
+#[cfg(feature = "channel")]
 impl<T> Sender<T> {
     /// Send a value into the channel.
     pub fn send(&self, value: T) -> Result<()> {
@@ -58,18 +123,137 @@ impl<T> Sender<T> {
             .try_send(value)
             .map_err(|_| io::Error::from(io::ErrorKind::Other))
     }
+
+    /// Send a value into the channel, handing it back if it could not be sent.
+    pub fn try_send(&self, value: T) -> std::result::Result<(), TrySendError<T>> {
+        self.inner.try_send(value).map_err(|err| match err {
+            async_channel::TrySendError::Full(value) => TrySendError::Full(value),
+            async_channel::TrySendError::Closed(value) => TrySendError::Closed(value),
+        })
+    }
+
+    /// Get the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Whether the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Get the capacity of the channel, or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.inner.capacity()
+    }
+
+    /// Whether the channel is closed, for instance because the receiver was dropped.
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Close the channel.
+    ///
+    /// The receiver is woken up, and once it has received every remaining value it observes
+    /// that the channel is disconnected. Returns `false` if the channel was already closed.
+    pub fn close(&self) -> bool {
+        self.inner.close()
+    }
 }
 
+#[cfg(feature = "channel")]
 impl<T: Send + 'static> Receiver<T> {
     /// Receive a value from the channel.
+    ///
+    /// Returns `None` both when the channel is empty and when it is disconnected; use
+    /// [`Receiver::poll_recv`] or [`Receiver::try_recv`] to tell the two apart.
     pub fn recv(&mut self) -> Option<T> {
-        match self.future.poll_unpin() {
+        match self.poll_recv() {
             Poll::Ready(value) => value,
-            _ => None,
+            Poll::Pending => None,
+        }
+    }
+
+    /// Poll for a value from the channel.
+    ///
+    /// Like [`Stream::poll_next`], `Poll::Pending` means the channel is empty and the receiver
+    /// will be woken up once a value is sent, while `Poll::Ready(None)` means the channel is
+    /// disconnected, so the receiver can be deregistered.
+    pub fn poll_recv(&mut self) -> Poll<Option<T>> {
+        self.future.poll_unpin()
+    }
+
+    /// Receive a value from the channel, reporting why none was available.
+    pub fn try_recv(&mut self) -> std::result::Result<T, TryRecvError> {
+        match self.poll_recv() {
+            Poll::Ready(Some(value)) => Ok(value),
+            Poll::Ready(None) => Err(TryRecvError::Disconnected),
+            Poll::Pending => Err(TryRecvError::Empty),
         }
     }
+
+    /// Receive a value, giving up once a timer from `wheel` fires after `timeout`.
+    ///
+    /// The returned source registers both the receiver and the timer, so the receiver should
+    /// not be registered on its own while it is in use. The wheel has to be fired for the
+    /// timer to go off.
+    #[cfg(feature = "timer")]
+    pub fn recv_timeout(
+        &mut self,
+        wheel: &mut TimerWheel,
+        timeout: Duration,
+    ) -> Result<RecvTimeout<'_, T>> {
+        Ok(RecvTimeout {
+            receiver: self,
+            timer: wheel.after(timeout)?,
+            timed_out: false,
+        })
+    }
+
+    /// Receive every value that is currently in the channel.
+    ///
+    /// The iterator stops once the channel is empty or disconnected, and
+    /// [`Drain::is_disconnected`] tells the two apart afterwards.
+    pub fn drain(&mut self) -> Drain<'_, T> {
+        Drain {
+            receiver: self,
+            disconnected: false,
+        }
+    }
+
+    /// Get the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.future.future().0.len()
+    }
+
+    /// Whether the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.future.future().0.is_empty()
+    }
+
+    /// Get the capacity of the channel, or `None` if it is unbounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.future.future().0.capacity()
+    }
+
+    /// Whether the channel is closed, for instance because every sender was dropped.
+    ///
+    /// Values sent before the channel was closed can still be received.
+    pub fn is_closed(&self) -> bool {
+        self.future.future().0.is_closed()
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: Send + 'static> EventSource for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(&mut self) -> Poll<Option<T>> {
+        self.poll_recv()
+    }
 }
 
+#[cfg(feature = "channel")]
 impl<T: Send + 'static> Source for Receiver<T> {
     fn register(
         &mut self,
@@ -86,22 +270,637 @@ impl<T: Send + 'static> Source for Receiver<T> {
         interest: Event,
         mode: PollMode,
     ) -> Result<()> {
-        self.future.reregister(poller, interest, mode)?;
+        self.future.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.future.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.future.handle_event(poller, event)?;
+
+        // A send that failed to wake us up would otherwise go unnoticed.
+        match self.future.take_error() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Reports progress if a value can be received, or if the channel is disconnected.
+    fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+        self.handle_event(poller, event)?;
+        Ok(!self.is_empty() || self.is_closed())
+    }
+}
+
+/// A receive that gives up once a timer fires, returned by [`Receiver::recv_timeout`].
+///
+/// The receiver is registered with the given interest, and the timer under its
+/// [secondary key](crate::secondary_key).
+#[cfg(all(feature = "channel", feature = "timer"))]
+#[derive(Debug)]
+pub struct RecvTimeout<'a, T> {
+    /// The receiver to receive from.
+    receiver: &'a mut Receiver<T>,
+
+    /// The timer that signals the timeout.
+    timer: Timer,
+
+    /// Whether the timer has fired.
+    timed_out: bool,
+}
+
+#[cfg(all(feature = "channel", feature = "timer"))]
+impl<T: Send + 'static> RecvTimeout<'_, T> {
+    /// Poll for a value, or for the timeout.
+    ///
+    /// If a value arrives in the same wakeup as the timeout, the value wins.
+    pub fn poll(&mut self) -> Poll<std::result::Result<T, RecvTimeoutError>> {
+        match self.receiver.poll_recv() {
+            Poll::Ready(Some(value)) => Poll::Ready(Ok(value)),
+            Poll::Ready(None) => Poll::Ready(Err(RecvTimeoutError::Disconnected)),
+            Poll::Pending if self.timed_out => Poll::Ready(Err(RecvTimeoutError::Timeout)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
 
-        // Reset the future.
-        *self.future.future_mut() = Box::pin({
-            let receiver = self.inner.clone();
-            async move { receiver.recv().await.ok() }
-        }) as GenFuture<Option<T>>;
+#[cfg(all(feature = "channel", feature = "timer"))]
+impl<T: Send + 'static> Source for RecvTimeout<'_, T> {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.receiver.register(poller, interest, mode)?;
+        if let Err(err) = self
+            .timer
+            .register(poller, secondary_interest(interest), mode)
+        {
+            let _ = self.receiver.deregister(poller);
+            return Err(err);
+        }
 
         Ok(())
     }
 
-    fn deregister(&mut self, poller: &std::sync::Arc<Poller>) -> Result<()> {
-        self.future.deregister(poller)
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.receiver.reregister(poller, interest, mode)?;
+        self.timer
+            .reregister(poller, secondary_interest(interest), mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        let result = self.receiver.deregister(poller);
+        self.timer.deregister(poller).and(result)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        if !is_secondary_key(event.key) {
+            return self.receiver.handle_event(poller, event);
+        }
+
+        // The timer only fires once, so its event means the time is up.
+        if matches!(self.timer.registration(), Some((interest, _)) if interest.key == event.key) {
+            self.timer.handle_event(poller, event)?;
+            self.timed_out = true;
+        }
+
+        Ok(())
+    }
+}
+
+/// The error returned by [`RecvTimeout::poll`] when no value was received.
+#[cfg(all(feature = "channel", feature = "timer"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    /// The timer fired before a value arrived.
+    Timeout,
+
+    /// The channel is empty and closed, so no more values will arrive.
+    Disconnected,
+}
+
+#[cfg(all(feature = "channel", feature = "timer"))]
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => f.write_str("timed out waiting on a channel"),
+            RecvTimeoutError::Disconnected => {
+                f.write_str("receiving from an empty and closed channel")
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "channel", feature = "timer"))]
+impl std::error::Error for RecvTimeoutError {}
+
+/// An iterator over the values currently in a channel, returned by [`Receiver::drain`].
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+pub struct Drain<'a, T> {
+    /// The receiver being drained.
+    receiver: &'a mut Receiver<T>,
+
+    /// Whether the channel was found to be disconnected.
+    disconnected: bool,
+}
+
+#[cfg(feature = "channel")]
+impl<T> Drain<'_, T> {
+    /// Whether draining stopped because the channel is disconnected.
+    ///
+    /// Once this is `true`, no more values will arrive and the receiver can be deregistered.
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: Send + 'static> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.disconnected {
+            return None;
+        }
+
+        match self.receiver.try_recv() {
+            Ok(value) => Some(value),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.disconnected = true;
+                None
+            }
+        }
+    }
+}
+
+/// The error returned by [`Receiver::try_recv`] when no value could be received.
+#[cfg(feature = "channel")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is empty, but values may still be sent.
+    Empty,
+
+    /// The channel is empty and closed, so no more values will arrive.
+    Disconnected,
+}
+
+#[cfg(feature = "channel")]
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => f.write_str("receiving from an empty channel"),
+            TryRecvError::Disconnected => f.write_str("receiving from an empty and closed channel"),
+        }
+    }
+}
+
+#[cfg(feature = "channel")]
+impl std::error::Error for TryRecvError {}
+
+/// The error returned by [`Sender::try_send`], which hands back the value that was not sent.
+#[cfg(feature = "channel")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full.
+    Full(T),
+
+    /// The channel is closed, so the value can never be received.
+    Closed(T),
+}
+
+#[cfg(feature = "channel")]
+impl<T> TrySendError<T> {
+    /// Get the value that was not sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Closed(value) => value,
+        }
+    }
+
+    /// Whether the channel was full.
+    pub fn is_full(&self) -> bool {
+        matches!(self, TrySendError::Full(_))
+    }
+
+    /// Whether the channel was closed.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, TrySendError::Closed(_))
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => f.write_str("sending into a full channel"),
+            TrySendError::Closed(_) => f.write_str("sending into a closed channel"),
+        }
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: fmt::Debug> std::error::Error for TrySendError<T> {}
+
+/// Create a new, unbounded channel whose receiver is woken up by a bare [`Ping`].
+///
+/// Unlike [`unbounded`], this does not drive a future through a [`Waker`], so it has less
+/// overhead per receiver and is available with only the `ping-channel` feature. Only the first send after the receiver finds the channel empty
+/// notifies the ping, so a burst of sends costs a single wakeup. In exchange, the receiver has
+/// to drain the channel with [`PingReceiver::try_recv`] until it returns `None` after each
+/// event.
+///
+/// [`Waker`]: std::task::Waker
+pub fn unbounded_ping<T>() -> Result<(PingSender<T>, PingReceiver<T>)> {
+    let (sender, receiver) = async_channel::unbounded();
+
+    // In counter mode a single event consumes every pending send.
+    let ping = Ping::with_semaphore(false)?;
+    let notified = Arc::new(AtomicBool::new(false));
+
+    let sender = PingSender {
+        inner: sender,
+        notifier: ping.notifier(),
+        notified: notified.clone(),
+    };
+    let receiver = PingReceiver {
+        inner: receiver,
+        ping,
+        notified,
+    };
+
+    Ok((sender, receiver))
+}
+
+/// The sender side of a channel created with [`unbounded_ping`].
+#[derive(Debug)]
+pub struct PingSender<T> {
+    inner: async_channel::Sender<T>,
+    notifier: Notifier,
+
+    /// Whether the ping has been notified since the receiver last found the channel empty.
+    notified: Arc<AtomicBool>,
+}
+
+/// The receiver side of a channel created with [`unbounded_ping`].
+#[derive(Debug)]
+pub struct PingReceiver<T> {
+    inner: async_channel::Receiver<T>,
+    ping: Ping,
+
+    /// Whether the ping has been notified since the receiver last found the channel empty.
+    notified: Arc<AtomicBool>,
+}
+
+impl<T> PingSender<T> {
+    /// Send a value into the channel and wake up the receiver.
+    ///
+    /// The ping is only notified if it has not been since the receiver last found the channel
+    /// empty.
+    pub fn send(&self, value: T) -> Result<()> {
+        self.inner
+            .try_send(value)
+            .map_err(|_| io::Error::from(io::ErrorKind::Other))?;
+
+        // A pending wakeup already covers this value, since the receiver drains the channel
+        // until it is empty after handling it.
+        if self.notified.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let result = self.notifier.notify();
+        if result.is_err() {
+            self.notified.store(false, Ordering::Release);
+        }
+        result
+    }
+}
+
+impl<T> PingReceiver<T> {
+    /// Receive a value from the channel, if one is available.
+    ///
+    /// Once this returns `None`, the next send wakes the receiver up again.
+    pub fn try_recv(&self) -> Option<T> {
+        if let Ok(value) = self.inner.try_recv() {
+            return Some(value);
+        }
+
+        // Values sent from now on need a new wakeup. A value sent while the flag was still set
+        // didn't notify the ping, so check again before reporting the channel as empty.
+        if self.notified.swap(false, Ordering::AcqRel) {
+            self.inner.try_recv().ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Source for PingReceiver<T> {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        if !self.ping.is_for(poller, &event) {
+            return Ok(());
+        }
+
+        self.ping.handle_event(poller, event)
+    }
+}
+
+/// Create a new channel that only keeps the latest value.
+///
+/// Sending several values before the receiver handles an event only wakes it up once.
+#[cfg(feature = "channel")]
+pub fn watch<T: Clone + Send + 'static>(initial: T) -> Result<(WatchSender<T>, WatchReceiver<T>)> {
+    // In counter mode a single event consumes every pending send.
+    let ping = Ping::with_semaphore(false)?;
+    let shared = Arc::new(Mutex::new(WatchState {
+        value: initial,
+        version: 0,
+    }));
+
+    let sender = WatchSender {
+        shared: shared.clone(),
+        notifier: ping.notifier(),
+    };
+    let receiver = WatchReceiver {
+        shared,
+        ping,
+        seen: 0,
+    };
+
+    Ok((sender, receiver))
+}
+
+/// The sender side of a watch channel.
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+pub struct WatchSender<T> {
+    shared: Arc<Mutex<WatchState<T>>>,
+    notifier: Notifier,
+}
+
+/// The receiver side of a watch channel.
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+pub struct WatchReceiver<T> {
+    shared: Arc<Mutex<WatchState<T>>>,
+    ping: Ping,
+
+    /// The version of the value that was current when the last event was handled.
+    seen: u64,
+}
+
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+struct WatchState<T> {
+    /// The latest value.
+    value: T,
+
+    /// The number of values that have been sent.
+    version: u64,
+}
+
+#[cfg(feature = "channel")]
+impl<T> WatchSender<T> {
+    /// Replace the value in the channel and wake up the receiver.
+    pub fn send(&self, value: T) -> Result<()> {
+        {
+            let mut state = lock(&self.shared);
+            state.value = value;
+            state.version += 1;
+        }
+
+        self.notifier.notify()
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: Clone> WatchReceiver<T> {
+    /// Get a copy of the latest value.
+    pub fn borrow(&self) -> T {
+        lock(&self.shared).value.clone()
+    }
+
+    /// Whether a value was sent since the last event was handled.
+    pub fn has_changed(&self) -> bool {
+        lock(&self.shared).version != self.seen
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: Clone> Source for WatchReceiver<T> {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.ping.handle_event(poller, event)?;
+        self.seen = lock(&self.shared).version;
+        Ok(())
     }
+}
+
+/// Create a new channel where every receiver gets its own copy of each value.
+///
+/// The channel holds on to the last `cap` values. A receiver that falls further behind than
+/// that skips the oldest values, and its next call to `recv` reports how many it missed with
+/// a [`Lagged`] error. Sending never blocks.
+///
+/// # Panics
+///
+/// Panics if `cap` is zero.
+#[cfg(feature = "channel")]
+pub fn broadcast<T: Clone + Send + 'static>(
+    cap: usize,
+) -> Result<(BroadcastSender<T>, BroadcastReceiver<T>)> {
+    assert!(cap > 0, "broadcast channel capacity must be nonzero");
+
+    let sender = BroadcastSender {
+        shared: Arc::new(Mutex::new(BroadcastState {
+            values: VecDeque::with_capacity(cap),
+            head: 0,
+            cap,
+            receivers: Vec::new(),
+            next_id: 0,
+        })),
+    };
+    let receiver = sender.subscribe()?;
+
+    Ok((sender, receiver))
+}
 
-    fn handle_event(&mut self, poller: &std::sync::Arc<Poller>, event: Event) -> Result<()> {
-        self.future.handle_event(poller, event)
+/// The sender side of a broadcast channel.
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+pub struct BroadcastSender<T> {
+    shared: Arc<Mutex<BroadcastState<T>>>,
+}
+
+/// The receiver side of a broadcast channel.
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+pub struct BroadcastReceiver<T> {
+    shared: Arc<Mutex<BroadcastState<T>>>,
+    ping: Ping,
+
+    /// The ID used to unsubscribe on drop.
+    id: usize,
+
+    /// The position of the next value to receive.
+    next: u64,
+}
+
+#[cfg(feature = "channel")]
+#[derive(Debug)]
+struct BroadcastState<T> {
+    /// The most recent values.
+    values: VecDeque<T>,
+
+    /// The position of the oldest value in `values`.
+    head: u64,
+
+    /// The maximum number of values to keep.
+    cap: usize,
+
+    /// The notifiers for every receiver.
+    receivers: Vec<(usize, Notifier)>,
+
+    /// The ID to give to the next receiver.
+    next_id: usize,
+}
+
+/// The error returned when a [`BroadcastReceiver`] fell behind and missed some values.
+#[cfg(feature = "channel")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged(pub u64);
+
+#[cfg(feature = "channel")]
+impl fmt::Display for Lagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "receiver lagged behind by {} values", self.0)
     }
 }
+
+#[cfg(feature = "channel")]
+impl std::error::Error for Lagged {}
+
+#[cfg(feature = "channel")]
+impl<T> BroadcastSender<T> {
+    /// Send a value to every receiver.
+    ///
+    /// Every receiver is notified even if notifying one of them fails; the first error is
+    /// returned.
+    pub fn send(&self, value: T) -> Result<()> {
+        let mut state = lock(&self.shared);
+        if state.values.len() == state.cap {
+            state.values.pop_front();
+            state.head += 1;
+        }
+        state.values.push_back(value);
+
+        let mut result = Ok(());
+        for (_, notifier) in &state.receivers {
+            if let Err(err) = notifier.notify() {
+                result = result.and(Err(err));
+            }
+        }
+
+        result
+    }
+
+    /// Create a new receiver that gets every value sent from now on.
+    pub fn subscribe(&self) -> Result<BroadcastReceiver<T>> {
+        // In counter mode a single event covers every value sent before it is handled.
+        let ping = Ping::with_semaphore(false)?;
+
+        let mut state = lock(&self.shared);
+        let id = state.next_id;
+        state.next_id += 1;
+        state.receivers.push((id, ping.notifier()));
+        let next = state.head + state.values.len() as u64;
+        drop(state);
+
+        Ok(BroadcastReceiver {
+            shared: self.shared.clone(),
+            ping,
+            id,
+            next,
+        })
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: Clone> BroadcastReceiver<T> {
+    /// Receive the next value, if there is one.
+    ///
+    /// Call this until it returns `Ok(None)` after handling an event. If values were dropped
+    /// before this receiver saw them, returns how many were missed and skips ahead.
+    pub fn recv(&mut self) -> std::result::Result<Option<T>, Lagged> {
+        let state = lock(&self.shared);
+        if self.next < state.head {
+            let missed = state.head - self.next;
+            self.next = state.head;
+            return Err(Lagged(missed));
+        }
+
+        let value = state.values.get((self.next - state.head) as usize).cloned();
+        if value.is_some() {
+            self.next += 1;
+        }
+
+        Ok(value)
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T> Drop for BroadcastReceiver<T> {
+    fn drop(&mut self) {
+        let id = self.id;
+        lock(&self.shared)
+            .receivers
+            .retain(|(receiver, _)| *receiver != id);
+    }
+}
+
+#[cfg(feature = "channel")]
+impl<T: Clone> Source for BroadcastReceiver<T> {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.ping.handle_event(poller, event)
+    }
+}
+
+#[cfg(feature = "channel")]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,22 @@
+//! Helpers for building [`Event`]s.
+
+use crate::Event;
+
+/// Interest in both reading and writing for the given key.
+pub fn both(key: usize) -> Event {
+    Event::all(key)
+}
+
+/// Interest in neither reading nor writing for the given key.
+pub fn none(key: usize) -> Event {
+    Event::none(key)
+}
+
+/// Interest in reading, writing, both, or neither for the given key.
+pub fn with(key: usize, readable: bool, writable: bool) -> Event {
+    Event {
+        key,
+        readable,
+        writable,
+    }
+}
@@ -10,19 +10,40 @@ macro_rules! cfg_futures_io {
     ($($i:item)*) => {};
 }
 
+#[cfg(feature = "timer")]
+macro_rules! cfg_timer {
+    ($($i:item)*) => {$($i)*};
+}
+
+#[cfg(not(feature = "timer"))]
+macro_rules! cfg_timer {
+    ($($i:item)*) => {};
+}
+
 use std::future::Future;
+use std::io;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll, Wake, Waker};
 
 use pin_project_lite::pin_project;
 
 use crate::ping::{Notifier, Ping};
-use crate::{Event, PollMode, Poller, Result, Source};
+use crate::{is_secondary_key, secondary_interest, Event, PollMode, Poller, Result, Source};
 
 cfg_futures_io! {
-    use futures_io::{AsyncRead, AsyncWrite, AsyncSeek};
-    use std::io::SeekFrom;
+    use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncSeek};
+    use std::convert::TryFrom;
+    use std::io::{IoSlice, IoSliceMut, SeekFrom};
+    use std::sync::Weak;
+    use std::task::ready;
+}
+
+cfg_timer! {
+    use crate::timer::{Timer, TimerWheel};
+    use std::fmt;
+    use std::time::Duration;
 }
 
 pin_project! {
@@ -41,6 +62,9 @@ pin_project! {
         // The ping event source.
         ping: Ping,
 
+        // The shared state behind the waker.
+        notify: Arc<Notify>,
+
         // The waker to be used to wake up the poll loop.
         waker: Waker,
 
@@ -53,6 +77,10 @@ pin_project! {
 cfg_futures_io! {
     pin_project! {
         /// A wrapper around an asynchronous reader.
+        ///
+        /// Any [`AsyncRead`] can be wrapped, including I/O handles driven by another reactor
+        /// such as `async_io::Async<TcpStream>`. That reactor wakes up this poll loop
+        /// whenever the handle becomes readable.
         #[derive(Debug)]
         pub struct PollRead<R: ?Sized> {
             #[pin]
@@ -60,6 +88,15 @@ cfg_futures_io! {
         }
     }
 
+    pin_project! {
+        /// A wrapper around an asynchronous buffered reader.
+        #[derive(Debug)]
+        pub struct PollBufRead<R: ?Sized> {
+            #[pin]
+            inner: PollFutureWithArg<BufReadPoller<R>>
+        }
+    }
+
     pin_project! {
         /// A wrapper around an asynchronous writer.
         #[derive(Debug)]
@@ -69,6 +106,29 @@ cfg_futures_io! {
         }
     }
 
+    pin_project! {
+        /// A wrapper around a stream that can be both read from and written to.
+        ///
+        /// Reads and writes share a single ping, so a stream such as
+        /// `async_io::Async<TcpStream>` only takes up one registration in the poller.
+        /// The directions that are waiting on the stream can be checked with
+        /// [`PollDuplex::read_waiting`] and [`PollDuplex::write_waiting`].
+        ///
+        /// Whenever a direction starts waiting on the stream, a oneshot registration that has
+        /// fired is re-armed in place, so it does not have to be re-registered after each event.
+        #[derive(Debug)]
+        pub struct PollDuplex<T: ?Sized> {
+            // The poller the stream is registered in, to re-arm the registration.
+            poller: Option<Weak<Poller>>,
+
+            // Whether the registration can still report an event.
+            armed: bool,
+
+            #[pin]
+            inner: PollFutureWithArg<DuplexPoller<T>>
+        }
+    }
+
     pin_project! {
         /// A wrapper around an asynchronous seeker.
         #[derive(Debug)]
@@ -77,21 +137,66 @@ cfg_futures_io! {
             inner: PollFutureWithArg<SeekPoller<S>>
         }
     }
+
+    pin_project! {
+        /// A reader that stops after a limited number of bytes.
+        ///
+        /// Created by [`PollRead::take`].
+        #[derive(Debug)]
+        pub struct PollTake<R: ?Sized> {
+            // The number of bytes that can still be read.
+            limit: u64,
+
+            #[pin]
+            inner: PollRead<R>,
+        }
+    }
 }
 
-impl<F: FutureWithArg + ?Sized> PollFutureWithArg<F> {
+impl<F: ?Sized> PollFutureWithArg<F> {
     /// Creates a new future to be polled.
     pub(crate) fn new_with_arg(future: F) -> Result<Self>
     where
         F: Sized,
     {
         let ping = Ping::new()?;
-        let waker = Waker::from(Arc::new(Notify(ping.notifier())));
-        Ok(Self {
+        let notifier = ping.notifier();
+        Ok(Self::with_notifier(future, ping, notifier))
+    }
+
+    /// Creates a new future whose waker notifies `notifier`.
+    fn with_notifier(future: F, ping: Ping, notifier: Notifier) -> Self
+    where
+        F: Sized,
+    {
+        let notify = Arc::new(Notify {
+            notifier,
+            error: Mutex::new(None),
+            woken: AtomicBool::new(false),
+        });
+        let waker = Waker::from(notify.clone());
+        Self {
             ping,
+            notify,
             waker,
             future,
-        })
+        }
+    }
+
+    /// Get the interest and mode the ping is registered with, if any.
+    #[cfg(feature = "futures-io")]
+    pub(crate) fn registration(&self) -> Option<(Event, PollMode)> {
+        self.ping.registration()
+    }
+
+    /// Take the error that occurred while waking up the poll loop, if any.
+    pub(crate) fn take_error(&self) -> Option<io::Error> {
+        self.notify.take_error()
+    }
+
+    /// Whether the waker has been woken since the future was last polled.
+    pub(crate) fn woken(&self) -> bool {
+        self.notify.woken.load(Ordering::Acquire)
     }
 
     /// Get a reference to the future.
@@ -109,19 +214,36 @@ impl<F: FutureWithArg + ?Sized> PollFutureWithArg<F> {
         self.project().future
     }
 
-    /// Poll this future to completion.
-    pub(crate) fn poll(self: Pin<&mut Self>, arg: &mut F::Argument<'_>) -> Poll<F::Output> {
-        let this = self.project();
-        let mut cx = Context::from_waker(this.waker);
-        this.future.poll_with_arg(&mut cx, arg)
+    /// Unwrap the future.
+    #[cfg(feature = "futures-io")]
+    pub(crate) fn into_future(self) -> F
+    where
+        F: Sized,
+    {
+        self.future
     }
 
-    /// Poll this future to completion, but without pinning.
-    pub(crate) fn poll_unpin(&mut self, arg: &mut F::Argument<'_>) -> Poll<F::Output>
+    /// Replace the future and wake up the poll loop so that it gets polled.
+    pub(crate) fn reset(&mut self, future: F)
     where
-        F: Unpin,
+        F: Sized,
     {
-        Pin::new(self).poll(arg)
+        self.future = future;
+        self.waker.wake_by_ref();
+    }
+
+    /// Poll the future with a custom function.
+    ///
+    /// This is used when the output borrows from the future, which `FutureWithArg` can't express.
+    #[cfg(feature = "futures-io")]
+    pub(crate) fn poll_with<'a, T>(
+        self: Pin<&'a mut Self>,
+        f: impl FnOnce(Pin<&'a mut F>, &mut Context<'_>) -> Poll<T>,
+    ) -> Poll<T> {
+        let this = self.project();
+        this.notify.woken.store(false, Ordering::Release);
+        let mut cx = Context::from_waker(this.waker);
+        f(this.future, &mut cx)
     }
 
     pub(crate) fn register(
@@ -148,21 +270,35 @@ impl<F: FutureWithArg + ?Sized> PollFutureWithArg<F> {
         this.ping.reregister(poller, interest, mode)
     }
 
-    pub(crate) fn deregister(self: Pin<&mut Self>, poller: &Arc<Poller>) -> Result<()> {
+    pub(crate) fn deregister(self: Pin<&mut Self>, poller: &Poller) -> Result<()> {
         let this = self.project();
         this.ping.deregister(poller)
     }
 
-    pub(crate) fn handle_event(
-        self: Pin<&mut Self>,
-        poller: &Arc<Poller>,
-        event: Event,
-    ) -> Result<()> {
+    pub(crate) fn handle_event(self: Pin<&mut Self>, poller: &Poller, event: Event) -> Result<()> {
         let this = self.project();
         this.ping.handle_event(poller, event)
     }
 }
 
+impl<F: FutureWithArg + ?Sized> PollFutureWithArg<F> {
+    /// Poll this future to completion.
+    pub(crate) fn poll(self: Pin<&mut Self>, arg: &mut F::Argument<'_>) -> Poll<F::Output> {
+        let this = self.project();
+        this.notify.woken.store(false, Ordering::Release);
+        let mut cx = Context::from_waker(this.waker);
+        this.future.poll_with_arg(&mut cx, arg)
+    }
+
+    /// Poll this future to completion, but without pinning.
+    pub(crate) fn poll_unpin(&mut self, arg: &mut F::Argument<'_>) -> Poll<F::Output>
+    where
+        F: Unpin,
+    {
+        Pin::new(self).poll(arg)
+    }
+}
+
 impl<F: Future + ?Sized> PollFuture<F> {
     /// Creates a new future to be polled.
     pub fn new(future: F) -> Result<Self>
@@ -189,7 +325,24 @@ impl<F: Future + ?Sized> PollFuture<F> {
         self.project().inner.future_pin_mut()
     }
 
+    /// Replace the future with a new one, for instance after the old one completed.
+    ///
+    /// This wakes up the poll loop so that the new future gets polled. A source registered in
+    /// oneshot mode still has to be re-registered to receive that event.
+    pub fn reset(&mut self, future: F)
+    where
+        F: Sized,
+    {
+        self.inner.reset(future);
+    }
+
     /// Poll this future to completion.
+    ///
+    /// Errors that occur while waking up the poll loop are not reported here. If the waker
+    /// fails to notify the ping, the poll loop is not woken up, so the future may never be
+    /// polled again through its event. The error is kept until [`PollFuture::try_poll`]
+    /// returns it; use that instead to observe these failures. The sources in this crate that
+    /// are built on a `PollFuture` return it from their `handle_event`.
     pub fn poll(self: Pin<&mut Self>) -> Poll<F::Output> {
         self.project().inner.poll(&mut ())
     }
@@ -201,6 +354,57 @@ impl<F: Future + ?Sized> PollFuture<F> {
     {
         self.inner.poll_unpin(&mut ())
     }
+
+    /// Poll this future to completion, returning an error if waking up the poll loop failed.
+    pub fn try_poll(self: Pin<&mut Self>) -> Poll<Result<F::Output>> {
+        if let Some(err) = self.inner.take_error() {
+            return Poll::Ready(Err(err));
+        }
+
+        self.poll().map(Ok)
+    }
+
+    /// Poll this future to completion without pinning, returning an error if waking up the
+    /// poll loop failed.
+    pub fn try_poll_unpin(&mut self) -> Poll<Result<F::Output>>
+    where
+        F: Unpin,
+    {
+        Pin::new(self).try_poll()
+    }
+
+    /// Take the error that occurred while waking up the poll loop, if any.
+    ///
+    /// Sources that poll with [`PollFuture::poll`] report this from their `handle_event`.
+    #[cfg(any(feature = "channel", all(feature = "threadpool", feature = "timer")))]
+    pub(crate) fn take_error(&self) -> Option<io::Error> {
+        self.inner.take_error()
+    }
+
+    /// Poll the future with the caller's context instead of the internal waker.
+    ///
+    /// This is useful when the `PollFuture` is nested inside another future that is driven by
+    /// an executor: the future wakes that executor directly, without a hop through the ping.
+    /// Wakeups from this poll do not wake up the poll loop.
+    pub fn poll_with_context(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        self.future_pin_mut().poll(cx)
+    }
+
+    /// Poll the future with the caller's context, but without pinning.
+    pub fn poll_with_context_unpin(&mut self, cx: &mut Context<'_>) -> Poll<F::Output>
+    where
+        F: Unpin,
+    {
+        Pin::new(self).poll_with_context(cx)
+    }
+
+    /// Whether the future has been woken since it was last polled.
+    ///
+    /// This can be used to skip polling futures that are expensive to poll. It is only a hint:
+    /// polling a future that has not been woken is always allowed.
+    pub fn woken(&self) -> bool {
+        self.inner.woken()
+    }
 }
 
 cfg_futures_io! {
@@ -230,8 +434,22 @@ cfg_futures_io! {
             self.project().inner.future_pin_mut().project().reader
         }
 
+        /// Unwrap the reader.
+        pub fn into_reader(self) -> R
+        where
+            R: Sized,
+        {
+            self.inner.into_future().reader
+        }
+
         /// Poll this reader to completion.
+        ///
+        /// Returns an error if waking up the poll loop failed.
         pub fn poll(self: Pin<&mut Self>, buf: &mut [u8]) -> Poll<Result<usize>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
             self.project().inner.poll(buf)
         }
 
@@ -240,7 +458,238 @@ cfg_futures_io! {
         where
             R: Unpin,
         {
-            self.inner.poll_unpin(buf)
+            Pin::new(self).poll(buf)
+        }
+
+        /// Poll to read into several buffers at once.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_read_vectored(
+            self: Pin<&mut Self>,
+            bufs: &mut [IoSliceMut<'_>],
+        ) -> Poll<Result<usize>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.project()
+                .inner
+                .poll_with(|reader, cx| reader.project().reader.poll_read_vectored(cx, bufs))
+        }
+
+        /// Poll to read into several buffers at once, but without pinning.
+        pub fn poll_read_vectored_unpin(
+            &mut self,
+            bufs: &mut [IoSliceMut<'_>],
+        ) -> Poll<Result<usize>>
+        where
+            R: Unpin,
+        {
+            Pin::new(self).poll_read_vectored(bufs)
+        }
+
+        /// Poll to fill the whole buffer.
+        ///
+        /// `filled` is the number of bytes at the start of `buf` that have already been read,
+        /// and is updated as more bytes come in, so reading can resume after `Pending`. Returns
+        /// an `UnexpectedEof` error if the reader ends before the buffer is full.
+        pub fn poll_read_exact(
+            mut self: Pin<&mut Self>,
+            buf: &mut [u8],
+            filled: &mut usize,
+        ) -> Poll<Result<()>> {
+            while *filled < buf.len() {
+                match ready!(self.as_mut().poll(&mut buf[*filled..])) {
+                    Ok(0) => {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "failed to fill whole buffer",
+                        )))
+                    }
+                    Ok(n) => *filled += n,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Poll::Ready(Err(err)),
+                }
+            }
+
+            Poll::Ready(Ok(()))
+        }
+
+        /// Poll to fill the whole buffer, but without pinning.
+        pub fn poll_read_exact_unpin(
+            &mut self,
+            buf: &mut [u8],
+            filled: &mut usize,
+        ) -> Poll<Result<()>>
+        where
+            R: Unpin,
+        {
+            Pin::new(self).poll_read_exact(buf, filled)
+        }
+
+        /// Poll to read until the reader reaches EOF, appending the bytes to `buf`.
+        ///
+        /// Bytes that were read before `Pending` is returned are kept in `buf`, so reading can
+        /// resume on the next poll. Once EOF is reached, returns the number of bytes this call
+        /// appended to `buf`, like [`Read::read_to_end`](std::io::Read::read_to_end).
+        pub fn poll_read_to_end(
+            mut self: Pin<&mut Self>,
+            buf: &mut Vec<u8>,
+        ) -> Poll<Result<usize>> {
+            let start = buf.len();
+
+            // Read through a chunk on the stack, so the spare capacity of `buf` never has to be
+            // zeroed.
+            let mut chunk = [0u8; READ_TO_END_CHUNK_SIZE];
+            loop {
+                match self.as_mut().poll(&mut chunk) {
+                    Poll::Ready(Ok(0)) => return Poll::Ready(Ok(buf.len() - start)),
+                    Poll::Ready(Ok(n)) => buf.extend_from_slice(&chunk[..n]),
+                    Poll::Ready(Err(err)) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+
+        /// Poll to read until the reader reaches EOF, but without pinning.
+        pub fn poll_read_to_end_unpin(&mut self, buf: &mut Vec<u8>) -> Poll<Result<usize>>
+        where
+            R: Unpin,
+        {
+            Pin::new(self).poll_read_to_end(buf)
+        }
+
+        /// Limit the reader to at most `limit` bytes in total.
+        ///
+        /// Once the limit is reached, the returned reader reports EOF without reading any more
+        /// from this one.
+        pub fn take(self, limit: u64) -> PollTake<R>
+        where
+            R: Sized,
+        {
+            PollTake { limit, inner: self }
+        }
+    }
+
+    impl<R: AsyncRead + ?Sized> PollTake<R> {
+        /// Get the number of bytes that can still be read.
+        pub fn limit(&self) -> u64 {
+            self.limit
+        }
+
+        /// Set the number of bytes that can still be read.
+        pub fn set_limit(&mut self, limit: u64) {
+            self.limit = limit;
+        }
+
+        /// Get a reference to the underlying reader.
+        pub fn get_ref(&self) -> &PollRead<R> {
+            &self.inner
+        }
+
+        /// Get a mutable reference to the underlying reader.
+        pub fn get_mut(&mut self) -> &mut PollRead<R> {
+            &mut self.inner
+        }
+
+        /// Unwrap the underlying reader.
+        pub fn into_inner(self) -> PollRead<R>
+        where
+            R: Sized,
+        {
+            self.inner
+        }
+
+        /// Poll to read up to the remaining limit.
+        ///
+        /// Returns `Ok(0)` once the limit has been reached.
+        pub fn poll(self: Pin<&mut Self>, buf: &mut [u8]) -> Poll<Result<usize>> {
+            let this = self.project();
+            if *this.limit == 0 {
+                return Poll::Ready(Ok(0));
+            }
+
+            let len = usize::try_from(*this.limit).map_or(buf.len(), |limit| limit.min(buf.len()));
+            let n = ready!(this.inner.poll(&mut buf[..len]))?;
+            if n > len {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "reader returned more bytes than the buffer holds",
+                )));
+            }
+
+            *this.limit -= n as u64;
+            Poll::Ready(Ok(n))
+        }
+
+        /// Poll to read up to the remaining limit, but without pinning.
+        pub fn poll_unpin(&mut self, buf: &mut [u8]) -> Poll<Result<usize>>
+        where
+            R: Unpin,
+        {
+            Pin::new(self).poll(buf)
+        }
+    }
+
+    impl<R: AsyncBufRead + ?Sized> PollBufRead<R> {
+        /// Creates a new buffered reader to be polled.
+        pub fn new(reader: R) -> Result<Self>
+        where
+            R: Sized,
+        {
+            Ok(Self {
+                inner: PollFutureWithArg::new_with_arg(BufReadPoller { reader })?,
+            })
+        }
+
+        /// Get a reference to the reader.
+        pub fn reader(&self) -> &R {
+            &self.inner.future().reader
+        }
+
+        /// Get a mutable reference to the reader.
+        pub fn reader_mut(&mut self) -> &mut R {
+            &mut self.inner.future_mut().reader
+        }
+
+        /// Get a pinned reference to the reader.
+        pub fn reader_pin_mut(self: Pin<&mut Self>) -> Pin<&mut R> {
+            self.project().inner.future_pin_mut().project().reader
+        }
+
+        /// Poll for the contents of the internal buffer, filling it if it is empty.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_fill_buf(self: Pin<&mut Self>) -> Poll<Result<&[u8]>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.project()
+                .inner
+                .poll_with(|reader, cx| reader.project().reader.poll_fill_buf(cx))
+        }
+
+        /// Poll for the contents of the internal buffer, but without pinning.
+        pub fn poll_fill_buf_unpin(&mut self) -> Poll<Result<&[u8]>>
+        where
+            R: Unpin,
+        {
+            Pin::new(self).poll_fill_buf()
+        }
+
+        /// Mark `amt` bytes of the internal buffer as consumed.
+        pub fn consume(self: Pin<&mut Self>, amt: usize) {
+            self.reader_pin_mut().consume(amt)
+        }
+
+        /// Mark `amt` bytes of the internal buffer as consumed, but without pinning.
+        pub fn consume_unpin(&mut self, amt: usize)
+        where
+            R: Unpin,
+        {
+            Pin::new(self).consume(amt)
         }
     }
 
@@ -251,7 +700,10 @@ cfg_futures_io! {
             W: Sized,
         {
             Ok(Self {
-                inner: PollFutureWithArg::new_with_arg(WritePoller { writer })?,
+                inner: PollFutureWithArg::new_with_arg(WritePoller {
+                    closed: false,
+                    writer,
+                })?,
             })
         }
 
@@ -270,66 +722,545 @@ cfg_futures_io! {
             self.project().inner.future_pin_mut().project().writer
         }
 
+        /// Unwrap the writer.
+        ///
+        /// Data that has not been flushed yet stays in the writer.
+        pub fn into_writer(self) -> W
+        where
+            W: Sized,
+        {
+            self.inner.into_future().writer
+        }
+
         /// Poll this writer to completion.
+        ///
+        /// Returns an error if waking up the poll loop failed.
         pub fn poll(self: Pin<&mut Self>, mut buf: &[u8]) -> Poll<Result<usize>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
             self.project().inner.poll(&mut buf)
         }
 
         /// Poll this writer to completion, but without pinning.
-        pub fn poll_unpin(&mut self, mut buf: &[u8]) -> Poll<Result<usize>>
+        pub fn poll_unpin(&mut self, buf: &[u8]) -> Poll<Result<usize>>
         where
             W: Unpin,
         {
-            self.inner.poll_unpin(&mut buf)
+            Pin::new(self).poll(buf)
         }
-    }
 
-    impl<S: AsyncSeek + ?Sized> PollSeek<S> {
-        /// Creates a new seeker to be polled.
-        pub fn new(seeker: S) -> Result<Self>
+        /// Poll to write from several buffers at once.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_write_vectored(
+            self: Pin<&mut Self>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.project()
+                .inner
+                .poll_with(|writer, cx| writer.poll_write_vectored(cx, bufs))
+        }
+
+        /// Poll to write from several buffers at once, but without pinning.
+        pub fn poll_write_vectored_unpin(&mut self, bufs: &[IoSlice<'_>]) -> Poll<Result<usize>>
         where
-            S: Sized,
+            W: Unpin,
         {
-            Ok(Self {
-                inner: PollFutureWithArg::new_with_arg(SeekPoller { seeker })?,
-            })
+            Pin::new(self).poll_write_vectored(bufs)
         }
 
-        /// Get a reference to the seeker.
-        pub fn seeker(&self) -> &S {
-            &self.inner.future().seeker
-        }
+        /// Poll to write the whole buffer.
+        ///
+        /// `written` is the number of bytes at the start of `buf` that have already been
+        /// written, and is updated as more bytes are written, so writing can resume after
+        /// `Pending`. Returns a `WriteZero` error if the writer stops accepting bytes.
+        pub fn poll_write_all(
+            mut self: Pin<&mut Self>,
+            buf: &[u8],
+            written: &mut usize,
+        ) -> Poll<Result<()>> {
+            while *written < buf.len() {
+                match ready!(self.as_mut().poll(&buf[*written..])) {
+                    Ok(0) => {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::WriteZero,
+                            "failed to write whole buffer",
+                        )))
+                    }
+                    Ok(n) => *written += n,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => return Poll::Ready(Err(err)),
+                }
+            }
 
-        /// Get a mutable reference to the seeker.
-        pub fn seeker_mut(&mut self) -> &mut S {
-            &mut self.inner.future_mut().seeker
+            Poll::Ready(Ok(()))
         }
 
-        /// Get a pinned reference to the seeker.
-        pub fn seeker_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
-            self.project().inner.future_pin_mut().project().seeker
+        /// Poll to write the whole buffer, but without pinning.
+        pub fn poll_write_all_unpin(&mut self, buf: &[u8], written: &mut usize) -> Poll<Result<()>>
+        where
+            W: Unpin,
+        {
+            Pin::new(self).poll_write_all(buf, written)
         }
 
-        /// Poll this seeker to completion.
-        pub fn poll(self: Pin<&mut Self>, mut pos: SeekFrom) -> Poll<Result<u64>> {
-            self.project().inner.poll(&mut pos)
+        /// Poll to write out an owned buffer.
+        ///
+        /// Bytes are removed from the front of `buf` as they are written, so the buffer keeps
+        /// track of what is left across wakeups. Returns `Ready` once `buf` is empty, and a
+        /// `WriteZero` error if the writer stops accepting bytes.
+        pub fn poll_write_owned(
+            mut self: Pin<&mut Self>,
+            buf: &mut Vec<u8>,
+        ) -> Poll<Result<()>> {
+            let mut written = 0;
+            let result = self.as_mut().poll_write_all(buf, &mut written);
+
+            // Drain once per poll rather than once per write.
+            buf.drain(..written);
+            result
         }
 
-        /// Poll this seeker to completion, but without pinning.
-        pub fn poll_unpin(&mut self, mut pos: SeekFrom) -> Poll<Result<u64>>
+        /// Poll to write out an owned buffer, but without pinning.
+        pub fn poll_write_owned_unpin(&mut self, buf: &mut Vec<u8>) -> Poll<Result<()>>
         where
-            S: Unpin,
+            W: Unpin,
         {
-            self.inner.poll_unpin(&mut pos)
+            Pin::new(self).poll_write_owned(buf)
         }
-    }
+
+        /// Poll to flush any data buffered by the writer.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_flush(self: Pin<&mut Self>) -> Poll<Result<()>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.project()
+                .inner
+                .poll_with(|writer, cx| writer.poll_flush(cx))
+        }
+
+        /// Poll to flush any data buffered by the writer, but without pinning.
+        pub fn poll_flush_unpin(&mut self) -> Poll<Result<()>>
+        where
+            W: Unpin,
+        {
+            Pin::new(self).poll_flush()
+        }
+
+        /// Poll to close the writer.
+        ///
+        /// Once this returns `Ready(Ok(()))`, further calls return `Ready(Ok(()))` immediately
+        /// and writes return an error.
+        pub fn poll_close(self: Pin<&mut Self>) -> Poll<Result<()>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.project()
+                .inner
+                .poll_with(|writer, cx| writer.poll_close(cx))
+        }
+
+        /// Poll to close the writer, but without pinning.
+        pub fn poll_close_unpin(&mut self) -> Poll<Result<()>>
+        where
+            W: Unpin,
+        {
+            Pin::new(self).poll_close()
+        }
+    }
+
+    impl<T: AsyncRead + AsyncWrite + ?Sized> PollDuplex<T> {
+        /// Creates a new stream to be polled.
+        pub fn new(stream: T) -> Result<Self>
+        where
+            T: Sized,
+        {
+            Ok(Self {
+                poller: None,
+                armed: false,
+                inner: PollFutureWithArg::new_with_arg(DuplexPoller {
+                    read_waiting: false,
+                    write_waiting: false,
+                    closed: false,
+                    stream,
+                })?,
+            })
+        }
+
+        /// Get a reference to the stream.
+        pub fn stream(&self) -> &T {
+            &self.inner.future().stream
+        }
+
+        /// Get a mutable reference to the stream.
+        pub fn stream_mut(&mut self) -> &mut T {
+            &mut self.inner.future_mut().stream
+        }
+
+        /// Get a pinned reference to the stream.
+        pub fn stream_pin_mut(self: Pin<&mut Self>) -> Pin<&mut T> {
+            self.project().inner.future_pin_mut().project().stream
+        }
+
+        /// Whether the last read returned `Pending` and is waiting on the stream.
+        pub fn read_waiting(&self) -> bool {
+            self.inner.future().read_waiting
+        }
+
+        /// Whether the last write, flush or close returned `Pending` and is waiting on the
+        /// stream.
+        pub fn write_waiting(&self) -> bool {
+            self.inner.future().write_waiting
+        }
+
+        /// Whether the waker has been woken since the stream was last polled.
+        pub fn woken(&self) -> bool {
+            self.inner.woken()
+        }
+
+        /// Poll to read from the stream.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_read(self: Pin<&mut Self>, buf: &mut [u8]) -> Poll<Result<usize>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            let mut this = self;
+            let poll = this
+                .as_mut()
+                .project()
+                .inner
+                .poll_with(|stream, cx| stream.poll_read(cx, buf));
+            this.rearm_pending(poll)
+        }
+
+        /// Poll to read from the stream, but without pinning.
+        pub fn poll_read_unpin(&mut self, buf: &mut [u8]) -> Poll<Result<usize>>
+        where
+            T: Unpin,
+        {
+            Pin::new(self).poll_read(buf)
+        }
+
+        /// Poll to write to the stream.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_write(self: Pin<&mut Self>, buf: &[u8]) -> Poll<Result<usize>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            let mut this = self;
+            let poll = this.as_mut().project().inner.poll_with(|stream, cx| {
+                stream.poll_write_with(cx, |stream, cx| stream.poll_write(cx, buf))
+            });
+            this.rearm_pending(poll)
+        }
+
+        /// Poll to write to the stream, but without pinning.
+        pub fn poll_write_unpin(&mut self, buf: &[u8]) -> Poll<Result<usize>>
+        where
+            T: Unpin,
+        {
+            Pin::new(self).poll_write(buf)
+        }
+
+        /// Poll to flush any data buffered by the stream.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll_flush(self: Pin<&mut Self>) -> Poll<Result<()>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            let mut this = self;
+            let poll = this
+                .as_mut()
+                .project()
+                .inner
+                .poll_with(|stream, cx| stream.poll_write_with(cx, |stream, cx| stream.poll_flush(cx)));
+            this.rearm_pending(poll)
+        }
+
+        /// Poll to flush any data buffered by the stream, but without pinning.
+        pub fn poll_flush_unpin(&mut self) -> Poll<Result<()>>
+        where
+            T: Unpin,
+        {
+            Pin::new(self).poll_flush()
+        }
+
+        /// Poll to close the writing half of the stream.
+        ///
+        /// Once this returns `Ready(Ok(()))`, further calls return `Ready(Ok(()))` immediately
+        /// and writes return an error. Reads are not affected.
+        pub fn poll_close(self: Pin<&mut Self>) -> Poll<Result<()>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            let mut this = self;
+            let poll = this
+                .as_mut()
+                .project()
+                .inner
+                .poll_with(|stream, cx| stream.poll_close(cx));
+            this.rearm_pending(poll)
+        }
+
+        /// Poll to close the writing half of the stream, but without pinning.
+        pub fn poll_close_unpin(&mut self) -> Poll<Result<()>>
+        where
+            T: Unpin,
+        {
+            Pin::new(self).poll_close()
+        }
+
+        /// Re-arm the registration if the poll has to wait on the stream.
+        ///
+        /// An error re-arming the registration is returned in place of `Pending`, since the
+        /// poll loop would not be woken up again.
+        fn rearm_pending<R>(self: Pin<&mut Self>, poll: Poll<Result<R>>) -> Poll<Result<R>> {
+            let this = self.project();
+            if poll.is_ready() || *this.armed {
+                return poll;
+            }
+
+            let poller = match this.poller.as_ref().and_then(Weak::upgrade) {
+                Some(poller) => poller,
+                None => return poll,
+            };
+            let (mut interest, mode) = match this.inner.registration() {
+                Some(registration) => registration,
+                None => return poll,
+            };
+
+            // The ping only ever becomes readable.
+            interest.readable = true;
+            if let Err(err) = this.inner.reregister(&poller, interest, mode) {
+                return Poll::Ready(Err(err));
+            }
+            *this.armed = true;
+            poll
+        }
+    }
+
+    impl<T: AsyncRead + AsyncWrite + ?Sized> PinnedSource for PollDuplex<T> {
+        fn register_pinned(
+            self: Pin<&mut Self>,
+            poller: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> Result<()> {
+            let this = self.project();
+            this.inner.register(poller, interest, mode)?;
+            *this.poller = Some(Arc::downgrade(poller));
+            *this.armed = interest.readable;
+            Ok(())
+        }
+
+        fn reregister_pinned(
+            self: Pin<&mut Self>,
+            poller: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> Result<()> {
+            let this = self.project();
+            this.inner.reregister(poller, interest, mode)?;
+            *this.poller = Some(Arc::downgrade(poller));
+            *this.armed = interest.readable;
+            Ok(())
+        }
+
+        fn deregister_pinned(self: Pin<&mut Self>, poller: &Poller) -> Result<()> {
+            let this = self.project();
+            this.inner.deregister(poller)?;
+            *this.poller = None;
+            *this.armed = false;
+            Ok(())
+        }
+
+        fn handle_event_pinned(self: Pin<&mut Self>, poller: &Poller, event: Event) -> Result<()> {
+            let this = self.project();
+            let fired = match this.inner.registration() {
+                Some((interest, mode)) if interest.key == event.key => {
+                    matches!(mode, PollMode::Oneshot | PollMode::EdgeOneshot)
+                }
+                _ => false,
+            };
+            this.inner.handle_event(poller, event)?;
+
+            // The event disarmed a oneshot registration.
+            if fired {
+                *this.armed = false;
+            }
+            Ok(())
+        }
+    }
+
+    impl<S: AsyncSeek + ?Sized> PollSeek<S> {
+        /// Creates a new seeker to be polled.
+        pub fn new(seeker: S) -> Result<Self>
+        where
+            S: Sized,
+        {
+            Ok(Self {
+                inner: PollFutureWithArg::new_with_arg(SeekPoller { seeker })?,
+            })
+        }
+
+        /// Get a reference to the seeker.
+        pub fn seeker(&self) -> &S {
+            &self.inner.future().seeker
+        }
+
+        /// Get a mutable reference to the seeker.
+        pub fn seeker_mut(&mut self) -> &mut S {
+            &mut self.inner.future_mut().seeker
+        }
+
+        /// Get a pinned reference to the seeker.
+        pub fn seeker_pin_mut(self: Pin<&mut Self>) -> Pin<&mut S> {
+            self.project().inner.future_pin_mut().project().seeker
+        }
+
+        /// Poll this seeker to completion.
+        ///
+        /// Returns an error if waking up the poll loop failed.
+        pub fn poll(self: Pin<&mut Self>, mut pos: SeekFrom) -> Poll<Result<u64>> {
+            if let Some(err) = self.inner.take_error() {
+                return Poll::Ready(Err(err));
+            }
+
+            self.project().inner.poll(&mut pos)
+        }
+
+        /// Poll this seeker to completion, but without pinning.
+        pub fn poll_unpin(&mut self, pos: SeekFrom) -> Poll<Result<u64>>
+        where
+            S: Unpin,
+        {
+            Pin::new(self).poll(pos)
+        }
+
+        /// Poll for the current position in the stream.
+        ///
+        /// This seeks by `SeekFrom::Current(0)`, so it costs as much as any other seek on the
+        /// underlying stream, and resolves to the absolute offset.
+        pub fn poll_stream_position(self: Pin<&mut Self>) -> Poll<Result<u64>> {
+            self.poll(SeekFrom::Current(0))
+        }
+
+        /// Poll for the current position in the stream, but without pinning.
+        pub fn poll_stream_position_unpin(&mut self) -> Poll<Result<u64>>
+        where
+            S: Unpin,
+        {
+            Pin::new(self).poll_stream_position()
+        }
+    }
+}
+
+/// A source made of parts that have to stay pinned.
+///
+/// [`pinned_source!`] implements [`Source`] on top of this for pinned references to the type,
+/// and for the type itself when it is [`Unpin`].
+trait PinnedSource {
+    fn register_pinned(
+        self: Pin<&mut Self>,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()>;
+
+    fn reregister_pinned(
+        self: Pin<&mut Self>,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()>;
+
+    fn deregister_pinned(self: Pin<&mut Self>, poller: &Poller) -> Result<()>;
+
+    fn handle_event_pinned(self: Pin<&mut Self>, poller: &Poller, event: Event) -> Result<()>;
+}
+
+macro_rules! pinned_source {
+    (
+        impl[$($gen:tt)*] Source for $ty:ty where [$($unpin:tt)*]
+    ) => {
+        impl<$($gen)*> Source for $ty where $($unpin)* {
+            fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+                Pin::new(self).register_pinned(poller, interest, mode)
+            }
+
+            fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+                Pin::new(self).reregister_pinned(poller, interest, mode)
+            }
+
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
+                Pin::new(self).deregister_pinned(poller)
+            }
+
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+                Pin::new(self).handle_event_pinned(poller, event)
+            }
+        }
+
+        impl<$($gen)*> Source for Pin<&mut $ty> {
+            fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+                self.as_mut().register_pinned(poller, interest, mode)
+            }
+
+            fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+                self.as_mut().reregister_pinned(poller, interest, mode)
+            }
+
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
+                self.as_mut().deregister_pinned(poller)
+            }
+
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+                self.as_mut().handle_event_pinned(poller, event)
+            }
+        }
+
+        impl<$($gen)*> Source for Pin<Box<$ty>> {
+            fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+                self.as_mut().register_pinned(poller, interest, mode)
+            }
+
+            fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+                self.as_mut().reregister_pinned(poller, interest, mode)
+            }
+
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
+                self.as_mut().deregister_pinned(poller)
+            }
+
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+                self.as_mut().handle_event_pinned(poller, event)
+            }
+        }
+    };
 }
 
 macro_rules! wrapper_around_inner {
     (
-        impl <$($param:ident: $gen:ident)?> Source for $ty:ty { .. }
+        impl <$($param:ident: $gen:ident $(+ $more:ident)*)?> Source for $ty:ty { .. }
     ) => {
-        impl <$($param: $gen + Unpin + ?Sized)?> Source for $ty {
+        impl <$($param: $gen $(+ $more)* + Unpin + ?Sized)?> Source for $ty {
             fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
                 Pin::new(self)
                     .project()
@@ -344,21 +1275,21 @@ macro_rules! wrapper_around_inner {
                     .reregister(poller, interest, mode)
             }
 
-            fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
                 Pin::new(self).project().inner.deregister(poller)
             }
 
-            fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
                 Pin::new(self).project().inner.handle_event(poller, event)
             }
         }
 
-        impl<$($param: $gen + ?Sized)?> Source for Pin<&mut $ty> {
-            fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+        impl<$($param: $gen $(+ $more)* + ?Sized)?> Source for Pin<&mut $ty> {
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
                 self.as_mut().project().inner.deregister(poller)
             }
 
-            fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
                 self.as_mut().project().inner.handle_event(poller, event)
             }
 
@@ -377,12 +1308,12 @@ macro_rules! wrapper_around_inner {
             }
         }
 
-        impl<$($param: $gen + ?Sized)?> Source for Pin<Box<$ty>> {
-            fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+        impl<$($param: $gen $(+ $more)* + ?Sized)?> Source for Pin<Box<$ty>> {
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
                 self.as_mut().project().inner.deregister(poller)
             }
 
-            fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
                 self.as_mut().project().inner.handle_event(poller, event)
             }
 
@@ -412,6 +1343,14 @@ cfg_futures_io! {
         impl<R: AsyncRead> Source for PollRead<R> { .. }
     }
 
+    wrapper_around_inner! {
+        impl<R: AsyncRead> Source for PollTake<R> { .. }
+    }
+
+    wrapper_around_inner! {
+        impl<R: AsyncBufRead> Source for PollBufRead<R> { .. }
+    }
+
     wrapper_around_inner! {
         impl<W: AsyncWrite> Source for PollWrite<W> { .. }
     }
@@ -419,8 +1358,315 @@ cfg_futures_io! {
     wrapper_around_inner! {
         impl<S: AsyncSeek> Source for PollSeek<S> { .. }
     }
+
+    pinned_source! {
+        impl[T: AsyncRead + AsyncWrite + ?Sized] Source for PollDuplex<T> where [T: Unpin]
+    }
+
+    /// Reads with the caller's context, so the reader can be handed back to async code.
+    ///
+    /// The ping of the reader is bypassed, so reading this way does not wake up the poll loop.
+    /// Only [`PollRead::poll`] and the other polling methods of `PollRead` do that.
+    impl<R: AsyncRead + ?Sized> AsyncRead for PollRead<R> {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<Result<usize>> {
+            self.reader_pin_mut().poll_read(cx, buf)
+        }
+
+        fn poll_read_vectored(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &mut [IoSliceMut<'_>],
+        ) -> Poll<Result<usize>> {
+            self.reader_pin_mut().poll_read_vectored(cx, bufs)
+        }
+    }
+}
+
+pin_project! {
+    /// Two futures racing to complete first.
+    ///
+    /// The left future is registered with the key the select is registered with, and the right
+    /// future with its [secondary key](crate::secondary_key), so every event is handled by the
+    /// side it was delivered for.
+    ///
+    /// Futures that are not [`Unpin`] can be registered by pinning the select.
+    #[derive(Debug)]
+    pub struct Select<A, B> {
+        // The side that completed, if any.
+        finished: Option<Side>,
+
+        // The left future.
+        #[pin]
+        a: PollFuture<A>,
+
+        // The right future.
+        #[pin]
+        b: PollFuture<B>,
+    }
+}
+
+/// A value that is one of two types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Either<A, B> {
+    /// The left value.
+    Left(A),
+
+    /// The right value.
+    Right(B),
+}
+
+/// Which side of a [`Select`] completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Poll two futures, completing with the output of whichever finishes first.
+pub fn select<A: Future, B: Future>(a: PollFuture<A>, b: PollFuture<B>) -> Select<A, B> {
+    Select {
+        finished: None,
+        a,
+        b,
+    }
+}
+
+impl<A: Future, B: Future> Select<A, B> {
+    /// Poll both futures, returning the output of the first one to complete.
+    ///
+    /// The left future is polled first. Once one future has completed, this must not be polled
+    /// again.
+    pub fn poll(self: Pin<&mut Self>) -> Poll<Either<A::Output, B::Output>> {
+        let this = self.project();
+        if let Poll::Ready(output) = this.a.poll() {
+            *this.finished = Some(Side::Left);
+            return Poll::Ready(Either::Left(output));
+        }
+
+        if let Poll::Ready(output) = this.b.poll() {
+            *this.finished = Some(Side::Right);
+            return Poll::Ready(Either::Right(output));
+        }
+
+        Poll::Pending
+    }
+
+    /// Poll both futures, returning the output of the first one to complete, but without
+    /// pinning.
+    pub fn poll_unpin(&mut self) -> Poll<Either<A::Output, B::Output>>
+    where
+        A: Unpin,
+        B: Unpin,
+    {
+        Pin::new(self).poll()
+    }
+
+    /// Get the future that has not completed yet.
+    ///
+    /// Returns `None` if neither future has completed. The remaining future is still registered
+    /// with the key it was given by this `Select`, which is the secondary key for the right
+    /// future.
+    pub fn into_remaining(self) -> Option<Either<PollFuture<A>, PollFuture<B>>> {
+        match self.finished? {
+            Side::Left => Some(Either::Right(self.b)),
+            Side::Right => Some(Either::Left(self.a)),
+        }
+    }
+}
+
+impl<A: Future, B: Future> PinnedSource for Select<A, B> {
+    fn register_pinned(
+        self: Pin<&mut Self>,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        let mut this = self.project();
+        this.a.register(poller, interest, mode)?;
+        if let Err(err) = this.b.register(poller, secondary_interest(interest), mode) {
+            let _ = this.a.deregister(poller);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    fn reregister_pinned(
+        self: Pin<&mut Self>,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        let mut this = self.project();
+        this.a.reregister(poller, interest, mode)?;
+        this.b
+            .reregister(poller, secondary_interest(interest), mode)
+    }
+
+    fn deregister_pinned(self: Pin<&mut Self>, poller: &Poller) -> Result<()> {
+        let mut this = self.project();
+        let result = this.a.deregister(poller);
+        this.b.deregister(poller).and(result)
+    }
+
+    fn handle_event_pinned(self: Pin<&mut Self>, poller: &Poller, event: Event) -> Result<()> {
+        let mut this = self.project();
+        if is_secondary_key(event.key) {
+            this.b.handle_event(poller, event)
+        } else {
+            this.a.handle_event(poller, event)
+        }
+    }
+}
+
+pinned_source! {
+    impl[A: Future, B: Future] Source for Select<A, B> where [A: Unpin, B: Unpin]
+}
+
+cfg_timer! {
+    pin_project! {
+        /// A future that gives up once a timer fires.
+        ///
+        /// The future is registered with the key the timeout is registered with, and the timer
+        /// with its [secondary key](crate::secondary_key).
+        ///
+        /// Futures that are not [`Unpin`] can be registered by pinning the timeout.
+        #[derive(Debug)]
+        pub struct Timeout<F: ?Sized> {
+            // The timer that signals the timeout.
+            timer: Timer,
+
+            // Whether the timer has fired.
+            timed_out: bool,
+
+            // The future to be polled.
+            #[pin]
+            future: PollFuture<F>,
+        }
+    }
+
+    /// The error returned when a [`Timeout`] expires before its future completes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TimedOut;
+
+    impl fmt::Display for TimedOut {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("future timed out")
+        }
+    }
+
+    impl std::error::Error for TimedOut {}
+
+    impl<F: Future + ?Sized> Timeout<F> {
+        /// Creates a new future that times out after the given duration.
+        pub fn new(future: F, wheel: &mut TimerWheel, duration: Duration) -> Result<Self>
+        where
+            F: Sized,
+        {
+            Ok(Self {
+                timer: wheel.after(duration)?,
+                timed_out: false,
+                future: PollFuture::new(future)?,
+            })
+        }
+
+        /// Get a reference to the future.
+        pub fn future(&self) -> &F {
+            self.future.future()
+        }
+
+        /// Get a mutable reference to the future.
+        pub fn future_mut(&mut self) -> &mut F {
+            self.future.future_mut()
+        }
+
+        /// Poll this future to completion, or until the timer fires.
+        pub fn poll(self: Pin<&mut Self>) -> Poll<std::result::Result<F::Output, TimedOut>> {
+            let this = self.project();
+            if let Poll::Ready(output) = this.future.poll() {
+                return Poll::Ready(Ok(output));
+            }
+
+            if *this.timed_out {
+                Poll::Ready(Err(TimedOut))
+            } else {
+                Poll::Pending
+            }
+        }
+
+        /// Poll this future to completion, or until the timer fires, but without pinning.
+        pub fn poll_unpin(&mut self) -> Poll<std::result::Result<F::Output, TimedOut>>
+        where
+            F: Unpin,
+        {
+            Pin::new(self).poll()
+        }
+    }
+
+    impl<F: Future + ?Sized> PinnedSource for Timeout<F> {
+        fn register_pinned(
+            self: Pin<&mut Self>,
+            poller: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> Result<()> {
+            let mut this = self.project();
+            this.future.register(poller, interest, mode)?;
+            if let Err(err) = this.timer.register(poller, secondary_interest(interest), mode) {
+                let _ = this.future.deregister(poller);
+                return Err(err);
+            }
+
+            Ok(())
+        }
+
+        fn reregister_pinned(
+            self: Pin<&mut Self>,
+            poller: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> Result<()> {
+            let mut this = self.project();
+            this.future.reregister(poller, interest, mode)?;
+            this.timer.reregister(poller, secondary_interest(interest), mode)
+        }
+
+        fn deregister_pinned(self: Pin<&mut Self>, poller: &Poller) -> Result<()> {
+            let mut this = self.project();
+            let result = this.future.deregister(poller);
+            this.timer.deregister(poller).and(result)
+        }
+
+        fn handle_event_pinned(self: Pin<&mut Self>, poller: &Poller, event: Event) -> Result<()> {
+            let mut this = self.project();
+            if !is_secondary_key(event.key) {
+                return this.future.handle_event(poller, event);
+            }
+
+            // The timer is only notified once its deadline has passed.
+            let expired = this.timer.is_expired();
+            this.timer.handle_event(poller, event)?;
+            if expired {
+                *this.timed_out = true;
+            }
+
+            Ok(())
+        }
+    }
+
+    pinned_source! {
+        impl[F: Future + ?Sized] Source for Timeout<F> where [F: Unpin]
+    }
 }
 
+/// The size of the chunks that [`PollRead::poll_read_to_end`] reads.
+#[cfg(feature = "futures-io")]
+const READ_TO_END_CHUNK_SIZE: usize = 8 * 1024;
+
 /// Poll an async future with an argument.
 ///
 /// Good for wrappers like `PollRead` and `PollWrite`.
@@ -467,14 +1713,61 @@ cfg_futures_io! {
         }
     }
 
+    pin_project! {
+        #[derive(Debug)]
+        struct BufReadPoller<R: ?Sized> {
+            #[pin]
+            reader: R,
+        }
+    }
+
     pin_project! {
         #[derive(Debug)]
         struct WritePoller<W: ?Sized> {
+            closed: bool,
             #[pin]
             writer: W,
         }
     }
 
+    impl<W: AsyncWrite + ?Sized> WritePoller<W> {
+        fn poll_write_vectored(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<Result<usize>> {
+            let this = self.project();
+            if *this.closed {
+                return Poll::Ready(Err(closed_error()));
+            }
+
+            this.writer.poll_write_vectored(cx, bufs)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            let this = self.project();
+            if *this.closed {
+                return Poll::Ready(Ok(()));
+            }
+
+            this.writer.poll_flush(cx)
+        }
+
+        fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            let this = self.project();
+            if *this.closed {
+                return Poll::Ready(Ok(()));
+            }
+
+            let result = ready!(this.writer.poll_close(cx));
+            if result.is_ok() {
+                *this.closed = true;
+            }
+
+            Poll::Ready(result)
+        }
+    }
+
     impl<W: AsyncWrite + ?Sized> FutureWithArg for WritePoller<W> {
         type Argument<'a> = &'a [u8];
         type Output = Result<usize>;
@@ -485,10 +1778,69 @@ cfg_futures_io! {
             arg: &mut &[u8],
         ) -> Poll<Self::Output> {
             let this = self.project();
+            if *this.closed {
+                return Poll::Ready(Err(closed_error()));
+            }
+
             this.writer.poll_write(cx, arg)
         }
     }
 
+    fn closed_error() -> io::Error {
+        io::Error::new(io::ErrorKind::BrokenPipe, "writer has been closed")
+    }
+
+    pin_project! {
+        #[derive(Debug)]
+        struct DuplexPoller<T: ?Sized> {
+            // Whether the last read returned `Pending`.
+            read_waiting: bool,
+            // Whether the last write, flush or close returned `Pending`.
+            write_waiting: bool,
+            closed: bool,
+            #[pin]
+            stream: T,
+        }
+    }
+
+    impl<T: AsyncRead + AsyncWrite + ?Sized> DuplexPoller<T> {
+        fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize>> {
+            let this = self.project();
+            let poll = this.stream.poll_read(cx, buf);
+            *this.read_waiting = poll.is_pending();
+            poll
+        }
+
+        fn poll_write_with<R>(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            f: impl FnOnce(Pin<&mut T>, &mut Context<'_>) -> Poll<Result<R>>,
+        ) -> Poll<Result<R>> {
+            let this = self.project();
+            if *this.closed {
+                return Poll::Ready(Err(closed_error()));
+            }
+
+            let poll = f(this.stream, cx);
+            *this.write_waiting = poll.is_pending();
+            poll
+        }
+
+        fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            if self.closed {
+                return Poll::Ready(Ok(()));
+            }
+
+            let mut this = self;
+            let result = ready!(this.as_mut().poll_write_with(cx, |stream, cx| stream.poll_close(cx)));
+            if result.is_ok() {
+                *this.project().closed = true;
+            }
+
+            Poll::Ready(result)
+        }
+    }
+
     pin_project! {
         #[derive(Debug)]
         struct SeekPoller<S: ?Sized> {
@@ -512,14 +1864,76 @@ cfg_futures_io! {
     }
 }
 
-struct Notify(Notifier);
+/// Wakes up the poll loop, remembering any error that occurs.
+#[derive(Debug)]
+struct Notify {
+    /// The notifier for the ping event source.
+    notifier: Notifier,
+
+    /// The last error that occurred while notifying.
+    error: Mutex<Option<io::Error>>,
+
+    /// Whether we have been woken since the future was last polled.
+    woken: AtomicBool,
+}
+
+impl Notify {
+    fn take_error(&self) -> Option<io::Error> {
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
 
 impl Wake for Notify {
     fn wake(self: Arc<Self>) {
-        self.0.notify().expect("failed to notify");
+        self.wake_by_ref();
     }
 
     fn wake_by_ref(self: &Arc<Self>) {
-        self.0.notify().expect("failed to notify");
+        self.woken.store(true, Ordering::Release);
+
+        // Don't block or unwind into the executor; surface the error on the next poll instead.
+        // A full ping already has a wakeup pending.
+        if let Err(err) = self.notifier.try_notify() {
+            *self.error.lock().unwrap_or_else(|e| e.into_inner()) = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn wake_failure() {
+        use crate::ping::{PingBackend, PingBuilder};
+        use std::future::poll_fn;
+
+        // Writing to a pipe whose reader is gone fails.
+        let ping = PingBuilder::new()
+            .backend(PingBackend::Pipe)
+            .build()
+            .unwrap();
+        let notifier = ping.notifier();
+        drop(ping);
+
+        // A future that wakes itself every time it is polled.
+        let future = poll_fn(|cx| {
+            cx.waker().wake_by_ref();
+            Poll::<()>::Pending
+        });
+        let mut future = PollFuture {
+            inner: PollFutureWithArg::with_notifier(future, Ping::new().unwrap(), notifier),
+        };
+
+        // The wakeup fails without unwinding, and `poll` doesn't report it.
+        assert!(future.poll_unpin().is_pending());
+
+        // The next `try_poll` returns the error, and then polls as usual.
+        match future.try_poll_unpin() {
+            Poll::Ready(Err(err)) => assert_eq!(err.kind(), io::ErrorKind::BrokenPipe),
+            _ => panic!("the wake failure was not reported"),
+        }
+        assert!(future.try_poll_unpin().is_pending());
     }
 }
@@ -14,28 +14,53 @@
 //! [`polling`]: https://docs.rs/polling
 //! [`smol`]: https://docs.rs/smol
 
-#![cfg_attr(not(windows), forbid(unsafe_code))]
+#![forbid(unsafe_code)]
 
 use polling::Source as PSource;
 #[doc(inline)]
 pub use polling::{Event, PollMode, Poller};
 
-use std::io::Result;
-use std::sync::Arc;
+use std::collections::BTreeSet;
+use std::io::{self, Result};
+use std::ops;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::Poll;
+use std::time::Duration;
 
-#[cfg(feature = "channel")]
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{AsRawSocket, AsSocket, RawSocket};
+
+#[cfg(any(feature = "channel", feature = "ping-channel"))]
 pub mod channel;
+pub mod event;
 #[cfg(feature = "future")]
 pub mod future;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(all(feature = "ping", any(unix, polling_utils_socket_ping)))]
+pub mod os;
 #[cfg(feature = "ping")]
 pub mod ping;
+#[cfg(all(feature = "process", target_os = "linux"))]
+pub mod process;
+pub mod reactor;
+#[cfg(all(feature = "signal", any(target_os = "linux", target_os = "android")))]
+pub mod signal;
+pub mod source;
 #[cfg(feature = "threadpool")]
 pub mod threadpool;
 #[cfg(feature = "timer")]
 pub mod timer;
+#[cfg(all(feature = "watch", any(target_os = "linux", target_os = "android")))]
+pub mod watch;
 
 /// A source that can be registered into a [`Poller`].
 ///
+/// Registering takes the poller by [`Arc`] so that sources can keep a handle to it, which some
+/// backends need in order to post events. Deregistering and handling events only borrow it.
+///
 /// [`Poller`]: polling::Poller
 pub trait Source {
     /// Registers the source into the given [`Poller`].
@@ -49,23 +74,867 @@ pub trait Source {
     fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()>;
 
     /// Deregisters the source from the given [`Poller`].
-    fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()>;
+    fn deregister(&mut self, poller: &Poller) -> Result<()>;
 
     /// Handles an event that was received from the given [`Poller`].
-    fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()>;
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()>;
+
+    /// Handles an event, reporting whether the source made progress.
+    ///
+    /// Returns `false` if the event was spurious, for instance a [`Ping`](ping::Ping) woken up
+    /// without any notifications to drain, which lets reactors tell useful wakeups from
+    /// spurious ones. By default this calls [`Source::handle_event`] and returns `true`.
+    fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+        self.handle_event(poller, event)?;
+        Ok(true)
+    }
+
+    /// Handles a batch of events that were received from the given [`Poller`].
+    ///
+    /// By default this calls [`Source::handle_event`] for each event, stopping at the first
+    /// error. Sources can override this to handle coalesced events more efficiently.
+    fn handle_events(&mut self, poller: &Poller, events: &[Event]) -> Result<()> {
+        for event in events {
+            self.handle_event(poller, *event)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the interest and mode this source was last registered with, if it is registered.
+    ///
+    /// By default sources do not report their registration.
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        None
+    }
+
+    /// Re-registers the source with the interest and mode it was last registered with.
+    ///
+    /// This puts a oneshot source back into the poller without handling its event, which is
+    /// useful when a source decides that it should not consume an event. Returns an error if
+    /// the source does not report its [`registration`](Source::registration).
+    fn rearm(&mut self, poller: &Arc<Poller>) -> Result<()> {
+        let (interest, mode) = self.registration().ok_or_else(not_registered)?;
+        self.reregister(poller, interest, mode)
+    }
+
+    /// Get the interest and mode this source was configured to use by default, if any.
+    ///
+    /// By default sources have no default interest.
+    fn default_interest(&self) -> Option<(Event, PollMode)> {
+        None
+    }
+
+    /// Registers the source with its [default interest](Source::default_interest), or
+    /// re-registers it if it is already registered.
+    ///
+    /// This re-arms a oneshot source without passing the interest and mode again. Sources
+    /// without a default interest fall back to [`Source::rearm`].
+    fn rearm_default(&mut self, poller: &Arc<Poller>) -> Result<()> {
+        match self.default_interest() {
+            Some((interest, mode)) if self.registration().is_some() => {
+                self.reregister(poller, interest, mode)
+            }
+            Some((interest, mode)) => self.register(poller, interest, mode),
+            None => self.rearm(poller),
+        }
+    }
+
+    /// Handles a batch of events, carrying on past any errors.
+    ///
+    /// Unlike [`Source::handle_events`], every event is handled even if an earlier one fails.
+    /// Returns each event that failed along with its error.
+    fn try_handle_events(&mut self, poller: &Poller, events: &[Event]) -> Vec<(Event, io::Error)> {
+        events
+            .iter()
+            .filter_map(|&event| {
+                self.handle_event(poller, event)
+                    .err()
+                    .map(|err| (event, err))
+            })
+            .collect()
+    }
+
+    /// Get an event that is ready without waiting on the poller, if any.
+    ///
+    /// Sources that are notified without a file descriptor, like
+    /// [`LocalPing`](crate::ping::LocalPing), report their events here. When any source has an
+    /// event ready, [`dispatch`] and [`Reactor::poll`](reactor::Reactor::poll) only check the
+    /// poller without blocking, and then dispatch the ready events along with the poller's.
+    /// By default sources have no such events.
+    fn pending_event(&self) -> Option<Event> {
+        None
+    }
+
+    /// Whether this kind of source can be registered into the given [`Poller`] with `mode`.
+    ///
+    /// By default this checks whether the poller supports level- or edge-triggered events.
+    /// Sources with further restrictions override this.
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool
+    where
+        Self: Sized,
+    {
+        poller_supports_mode(poller, mode)
+    }
+}
+
+/// A [`Source`] that produces values, such as the messages of a channel.
+///
+/// Handling an event readies the source, and [`EventSource::poll_next`] then hands out the
+/// values it produced. Sources that only signal readiness keep implementing [`Source`] alone.
+pub trait EventSource: Source {
+    /// The value produced by the source.
+    type Item;
+
+    /// Poll for the next value.
+    ///
+    /// `Poll::Pending` means there are no values until the source gets another event, and
+    /// `Poll::Ready(None)` means the source is finished and can be deregistered.
+    fn poll_next(&mut self) -> Poll<Option<Self::Item>>;
+}
+
+/// A type-erased [`Source`], useful for storing different kinds of sources together.
+pub type BoxSource = Box<dyn Source + Send>;
+
+/// Forward every method of [`Source`] that takes `self` to the source behind a pointer.
+macro_rules! forward_source {
+    () => {
+        fn registration(&self) -> Option<(Event, PollMode)> {
+            (**self).registration()
+        }
+
+        fn pending_event(&self) -> Option<Event> {
+            (**self).pending_event()
+        }
+
+        fn rearm(&mut self, poller: &Arc<Poller>) -> Result<()> {
+            (**self).rearm(poller)
+        }
+
+        fn default_interest(&self) -> Option<(Event, PollMode)> {
+            (**self).default_interest()
+        }
+
+        fn rearm_default(&mut self, poller: &Arc<Poller>) -> Result<()> {
+            (**self).rearm_default(poller)
+        }
+
+        fn try_handle_events(
+            &mut self,
+            poller: &Poller,
+            events: &[Event],
+        ) -> Vec<(Event, io::Error)> {
+            (**self).try_handle_events(poller, events)
+        }
+
+        fn register(
+            &mut self,
+            poller: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> Result<()> {
+            (**self).register(poller, interest, mode)
+        }
+
+        fn reregister(
+            &mut self,
+            poller: &Arc<Poller>,
+            interest: Event,
+            mode: PollMode,
+        ) -> Result<()> {
+            (**self).reregister(poller, interest, mode)
+        }
+
+        fn deregister(&mut self, poller: &Poller) -> Result<()> {
+            (**self).deregister(poller)
+        }
+
+        fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+            (**self).handle_event(poller, event)
+        }
+
+        fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+            (**self).handle_event_progress(poller, event)
+        }
+
+        fn handle_events(&mut self, poller: &Poller, events: &[Event]) -> Result<()> {
+            (**self).handle_events(poller, events)
+        }
+    };
+}
+
+impl<S: Source> Source for Box<S> {
+    forward_source!();
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        S::supports_mode(poller, mode)
+    }
+}
+
+impl<S: Source> Source for &mut S {
+    forward_source!();
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        S::supports_mode(poller, mode)
+    }
+}
+
+/// Implement [`Source`] for pointers to trait objects.
+///
+/// The type of the source is erased, so these keep the default [`Source::supports_mode`].
+macro_rules! impl_source_for_dyn {
+    ($(impl[$($gen:tt)*] Source for $ty:ty;)*) => {
+        $(
+            impl<$($gen)*> Source for $ty {
+                forward_source!();
+            }
+        )*
+    };
+}
+
+impl_source_for_dyn! {
+    impl['a] Source for Box<dyn Source + 'a>;
+    impl['a] Source for Box<dyn Source + Send + 'a>;
+    impl['a] Source for Box<dyn Source + Send + Sync + 'a>;
+    impl['a, T] Source for Box<dyn EventSource<Item = T> + 'a>;
+    impl['a, T] Source for Box<dyn EventSource<Item = T> + Send + 'a>;
+    impl['a, 'b] Source for &'a mut (dyn Source + 'b);
+    impl['a, 'b] Source for &'a mut (dyn Source + Send + 'b);
+    impl['a, 'b] Source for &'a mut (dyn Source + Send + Sync + 'b);
+    impl['a, 'b, T] Source for &'a mut (dyn EventSource<Item = T> + 'b);
+    impl['a, 'b, T] Source for &'a mut (dyn EventSource<Item = T> + Send + 'b);
+}
+
+impl<S: EventSource + ?Sized> EventSource for Box<S>
+where
+    Self: Source,
+{
+    type Item = S::Item;
+
+    fn poll_next(&mut self) -> Poll<Option<Self::Item>> {
+        (**self).poll_next()
+    }
+}
+
+impl<S: EventSource + ?Sized> EventSource for &mut S
+where
+    Self: Source,
+{
+    type Item = S::Item;
+
+    fn poll_next(&mut self) -> Poll<Option<Self::Item>> {
+        (**self).poll_next()
+    }
+}
+
+/// Wait for events and dispatch each one to the source at the index given by its key.
+///
+/// This is a lightweight alternative to a [`Reactor`](reactor::Reactor) for simple loops,
+/// where `sources[i]` is registered with the key `i`. `events` is cleared before waiting.
+/// Events whose key is out of bounds are ignored. Events with a [secondary key](secondary_key)
+/// go to the source at the index of their primary key. If a source has a
+/// [pending event](Source::pending_event), the poller is checked without blocking.
+///
+/// Returns the number of events that were dispatched. Every event is dispatched even if a
+/// source fails to handle its event, and the first error is returned once all events have
+/// been dispatched.
+pub fn dispatch(
+    poller: &Arc<Poller>,
+    events: &mut Vec<Event>,
+    sources: &mut [&mut dyn Source],
+    timeout: Option<Duration>,
+) -> Result<usize> {
+    events.clear();
+    let ready = sources
+        .iter()
+        .filter_map(|source| source.pending_event())
+        .collect::<Vec<_>>();
+    let timeout = if ready.is_empty() {
+        timeout
+    } else {
+        Some(Duration::ZERO)
+    };
+    poller.wait(events, timeout)?;
+    events.extend(ready);
+
+    let mut dispatched = 0;
+    let mut result = Ok(());
+    for &event in events.iter() {
+        if let Some(source) = sources.get_mut(primary_key(event.key)) {
+            if let Err(err) = source.handle_event(poller, event) {
+                result = result.and(Err(err));
+            }
+            dispatched += 1;
+        }
+    }
+
+    result.map(|()| dispatched)
+}
+
+/// A [`dispatch`] loop that keeps its event buffer between calls.
+///
+/// The buffer grows to the number of events the loop sees at once and is then reused, so a
+/// hot loop does not allocate on every iteration.
+#[derive(Debug)]
+pub struct Dispatcher {
+    /// The poller to wait on.
+    poller: Arc<Poller>,
+
+    /// The buffer events are received into.
+    events: Vec<Event>,
+}
+
+impl Dispatcher {
+    /// Creates a dispatcher for the given [`Poller`], with room for `capacity` events.
+    pub fn new(poller: &Arc<Poller>, capacity: usize) -> Self {
+        Self {
+            poller: poller.clone(),
+            events: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Get the poller this dispatcher waits on.
+    pub fn poller(&self) -> &Arc<Poller> {
+        &self.poller
+    }
+
+    /// Get the events from the last call to [`Dispatcher::poll_and_dispatch`].
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Get the number of events the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Wait for events and dispatch each one to the source at the index given by its key.
+    ///
+    /// This behaves like [`dispatch`], reusing the dispatcher's buffer.
+    pub fn poll_and_dispatch(
+        &mut self,
+        sources: &mut [&mut dyn Source],
+        timeout: Option<Duration>,
+    ) -> Result<usize> {
+        dispatch(&self.poller, &mut self.events, sources, timeout)
+    }
+}
+
+/// A [`Source`] that registers nothing and never fires.
+///
+/// This can stand in for a source that has been compiled out, so that the code wiring sources
+/// into a reactor stays the same either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NoopSource;
+
+impl Source for NoopSource {
+    fn register(&mut self, _poller: &Arc<Poller>, _interest: Event, _mode: PollMode) -> Result<()> {
+        Ok(())
+    }
+
+    fn reregister(
+        &mut self,
+        _poller: &Arc<Poller>,
+        _interest: Event,
+        _mode: PollMode,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn deregister(&mut self, _poller: &Poller) -> Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, _poller: &Poller, _event: Event) -> Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_source_for_tuple {
+    ($($name:ident $idx:tt),*) => {
+        /// Registers every element with the same interest.
+        ///
+        /// If registering an element fails, the elements registered before it are deregistered.
+        ///
+        /// Since the elements share a key, an event can't tell which of them is ready, so it is
+        /// dispatched to every element. Each element must treat an event it has nothing for as
+        /// spurious, the way [`Ping`](crate::ping::Ping) drains nothing when it wasn't notified.
+        /// Sources that need to know which element fired should register the elements under
+        /// keys of their own instead.
+        impl<$($name: Source),*> Source for ($($name,)*) {
+            fn register(
+                &mut self,
+                poller: &Arc<Poller>,
+                interest: Event,
+                mode: PollMode,
+            ) -> Result<()> {
+                let mut registered = 0usize;
+                let mut register = || -> Result<()> {
+                    $(
+                        self.$idx.register(poller, interest, mode)?;
+                        registered += 1;
+                    )*
+                    Ok(())
+                };
+
+                let result = register();
+                if result.is_err() {
+                    // Roll back the elements that were registered.
+                    $(
+                        if $idx < registered {
+                            let _ = self.$idx.deregister(poller);
+                        }
+                    )*
+                }
+
+                result
+            }
+
+            fn reregister(
+                &mut self,
+                poller: &Arc<Poller>,
+                interest: Event,
+                mode: PollMode,
+            ) -> Result<()> {
+                $(self.$idx.reregister(poller, interest, mode)?;)*
+                Ok(())
+            }
+
+            fn pending_event(&self) -> Option<Event> {
+                None$(.or_else(|| self.$idx.pending_event()))*
+            }
+
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
+                let mut result = Ok(());
+                $(
+                    if let Err(err) = self.$idx.deregister(poller) {
+                        result = result.and(Err(err));
+                    }
+                )*
+                result
+            }
+
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+                let mut result = Ok(());
+                $(
+                    if let Err(err) = self.$idx.handle_event(poller, event) {
+                        result = result.and(Err(err));
+                    }
+                )*
+                result
+            }
+
+            fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+                let mut result = Ok(false);
+                $(
+                    match self.$idx.handle_event_progress(poller, event) {
+                        Ok(progress) => result = result.map(|made| made || progress),
+                        Err(err) => result = result.and(Err(err)),
+                    }
+                )*
+                result
+            }
+
+            fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+                $($name::supports_mode(poller, mode))&&*
+            }
+        }
+    };
+}
+
+impl_source_for_tuple!(A 0, B 1);
+impl_source_for_tuple!(A 0, B 1, C 2);
+impl_source_for_tuple!(A 0, B 1, C 2, D 3);
+impl_source_for_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_source_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+
+/// A [`Source`] that is deregistered from its [`Poller`] when it is dropped.
+///
+/// This derefs to the underlying source, so it can still be re-registered or handle events
+/// like any other source.
+#[derive(Debug)]
+pub struct Registered<S: Source> {
+    /// The registered source.
+    source: S,
+
+    /// The poller the source is registered into.
+    poller: Arc<Poller>,
+}
+
+impl<S: Source> Registered<S> {
+    /// Registers the source into the given [`Poller`].
+    pub fn new(
+        mut source: S,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<Self> {
+        source.register(poller, interest, mode)?;
+        Ok(Self {
+            source,
+            poller: poller.clone(),
+        })
+    }
+
+    /// Get the poller the source is registered into.
+    pub fn poller(&self) -> &Arc<Poller> {
+        &self.poller
+    }
+}
+
+impl<S: Source> ops::Deref for Registered<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S: Source> ops::DerefMut for Registered<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
+
+impl<S: Source> Drop for Registered<S> {
+    fn drop(&mut self) {
+        // There is nobody to report the error to.
+        let _ = self.source.deregister(&self.poller);
+    }
+}
+
+/// A buffer of events returned by a [`Poller`].
+///
+/// This derefs to the underlying `Vec<Event>`, so it can be passed straight to
+/// [`Poller::wait`].
+#[derive(Debug, Default, Clone)]
+pub struct Events(Vec<Event>);
+
+impl Events {
+    /// Creates an empty event buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty event buffer with room for `capacity` events.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    /// Get the first event with the given key, if any.
+    pub fn by_key(&self, key: usize) -> Option<Event> {
+        self.0.iter().find(|event| event.key == key).copied()
+    }
+
+    /// Iterate over the keys of the readable events.
+    pub fn readable_keys(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .filter(|event| event.readable)
+            .map(|event| event.key)
+    }
+
+    /// Iterate over the keys of the writable events.
+    pub fn writable_keys(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0
+            .iter()
+            .filter(|event| event.writable)
+            .map(|event| event.key)
+    }
+
+    /// Remove all events from the buffer.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Convert the buffer into the underlying `Vec<Event>`.
+    pub fn into_inner(self) -> Vec<Event> {
+        self.0
+    }
+}
+
+impl From<Vec<Event>> for Events {
+    fn from(events: Vec<Event>) -> Self {
+        Self(events)
+    }
+}
+
+impl ops::Deref for Events {
+    type Target = Vec<Event>;
+
+    fn deref(&self) -> &Vec<Event> {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for Events {
+    fn deref_mut(&mut self) -> &mut Vec<Event> {
+        &mut self.0
+    }
+}
+
+/// The bit that is set in the keys of the second part of a composite source.
+const SECONDARY_KEY_BIT: usize = 1 << (usize::BITS - 1);
+
+/// Get the key that the second part of a composite source is registered with.
+///
+/// Sources made of two parts, like `future::Select`, register their first part with the key
+/// they were given and their second part with this key, so that each event tells which part
+/// it is for. [`dispatch`] and [`Reactor`](reactor::Reactor) route events with either key to
+/// the source, so keys handed to sources must not have the top bit set.
+pub fn secondary_key(key: usize) -> usize {
+    key | SECONDARY_KEY_BIT
+}
+
+/// Get the key of the source an event is for, undoing [`secondary_key`].
+pub fn primary_key(key: usize) -> usize {
+    key & !SECONDARY_KEY_BIT
+}
+
+/// Whether the key is one returned by [`secondary_key`].
+pub fn is_secondary_key(key: usize) -> bool {
+    key & SECONDARY_KEY_BIT != 0
+}
+
+/// Get the interest that the second part of a composite source is registered with.
+#[cfg(feature = "future")]
+pub(crate) fn secondary_interest(mut interest: Event) -> Event {
+    interest.key = secondary_key(interest.key);
+    interest
+}
+
+/// Hands out unique event keys, recycling keys that have been freed.
+#[derive(Debug, Clone)]
+pub struct KeyAllocator {
+    /// The lowest key that may be handed out.
+    base: usize,
+
+    /// The next key that has never been handed out, or `None` if every key has been.
+    next: Option<usize>,
+
+    /// Keys that have been freed and can be handed out again.
+    free: BTreeSet<usize>,
+}
+
+impl Default for KeyAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyAllocator {
+    /// Creates a new key allocator starting at zero.
+    pub fn new() -> Self {
+        Self::with_base(0)
+    }
+
+    /// Creates a new key allocator that never hands out keys below `base`.
+    ///
+    /// This is useful for reserving keys like `0` as sentinels.
+    pub fn with_base(base: usize) -> Self {
+        Self {
+            base,
+            next: Some(base),
+            free: BTreeSet::new(),
+        }
+    }
+
+    /// Allocate a key that is not currently in use.
+    ///
+    /// Freed keys are handed out again, lowest first. Returns an error if every key is in use.
+    pub fn alloc(&mut self) -> Result<usize> {
+        if let Some(&key) = self.free.iter().next() {
+            self.free.remove(&key);
+            return Ok(key);
+        }
+
+        let key = self
+            .next
+            .ok_or_else(|| io::Error::new(io::ErrorKind::OutOfMemory, "ran out of event keys"))?;
+        self.next = key.checked_add(1);
+        Ok(key)
+    }
+
+    /// Free a key so that it can be allocated again.
+    ///
+    /// Returns an error if the key is not currently allocated, because it is below the base,
+    /// was never handed out, or was already freed.
+    pub fn free(&mut self, key: usize) -> Result<()> {
+        let allocated = key >= self.base
+            && match self.next {
+                Some(next) => key < next,
+                None => true,
+            }
+            && !self.free.contains(&key);
+        if !allocated {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the key is not allocated",
+            ));
+        }
+
+        self.free.insert(key);
+        Ok(())
+    }
+}
+
+/// Splits bytes read from a descriptor into complete records.
+///
+/// Descriptors like inotify and signalfd return packed records, and a read may end partway
+/// through one. Bytes that do not form a complete record yet are kept until the rest of the
+/// record is added.
+#[derive(Debug, Default, Clone)]
+pub struct RecordReader {
+    /// The bytes that have been added.
+    buffer: Vec<u8>,
+
+    /// The start of the first record that has not been returned.
+    start: usize,
+}
+
+impl RecordReader {
+    /// Creates a new, empty record reader.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add bytes to the end of the buffer.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        // Drop the records that have already been returned.
+        self.buffer.drain(..self.start);
+        self.start = 0;
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Get the next complete record, if any.
+    ///
+    /// `record_len` is given the buffered bytes starting at the next record, and returns the
+    /// length of that record, or `None` if not enough bytes are buffered to tell. If the record
+    /// is incomplete, it is kept and `None` is returned.
+    pub fn next_record(
+        &mut self,
+        record_len: impl FnOnce(&[u8]) -> Option<usize>,
+    ) -> Option<&[u8]> {
+        let rest = &self.buffer[self.start..];
+        let len = record_len(rest)?;
+        if len == 0 || len > rest.len() {
+            return None;
+        }
+
+        let record = self.start..self.start + len;
+        self.start += len;
+        Some(&self.buffer[record])
+    }
+
+    /// Get the bytes that have not been returned as part of a record.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
 }
 
 /// The typical socket source registed into the [`Poller`].
+///
+/// Any type `T` where `&T` is a [`polling::Source`] can be registered, which is any type that
+/// implements [`AsRawFd`] on Unix or [`AsRawSocket`] on Windows. This includes owned
+/// descriptors such as `OwnedFd`, which is also the type `rustix` uses, and shared sockets such
+/// as `Arc<UdpSocket>`. Types that only lend out their descriptor through `AsFd` or `AsSocket`
+/// can be registered by wrapping them in [`Borrowed`].
+///
+/// A socket can be registered into several pollers at once, with a different interest in
+/// each, and deregistered from each of them independently. Registering a socket into a poller
+/// it is already registered into returns an [`AlreadyExists`] error; use
+/// [`Source::reregister`] to change its interest instead.
+///
+/// [`AlreadyExists`]: std::io::ErrorKind::AlreadyExists
+/// [`AsRawFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsRawFd.html
+/// [`AsRawSocket`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsRawSocket.html
 #[derive(Debug)]
 pub struct Socket<T> {
     /// The underlying socket.
     socket: T,
+
+    /// The pollers the socket is registered into, with the most recent registration last.
+    registrations: Vec<Registration>,
+}
+
+/// A poller a socket is registered into.
+#[derive(Debug)]
+struct Registration {
+    /// The poller the socket is registered into.
+    poller: Weak<Poller>,
+
+    /// The interest the socket is registered with.
+    interest: Interest,
+}
+
+/// The interest a source was registered with.
+#[derive(Debug, Clone, Copy)]
+struct Interest {
+    /// The event we are interested in.
+    event: Event,
+
+    /// The polling mode.
+    mode: PollMode,
 }
 
 impl<T> Socket<T> {
     /// Creates a new socket source.
     pub fn new(socket: T) -> Self {
-        Self { socket }
+        Self {
+            socket,
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Get the event this socket is currently registered with, if any.
+    ///
+    /// If the socket is registered into several pollers, this is the most recent registration.
+    pub fn interest(&self) -> Option<Event> {
+        self.last_interest().map(|interest| interest.event)
+    }
+
+    /// Get the polling mode this socket is currently registered with, if any.
+    ///
+    /// If the socket is registered into several pollers, this is the most recent registration.
+    pub fn mode(&self) -> Option<PollMode> {
+        self.last_interest().map(|interest| interest.mode)
+    }
+
+    /// Get the key this socket is currently registered with, if any.
+    ///
+    /// If the socket is registered into several pollers, this is the most recent registration.
+    pub fn key(&self) -> Option<usize> {
+        self.last_interest().map(|interest| interest.event.key)
+    }
+
+    /// Get the interest and mode this socket is registered with in the given poller, if any.
+    pub fn registration_in(&self, poller: &Poller) -> Option<(Event, PollMode)> {
+        self.position(poller).map(|index| {
+            let interest = self.registrations[index].interest;
+            (interest.event, interest.mode)
+        })
+    }
+
+    /// Whether the event could have been delivered for this socket's registration.
+    ///
+    /// Events are only rejected if the socket is registered, but not under the event's key.
+    pub fn is_for(&self, event: &Event) -> bool {
+        self.registrations.is_empty()
+            || self
+                .registrations
+                .iter()
+                .any(|registration| registration.interest.event.key == event.key)
+    }
+
+    /// Whether the event could have been delivered by `poller` for this socket's registration.
+    ///
+    /// This is like [`Socket::is_for`], but an event is also rejected if the socket is only
+    /// registered in other pollers.
+    pub fn is_for_in(&self, poller: &Poller, event: &Event) -> bool {
+        match self.registration_in(poller) {
+            Some((interest, _)) => interest.key == event.key,
+            None => self.registrations.is_empty(),
+        }
     }
 
     /// Get a reference to the underlying socket.
@@ -82,25 +951,628 @@ impl<T> Socket<T> {
     pub fn into_socket(self) -> T {
         self.socket
     }
+
+    /// Get the interest of the most recent registration.
+    fn last_interest(&self) -> Option<Interest> {
+        self.registrations
+            .last()
+            .map(|registration| registration.interest)
+    }
+
+    /// Get the index of the registration for the given poller.
+    fn position(&self, poller: &Poller) -> Option<usize> {
+        self.registrations
+            .iter()
+            .position(|registration| std::ptr::eq(registration.poller.as_ptr(), poller))
+    }
+
+    /// Forget the registrations whose poller has been dropped, which took them along.
+    ///
+    /// Returns whether any registration was forgotten.
+    fn prune(&mut self) -> bool {
+        let len = self.registrations.len();
+        self.registrations
+            .retain(|registration| registration.poller.strong_count() > 0);
+        self.registrations.len() != len
+    }
+}
+
+impl<T> Socket<T>
+where
+    for<'a> &'a T: PSource,
+{
+    /// Creates a new socket source and registers it into the given [`Poller`].
+    pub fn with_interest(
+        socket: T,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<Self> {
+        let mut socket = Self::new(socket);
+        socket.register(poller, interest, mode)?;
+        Ok(socket)
+    }
+
+    /// Wraps a socket that was already registered into the given [`Poller`].
+    ///
+    /// This is useful when the socket was added to the poller directly. The socket is not
+    /// added again, but it can be reregistered and deregistered as if it was registered
+    /// through [`Source::register`].
+    pub fn from_registered(
+        socket: T,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Self {
+        Self {
+            socket,
+            registrations: vec![Registration {
+                poller: Arc::downgrade(poller),
+                interest: Interest {
+                    event: interest,
+                    mode,
+                },
+            }],
+        }
+    }
+
+    /// Set whether this socket is interested in readability.
+    ///
+    /// The socket keeps its current key and polling mode. Returns an error if the socket is
+    /// not registered.
+    pub fn set_readable(&mut self, poller: &Arc<Poller>, readable: bool) -> Result<()> {
+        self.modify_interest(poller, |event| event.readable = readable)
+    }
+
+    /// Set whether this socket is interested in writability.
+    ///
+    /// The socket keeps its current key and polling mode. Returns an error if the socket is
+    /// not registered.
+    pub fn set_writable(&mut self, poller: &Arc<Poller>, writable: bool) -> Result<()> {
+        self.modify_interest(poller, |event| event.writable = writable)
+    }
+
+    fn modify_interest(&mut self, poller: &Arc<Poller>, f: impl FnOnce(&mut Event)) -> Result<()> {
+        let (mut event, mode) = self.registration_in(poller).ok_or_else(not_registered)?;
+        f(&mut event);
+        self.reregister(poller, event, mode)
+    }
+}
+
+impl<T> Socket<T>
+where
+    for<'a> &'a T: PSource,
+{
+    /// Split the socket into halves that are registered for reading and writing separately.
+    ///
+    /// A descriptor can only be registered once per poller, so the halves share a single
+    /// registration whose interest is the union of both halves' interests. Each half keeps its
+    /// own key, and both halves have to be registered into the same poller. Returns an error
+    /// if the socket is currently registered.
+    ///
+    /// The poller reports the events of the shared registration under the key of the read
+    /// half while it is registered, and under the key of the write half otherwise. Handing
+    /// such an event to that half passes the other direction's readiness on to the other half,
+    /// which reports it as its [pending event](Source::pending_event). [`dispatch`] and
+    /// [`Reactor`](reactor::Reactor) deliver those events without any extra work.
+    pub fn split(self) -> Result<(ReadHalf<T>, WriteHalf<T>)> {
+        if !self.registrations.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot split a registered source",
+            ));
+        }
+
+        let shared = Arc::new(Split {
+            socket: self.socket,
+            state: Mutex::new(SplitState {
+                registration: None,
+                read: None,
+                write: None,
+            }),
+        });
+
+        Ok((
+            ReadHalf {
+                shared: shared.clone(),
+            },
+            WriteHalf { shared },
+        ))
+    }
 }
 
 impl<T> Source for Socket<T>
 where
     for<'a> &'a T: PSource,
 {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.last_interest()
+            .map(|interest| (interest.event, interest.mode))
+    }
+
     fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
-        poller.add_with_mode(&self.socket, interest, mode)
+        // Registering into another poller is fine, since each poller has its own set.
+        self.prune();
+        if self.position(poller).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the socket is already registered",
+            ));
+        }
+
+        poller.add_with_mode(&self.socket, interest, mode)?;
+        self.registrations.push(Registration {
+            poller: Arc::downgrade(poller),
+            interest: Interest {
+                event: interest,
+                mode,
+            },
+        });
+        Ok(())
     }
 
     fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
-        poller.modify_with_mode(&self.socket, interest, mode)
+        let dropped = self.prune();
+        let index = match self.position(poller) {
+            Some(index) => index,
+            None if dropped => return Err(poller_dropped()),
+            None => return Err(not_registered()),
+        };
+
+        // Not every backend can change the mode of a registration in place. If it can't, a new
+        // mode is applied by registering the socket again.
+        if let Err(err) = poller.modify_with_mode(&self.socket, interest, mode) {
+            if self.registrations[index].interest.mode == mode {
+                return Err(err);
+            }
+
+            poller.delete(&self.socket)?;
+            if let Err(err) = poller.add_with_mode(&self.socket, interest, mode) {
+                // The socket is no longer in the poller.
+                self.registrations.remove(index);
+                return Err(err);
+            }
+        }
+
+        // This is now the most recent registration.
+        let mut registration = self.registrations.remove(index);
+        registration.interest = Interest {
+            event: interest,
+            mode,
+        };
+        self.registrations.push(registration);
+        Ok(())
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        // Deregistering a socket that was never registered does nothing. Neither does
+        // deregistering it after its poller was dropped, which took the registration with it.
+        self.prune();
+        let index = match self.position(poller) {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+
+        poller.delete(&self.socket)?;
+        self.registrations.remove(index);
+        Ok(())
+    }
+
+    fn handle_event(&mut self, _poller: &Poller, _event: Event) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A socket that is registered through the descriptor it lends out.
+///
+/// [`Socket`] registers types that implement [`AsRawFd`] on Unix or [`AsRawSocket`] on
+/// Windows. Wrapping a type that only implements [`AsFd`] or [`AsSocket`] lets it be
+/// registered through its borrowed descriptor instead. For instance, `Arc<TcpStream>` does not
+/// implement `AsRawSocket`, but `Socket<Borrowed<Arc<TcpStream>>>` can be registered.
+///
+/// [`AsFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsFd.html
+/// [`AsRawFd`]: https://doc.rust-lang.org/std/os/fd/trait.AsRawFd.html
+/// [`AsSocket`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsSocket.html
+/// [`AsRawSocket`]: https://doc.rust-lang.org/std/os/windows/io/trait.AsRawSocket.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Borrowed<T>(pub T);
+
+impl<T> Borrowed<T> {
+    /// Unwrap the socket.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Borrowed<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Borrowed<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+#[cfg(unix)]
+impl<T: AsFd> AsRawFd for Borrowed<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_fd().as_raw_fd()
+    }
+}
+
+#[cfg(windows)]
+impl<T: AsSocket> AsRawSocket for Borrowed<T> {
+    fn as_raw_socket(&self) -> RawSocket {
+        self.0.as_socket().as_raw_socket()
+    }
+}
+
+/// The half of a split [`Socket`] that waits for the socket to become readable.
+#[derive(Debug)]
+pub struct ReadHalf<T> {
+    /// The socket and the interests of both halves.
+    shared: Arc<Split<T>>,
+}
+
+/// The half of a split [`Socket`] that waits for the socket to become writable.
+#[derive(Debug)]
+pub struct WriteHalf<T> {
+    /// The socket and the interests of both halves.
+    shared: Arc<Split<T>>,
+}
+
+/// The socket shared by the halves of a split [`Socket`].
+#[derive(Debug)]
+struct Split<T> {
+    /// The underlying socket.
+    socket: T,
+
+    /// The interests of both halves.
+    state: Mutex<SplitState>,
+}
+
+/// The interests of the halves of a split [`Socket`].
+#[derive(Debug)]
+struct SplitState {
+    /// The registration of the socket, with the union of both halves' interests.
+    registration: Option<Registration>,
+
+    /// The registration of the read half.
+    read: Option<Half>,
+
+    /// The registration of the write half.
+    write: Option<Half>,
+}
+
+/// The registration of one half of a split [`Socket`].
+#[derive(Debug)]
+struct Half {
+    /// The event this half is registered with, with only its own direction set.
+    event: Event,
+
+    /// The polling mode.
+    mode: PollMode,
+
+    /// Whether the direction is part of the socket's registration.
+    ///
+    /// A oneshot registration is disarmed once its event arrives.
+    armed: bool,
+
+    /// An event that arrived under the other half's key and has to be handed to this half.
+    ready: Option<Event>,
+}
+
+impl SplitState {
+    /// Get the registration of the half for the given direction.
+    fn half(&mut self, readable: bool) -> &mut Option<Half> {
+        if readable {
+            &mut self.read
+        } else {
+            &mut self.write
+        }
+    }
+
+    /// Get the union of both halves' interests, under the key of the read half if it is
+    /// registered.
+    fn interest(&self) -> Result<Option<Interest>> {
+        let (first, second) = match (&self.read, &self.write) {
+            (None, None) => return Ok(None),
+            (Some(half), None) | (None, Some(half)) => (half, half),
+            (Some(read), Some(write)) => (read, write),
+        };
+        if first.mode != second.mode {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "both halves of a split socket have to use the same polling mode",
+            ));
+        }
+
+        let mut event = Event::none(first.event.key);
+        event.readable = matches!(&self.read, Some(half) if half.armed);
+        event.writable = matches!(&self.write, Some(half) if half.armed);
+        Ok(Some(Interest {
+            event,
+            mode: first.mode,
+        }))
+    }
+
+    /// Set the registration of one half, and update the socket's registration to match.
+    ///
+    /// `owned` is only needed if the socket isn't registered into `poller` yet.
+    fn set(
+        &mut self,
+        socket: impl PSource + Copy,
+        poller: &Poller,
+        owned: Option<&Arc<Poller>>,
+        readable: bool,
+        half: Option<Half>,
+    ) -> Result<()> {
+        // The registration went away along with its poller.
+        if matches!(&self.registration, Some(registration) if registration.poller.strong_count() == 0)
+        {
+            self.registration = None;
+        }
+
+        let old = std::mem::replace(self.half(readable), half);
+        let result = self.update(socket, poller, owned);
+        if result.is_err() {
+            *self.half(readable) = old;
+        }
+        result
     }
 
-    fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
-        poller.delete(&self.socket)
+    /// Register the socket with the union of both halves' interests.
+    fn update(
+        &mut self,
+        socket: impl PSource + Copy,
+        poller: &Poller,
+        owned: Option<&Arc<Poller>>,
+    ) -> Result<()> {
+        let interest = self.interest()?;
+        let (registration, interest) = match (&mut self.registration, interest) {
+            (Some(registration), _) if !std::ptr::eq(registration.poller.as_ptr(), poller) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "both halves of a split socket have to be registered into the same poller",
+                ));
+            }
+            (Some(_), None) => {
+                poller.delete(socket)?;
+                self.registration = None;
+                return Ok(());
+            }
+            (None, None) => return Ok(()),
+            (Some(registration), Some(interest)) => (registration, interest),
+            (None, Some(interest)) => {
+                let owned = owned.ok_or_else(not_registered)?;
+                poller.add_with_mode(socket, interest.event, interest.mode)?;
+                self.registration = Some(Registration {
+                    poller: Arc::downgrade(owned),
+                    interest,
+                });
+                return Ok(());
+            }
+        };
+
+        // Not every backend can change the mode of a registration in place, like for
+        // `Socket::reregister`.
+        if let Err(err) = poller.modify_with_mode(socket, interest.event, interest.mode) {
+            if registration.interest.mode == interest.mode {
+                return Err(err);
+            }
+
+            poller.delete(socket)?;
+            if let Err(err) = poller.add_with_mode(socket, interest.event, interest.mode) {
+                self.registration = None;
+                return Err(err);
+            }
+        }
+        registration.interest = interest;
+        Ok(())
     }
 
-    fn handle_event(&mut self, _poller: &Arc<Poller>, _event: Event) -> Result<()> {
+    /// Handle an event that was delivered to the half for the given direction.
+    fn handle_event(
+        &mut self,
+        socket: impl PSource + Copy,
+        poller: &Poller,
+        readable: bool,
+        event: Event,
+    ) -> Result<()> {
+        // An event handed over from the other half.
+        if let Some(half) = self.half(readable) {
+            if matches!(half.ready, Some(ready) if ready.key == event.key) {
+                half.ready = None;
+                return Ok(());
+            }
+        }
+
+        // Otherwise, only events for the socket's registration are ours.
+        let mode = match &self.registration {
+            Some(registration)
+                if std::ptr::eq(registration.poller.as_ptr(), poller)
+                    && registration.interest.event.key == event.key =>
+            {
+                registration.interest.mode
+            }
+            _ => return Ok(()),
+        };
+        let oneshot = matches!(mode, PollMode::Oneshot | PollMode::EdgeOneshot);
+
+        for (half, fired) in [
+            (&mut self.read, event.readable),
+            (&mut self.write, event.writable),
+        ] {
+            let half = match half {
+                Some(half) if half.armed && fired => half,
+                _ => continue,
+            };
+
+            if half.event.key != event.key {
+                half.ready = Some(half.event);
+            }
+            if oneshot {
+                half.armed = false;
+            }
+        }
+
+        // The event disarmed the whole registration, so re-arm the half that is still waiting.
+        if oneshot {
+            if let Some(interest) = self.interest()? {
+                if interest.event.readable || interest.event.writable {
+                    poller.modify_with_mode(socket, interest.event, interest.mode)?;
+                }
+                if let Some(registration) = &mut self.registration {
+                    registration.interest = interest;
+                }
+            }
+        }
+
         Ok(())
     }
 }
+
+macro_rules! impl_half {
+    ($ty:ident, $readable:expr) => {
+        impl<T> $ty<T> {
+            /// Get a reference to the underlying socket.
+            pub fn socket(&self) -> &T {
+                &self.shared.socket
+            }
+
+            /// Get the event this half is currently registered with, if any.
+            pub fn interest(&self) -> Option<Event> {
+                self.registration().map(|(event, _)| event)
+            }
+
+            /// Get the event and mode this half is currently registered with, if any.
+            fn registration(&self) -> Option<(Event, PollMode)> {
+                lock(&self.shared.state)
+                    .half($readable)
+                    .as_ref()
+                    .map(|half| (half.event, half.mode))
+            }
+        }
+
+        impl<T> Source for $ty<T>
+        where
+            for<'a> &'a T: PSource,
+        {
+            fn registration(&self) -> Option<(Event, PollMode)> {
+                $ty::registration(self)
+            }
+
+            fn pending_event(&self) -> Option<Event> {
+                lock(&self.shared.state)
+                    .half($readable)
+                    .as_ref()
+                    .and_then(|half| half.ready)
+            }
+
+            fn register(
+                &mut self,
+                poller: &Arc<Poller>,
+                interest: Event,
+                mode: PollMode,
+            ) -> Result<()> {
+                let mut state = lock(&self.shared.state);
+                if state.half($readable).is_some() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        "the socket is already registered",
+                    ));
+                }
+
+                let half = Half::new(interest, mode, $readable);
+                state.set(
+                    &self.shared.socket,
+                    poller,
+                    Some(poller),
+                    $readable,
+                    Some(half),
+                )
+            }
+
+            fn reregister(
+                &mut self,
+                poller: &Arc<Poller>,
+                interest: Event,
+                mode: PollMode,
+            ) -> Result<()> {
+                let mut state = lock(&self.shared.state);
+                if state.half($readable).is_none() {
+                    return Err(not_registered());
+                }
+
+                let half = Half::new(interest, mode, $readable);
+                state.set(
+                    &self.shared.socket,
+                    poller,
+                    Some(poller),
+                    $readable,
+                    Some(half),
+                )
+            }
+
+            fn deregister(&mut self, poller: &Poller) -> Result<()> {
+                let mut state = lock(&self.shared.state);
+                if state.half($readable).is_none() {
+                    return Ok(());
+                }
+
+                state.set(&self.shared.socket, poller, None, $readable, None)
+            }
+
+            fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+                lock(&self.shared.state).handle_event(&self.shared.socket, poller, $readable, event)
+            }
+        }
+    };
+}
+
+impl_half!(ReadHalf, true);
+impl_half!(WriteHalf, false);
+
+impl Half {
+    /// Create the registration of a half, keeping only its own direction of the interest.
+    fn new(mut interest: Event, mode: PollMode, readable: bool) -> Self {
+        interest.readable = readable;
+        interest.writable = !readable;
+        Half {
+            event: interest,
+            mode,
+            armed: true,
+            ready: None,
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Whether the poller supports the given polling mode.
+fn poller_supports_mode(poller: &Poller, mode: PollMode) -> bool {
+    match mode {
+        PollMode::Oneshot => true,
+        PollMode::Level => poller.supports_level(),
+        PollMode::Edge | PollMode::EdgeOneshot => poller.supports_edge(),
+        _ => false,
+    }
+}
+
+fn not_registered() -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, "source is not registered")
+}
+
+fn poller_dropped() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotConnected,
+        "the poller the source was registered into has been dropped",
+    )
+}
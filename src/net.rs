@@ -0,0 +1,247 @@
+//! Event sources for network sockets.
+//!
+//! These wrap a non-blocking socket in a [`Socket`] and translate `WouldBlock` errors into
+//! [`Poll::Pending`], re-arming the socket's registration so that the poller reports it again
+//! once the operation can make progress.
+
+use crate::{Event, EventSource, PollMode, Poller, Result, Socket, Source};
+
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Weak};
+use std::task::Poll;
+
+/// A UDP socket that is driven by a [`Poller`].
+#[derive(Debug)]
+pub struct UdpSource {
+    /// The underlying socket.
+    socket: Socket<UdpSocket>,
+
+    /// The poller the socket is registered into.
+    poller: Weak<Poller>,
+
+    /// Whether we added writable interest because a send would have blocked.
+    want_write: bool,
+}
+
+impl UdpSource {
+    /// Creates a new UDP source, putting the socket into non-blocking mode.
+    pub fn new(socket: UdpSocket) -> Result<Self> {
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket: Socket::new(socket),
+            poller: Weak::new(),
+            want_write: false,
+        })
+    }
+
+    /// Creates a new UDP source bound to the given address.
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::new(UdpSocket::bind(addr)?)
+    }
+
+    /// Get a reference to the underlying socket.
+    pub fn socket(&self) -> &UdpSocket {
+        self.socket.socket()
+    }
+
+    /// Get the event this source is currently registered with, if any.
+    pub fn interest(&self) -> Option<Event> {
+        self.socket.interest()
+    }
+
+    /// Receive a datagram, returning the number of bytes read and the address it came from.
+    ///
+    /// If no datagram is available this returns [`Poll::Pending`], and a oneshot registration
+    /// is re-armed so that the poller reports the next datagram.
+    pub fn recv_from(&mut self, buf: &mut [u8]) -> Poll<Result<(usize, SocketAddr)>> {
+        match self.socket.socket().recv_from(buf) {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            result => return Poll::Ready(result),
+        }
+
+        match rearm(&mut self.socket, &self.poller) {
+            Ok(()) => Poll::Pending,
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+
+    /// Send a datagram to the given address, returning the number of bytes written.
+    ///
+    /// If the socket's send buffer is full this returns [`Poll::Pending`] and adds writable
+    /// interest to the registration until a send succeeds.
+    pub fn send_to(&mut self, buf: &[u8], addr: impl ToSocketAddrs) -> Poll<Result<usize>> {
+        match self.socket.socket().send_to(buf, addr) {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => return Poll::Ready(Err(err)),
+            Ok(n) => {
+                // Stop waiting for writability once we can write again.
+                if self.want_write {
+                    if let Err(err) = self.set_want_write(false) {
+                        return Poll::Ready(Err(err));
+                    }
+                }
+
+                return Poll::Ready(Ok(n));
+            }
+        }
+
+        let result = if self.want_write {
+            rearm(&mut self.socket, &self.poller)
+        } else {
+            self.set_want_write(true)
+        };
+
+        match result {
+            Ok(()) => Poll::Pending,
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+
+    /// Add or remove the writable interest used to wait for a blocked send.
+    fn set_want_write(&mut self, want_write: bool) -> Result<()> {
+        // Without a registration there is nothing to wait on.
+        if let Some(poller) = self.poller.upgrade() {
+            self.socket.set_writable(&poller, want_write)?;
+        }
+
+        self.want_write = want_write;
+        Ok(())
+    }
+}
+
+impl Source for UdpSource {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.socket.register(poller, interest, mode)?;
+        self.poller = Arc::downgrade(poller);
+        self.want_write = false;
+        Ok(())
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.socket.reregister(poller, interest, mode)?;
+        self.poller = Arc::downgrade(poller);
+        self.want_write = false;
+        Ok(())
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.socket.deregister(poller)?;
+        self.poller = Weak::new();
+        self.want_write = false;
+        Ok(())
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.socket.handle_event(poller, event)
+    }
+}
+
+/// A TCP listener that accepts connections when the [`Poller`] reports it as readable.
+#[derive(Debug)]
+pub struct Acceptor {
+    /// The underlying listener.
+    listener: Socket<TcpListener>,
+
+    /// The poller the listener is registered into.
+    poller: Weak<Poller>,
+}
+
+impl Acceptor {
+    /// Creates a new acceptor, putting the listener into non-blocking mode.
+    pub fn new(listener: TcpListener) -> Result<Self> {
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener: Socket::new(listener),
+            poller: Weak::new(),
+        })
+    }
+
+    /// Creates a new acceptor listening on the given address.
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::new(TcpListener::bind(addr)?)
+    }
+
+    /// Get a reference to the underlying listener.
+    pub fn listener(&self) -> &TcpListener {
+        self.listener.socket()
+    }
+
+    /// Get the event this source is currently registered with, if any.
+    pub fn interest(&self) -> Option<Event> {
+        self.listener.interest()
+    }
+
+    /// Accept a new connection.
+    ///
+    /// If no connection is pending this returns [`Poll::Pending`]. A oneshot registration is
+    /// re-armed after every call, so it is reported once for each pending connection. In level
+    /// or edge mode, call this until it returns [`Poll::Pending`] to accept every connection
+    /// that is pending.
+    pub fn accept(&mut self) -> Poll<Result<(TcpStream, SocketAddr)>> {
+        let result = match self.listener.socket().accept() {
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => None,
+            result => Some(result),
+        };
+
+        if let Err(err) = rearm(&mut self.listener, &self.poller) {
+            return Poll::Ready(Err(err));
+        }
+
+        match result {
+            Some(result) => Poll::Ready(result),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Accepted connections are produced as they come in. The listener never finishes.
+impl EventSource for Acceptor {
+    type Item = Result<(TcpStream, SocketAddr)>;
+
+    fn poll_next(&mut self) -> Poll<Option<Self::Item>> {
+        self.accept().map(Some)
+    }
+}
+
+impl Source for Acceptor {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.listener.register(poller, interest, mode)?;
+        self.poller = Arc::downgrade(poller);
+        Ok(())
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.listener.reregister(poller, interest, mode)?;
+        self.poller = Arc::downgrade(poller);
+        Ok(())
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.listener.deregister(poller)?;
+        self.poller = Weak::new();
+        Ok(())
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.listener.handle_event(poller, event)
+    }
+}
+
+/// Re-arm a socket that is registered in oneshot mode.
+fn rearm<T>(socket: &mut Socket<T>, poller: &Weak<Poller>) -> Result<()>
+where
+    Socket<T>: Source,
+{
+    let (interest, mode) = match (socket.interest(), socket.mode()) {
+        (Some(interest), Some(mode @ (PollMode::Oneshot | PollMode::EdgeOneshot))) => {
+            (interest, mode)
+        }
+        _ => return Ok(()),
+    };
+
+    match poller.upgrade() {
+        Some(poller) => socket.reregister(&poller, interest, mode),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,14 @@
+//! Platform-specific functionality.
+
+#[cfg(unix)]
+pub mod unix;
+
+#[cfg(all(windows, polling_utils_socket_ping))]
+pub mod windows;
+
+mod __private {
+    #[doc(hidden)]
+    pub trait PingSealed {}
+
+    impl PingSealed for crate::ping::Ping {}
+}
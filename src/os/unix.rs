@@ -0,0 +1,23 @@
+//! Functionality that is only available on Unix.
+
+use crate::ping::Ping;
+
+use std::os::unix::io::BorrowedFd;
+
+/// Extension trait for accessing the file descriptor behind a [`Ping`].
+pub trait PingExt: super::__private::PingSealed {
+    /// Get the file descriptor that becomes readable when the ping is notified.
+    ///
+    /// This can be registered into a poller directly instead of going through [`Source`].
+    /// Reading from it consumes notifications behind the ping's back, so only poll it for
+    /// readiness.
+    ///
+    /// [`Source`]: crate::Source
+    fn as_fd(&self) -> BorrowedFd<'_>;
+}
+
+impl PingExt for Ping {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        Ping::as_fd(self)
+    }
+}
@@ -0,0 +1,26 @@
+//! Functionality that is only available on Windows.
+
+use crate::ping::Ping;
+
+use std::os::windows::io::BorrowedSocket;
+
+/// Extension trait for accessing the socket behind a [`Ping`].
+///
+/// This is only available with the loopback socket backend; pings posted through IOCP are not
+/// backed by a socket.
+pub trait PingExt: super::__private::PingSealed {
+    /// Get the socket that becomes readable when the ping is notified.
+    ///
+    /// This can be registered into a poller directly instead of going through [`Source`].
+    /// Reading from it consumes notifications behind the ping's back, so only poll it for
+    /// readiness.
+    ///
+    /// [`Source`]: crate::Source
+    fn as_socket(&self) -> BorrowedSocket<'_>;
+}
+
+impl PingExt for Ping {
+    fn as_socket(&self) -> BorrowedSocket<'_> {
+        Ping::as_socket(self)
+    }
+}
@@ -3,72 +3,527 @@
 cfg_if::cfg_if! {
     if #[cfg(target_os = "linux")] {
         mod eventfd;
-        use eventfd as sys;
+        mod linux;
+        mod pipe;
+        use linux as sys;
     } else if #[cfg(unix)] {
         mod pipe;
         use pipe as sys;
-    } else if #[cfg(windows)] {
+    } else if #[cfg(all(windows, not(polling_utils_socket_ping)))] {
         mod iocp;
         use iocp as sys;
+    } else if #[cfg(windows)] {
+        mod socket;
+        use socket as sys;
     } else {
         compile_error!("The ping feature is only supported on Unix.");
     }
 }
 
+#[cfg(feature = "local-ping")]
+mod local;
+#[cfg(feature = "local-ping")]
+pub use local::{LocalNotifier, LocalPing};
+
+mod multi;
+pub use multi::MultiPing;
+
+mod pool;
+pub use pool::{PingPool, PooledPing};
+
 use crate::{Event, PollMode, Poller, Result, Source};
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{self, AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
 
 /// A ping event source that wakes up when the user requests it to.
 #[derive(Debug)]
 pub struct Ping {
     /// The underlying source.
     source: sys::Ping,
+
+    /// The number of notifications consumed since the count was last taken.
+    count: u64,
+
+    /// The interest and mode to use by default.
+    default_interest: Option<(Event, PollMode)>,
+
+    /// The tasks waiting for this ping to be notified.
+    waiters: Arc<Waiters>,
 }
 
 /// The notifier that can be used to wake up the ping event source.
+///
+/// Notifiers compare equal if they notify the same ping.
 #[derive(Debug, Clone)]
 pub struct Notifier {
     /// The underlying notifier.
     notifier: sys::Notify,
+
+    /// The tasks waiting for the ping to be notified.
+    waiters: Arc<Waiters>,
+}
+
+/// The implementation a [`Ping`] is built on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PingBackend {
+    /// The default backend for the platform.
+    ///
+    /// This is an eventfd on Linux, falling back to a pipe if eventfds are unavailable, a pipe
+    /// on other Unix platforms, and a completion packet on Windows.
+    #[default]
+    Auto,
+
+    /// A Linux eventfd.
+    ///
+    /// This is only available on Linux.
+    Eventfd,
+
+    /// A pipe.
+    ///
+    /// This is only available on Unix.
+    Pipe,
+}
+
+/// Creates a [`Ping`] with custom options.
+#[derive(Debug, Clone)]
+pub struct PingBuilder {
+    /// The backend to build the ping on.
+    backend: PingBackend,
+
+    /// Whether each event consumes a single notification.
+    semaphore: bool,
+
+    /// The interest and mode the ping uses by default.
+    default_interest: Option<(Event, PollMode)>,
+}
+
+/// Tasks waiting for a ping to be notified.
+#[derive(Debug, Default)]
+struct Waiters {
+    /// Whether there are any wakers, so notifying doesn't need to lock otherwise.
+    waiting: AtomicBool,
+
+    /// The wakers of the waiting tasks.
+    wakers: Mutex<Vec<Waker>>,
+}
+
+/// A future that completes once a [`Ping`] has been notified.
+///
+/// This is returned by [`wait`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct Wait<'a> {
+    ping: &'a Ping,
+}
+
+/// Wait for the ping to be notified.
+///
+/// The returned future completes as soon as the ping has a notification that has not been
+/// handled, so it completes right away if one is already pending. It can be polled by any
+/// executor, including a [`PollFuture`](crate::future::PollFuture).
+pub fn wait(ping: &Ping) -> Wait<'_> {
+    Wait { ping }
 }
 
 impl Ping {
     /// Creates a new ping event source.
+    ///
+    /// On Linux this uses an eventfd. If eventfds are unavailable, for instance because a
+    /// sandbox blocks them, it falls back to a pipe.
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            source: sys::Ping::new()?,
-        })
+        Self::with_semaphore(true)
+    }
+
+    /// Creates a new ping event source and registers it into the given [`Poller`].
+    pub fn registered(poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<Self> {
+        let mut ping = Self::new()?;
+        ping.register(poller, interest, mode)?;
+        Ok(ping)
+    }
+
+    /// Creates a new ping event source, choosing how notifications are consumed.
+    ///
+    /// In semaphore mode, every call to `handle_event` consumes a single notification, so
+    /// `N` notifications take `N` events to clear. Otherwise, one call to `handle_event`
+    /// consumes every pending notification at once. [`Ping::new`] uses semaphore mode.
+    pub fn with_semaphore(semaphore: bool) -> Result<Self> {
+        PingBuilder::new().semaphore(semaphore).build()
+    }
+
+    /// Creates a new ping event source whose pipe can hold `capacity` bytes.
+    ///
+    /// Each pending notification takes up a byte of the pipe, so a smaller pipe saves memory
+    /// when there are many pings. The kernel rounds the capacity up to a whole number of pages.
+    ///
+    /// On Linux, where pings are usually built on an eventfd, this builds the ping on a pipe
+    /// instead. The capacity only has an effect where pipes can be resized, which is Linux and
+    /// Android. Elsewhere it is ignored.
+    pub fn with_pipe_capacity(capacity: usize) -> Result<Self> {
+        let backend = if cfg!(unix) {
+            PingBackend::Pipe
+        } else {
+            PingBackend::Auto
+        };
+
+        let ping = PingBuilder::new().backend(backend).build()?;
+        ping.source.set_pipe_capacity(capacity)?;
+        Ok(ping)
+    }
+
+    /// Get the number of notifications that have not been handled yet.
+    pub fn pending(&self) -> usize {
+        self.source.pending()
+    }
+
+    /// Take the number of notifications consumed by handling events since the last call.
+    ///
+    /// In counter mode a single event consumes every notification that was pending, so this
+    /// tells how many notifications were merged into one wakeup. In semaphore mode each event
+    /// usually consumes a single notification.
+    pub fn take_count(&mut self) -> u64 {
+        std::mem::take(&mut self.count)
+    }
+
+    /// Get the key this ping is currently registered with, if any.
+    ///
+    /// Events delivered for this ping carry this key, so it can be used to route them in a
+    /// hand-written event loop. On Windows the key is the one the completion packet is posted
+    /// with. It is kept after a oneshot registration fires, until the ping is deregistered.
+    pub fn event_key(&self) -> Option<usize> {
+        self.source.event_key()
+    }
+
+    /// Whether the event was delivered for this ping's registration in the poller.
+    ///
+    /// Events with a different key than the one we were registered with are ignored.
+    pub(crate) fn is_for(&self, poller: &Poller, event: &Event) -> bool {
+        self.source.is_for(poller, event)
+    }
+
+    /// Get the file descriptor that becomes readable when this ping is notified.
+    #[cfg(unix)]
+    pub(crate) fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        self.source.as_fd()
+    }
+
+    /// Get the socket that becomes readable when this ping is notified.
+    #[cfg(all(windows, polling_utils_socket_ping))]
+    pub(crate) fn as_socket(&self) -> std::os::windows::io::BorrowedSocket<'_> {
+        self.source.as_socket()
+    }
+
+    /// Reset this ping so that it can be handed out again as if it were new.
+    ///
+    /// Returns `false` without touching the ping if it cannot be reused, because it is still
+    /// registered or a notifier could still wake it up.
+    fn reset(&mut self) -> Result<bool> {
+        // Every notifier holds onto the waiters.
+        if self.registration().is_some() || Arc::strong_count(&self.waiters) > 1 {
+            return Ok(false);
+        }
+
+        self.source.clear()?;
+        self.count = 0;
+        self.default_interest = None;
+        Ok(true)
     }
 
     /// Create a new notifier for this ping event source.
     pub fn notifier(&self) -> Notifier {
         Notifier {
             notifier: self.source.notify().clone(),
+            waiters: self.waiters.clone(),
+        }
+    }
+}
+
+impl Default for PingBuilder {
+    fn default() -> Self {
+        Self {
+            backend: PingBackend::Auto,
+            semaphore: true,
+            default_interest: None,
+        }
+    }
+}
+
+impl PingBuilder {
+    /// Creates a builder for a semaphore-mode ping on the default backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the backend to build the ping on.
+    pub fn backend(mut self, backend: PingBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Set whether each event consumes a single notification.
+    ///
+    /// See [`Ping::with_semaphore`] for details.
+    pub fn semaphore(mut self, semaphore: bool) -> Self {
+        self.semaphore = semaphore;
+        self
+    }
+
+    /// Set the interest and mode the ping uses by default.
+    ///
+    /// See [`Source::rearm_default`].
+    pub fn default_interest(mut self, interest: Event, mode: PollMode) -> Self {
+        self.default_interest = Some((interest, mode));
+        self
+    }
+
+    /// Build the ping.
+    ///
+    /// Returns an `Unsupported` error if the backend is not available on this platform.
+    pub fn build(&self) -> Result<Ping> {
+        let semaphore = self.semaphore;
+        let source = match self.backend {
+            PingBackend::Auto => sys::Ping::new(semaphore)?,
+
+            #[cfg(target_os = "linux")]
+            PingBackend::Eventfd => sys::Ping::with_eventfd(semaphore)?,
+            #[cfg(target_os = "linux")]
+            PingBackend::Pipe => sys::Ping::with_pipe(semaphore)?,
+
+            #[cfg(all(unix, not(target_os = "linux")))]
+            PingBackend::Pipe => sys::Ping::new(semaphore)?,
+
+            #[allow(unreachable_patterns)]
+            backend => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "the {:?} ping backend is not available on this platform",
+                        backend
+                    ),
+                ))
+            }
+        };
+
+        Ok(self.finish(source))
+    }
+
+    /// Wrap the backend in a ping with the builder's options.
+    fn finish(&self, source: sys::Ping) -> Ping {
+        Ping {
+            source,
+            count: 0,
+            default_interest: self.default_interest,
+            waiters: Arc::default(),
         }
     }
 }
 
 impl Source for Ping {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.source.registration()
+    }
+
+    fn default_interest(&self) -> Option<(Event, PollMode)> {
+        self.default_interest
+    }
+
     fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        check_mode(poller, mode)?;
         self.source.register(poller, interest, mode)
     }
 
     fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        check_mode(poller, mode)?;
         self.source.reregister(poller, interest, mode)
     }
 
-    fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
         self.source.deregister(poller)
     }
 
-    fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
-        self.source.handle_event(poller, event)
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.handle_event_progress(poller, event).map(drop)
+    }
+
+    fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+        // Don't drain notifications for an event that was meant for another source.
+        if !self.is_for(poller, &event) {
+            return Ok(false);
+        }
+
+        let consumed = self.source.handle_event(poller, event)?;
+        self.count = self.count.saturating_add(consumed);
+        Ok(consumed > 0)
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        // Pings posted through IOCP can never be edge-triggered.
+        let iocp = cfg!(all(windows, not(polling_utils_socket_ping)));
+        let edge = matches!(mode, PollMode::Edge | PollMode::EdgeOneshot);
+        crate::poller_supports_mode(poller, mode) && !(iocp && edge)
     }
 }
 
 impl Notifier {
     /// Notifies the ping event source.
+    ///
+    /// This never blocks. If the pipe behind the ping is full, or an eventfd's counter would
+    /// overflow, the ping is already readable, so the notification is merged into the pending
+    /// ones instead. Use [`Notifier::try_notify`] to find out whether that happened.
     pub fn notify(&self) -> Result<()> {
-        self.notifier.notify()
+        self.try_notify().map(drop)
+    }
+
+    /// Notifies the ping event source, reporting whether the notification went through.
+    ///
+    /// Returns `Ok(false)` if the notification was merged into the pending ones because the
+    /// pipe behind the ping is full. A wakeup is already pending in that case, so the ping
+    /// still wakes up the poller.
+    pub fn try_notify(&self) -> Result<bool> {
+        let sent = self.notifier.notify()?;
+        self.waiters.wake();
+        Ok(sent)
+    }
+
+    /// Notify every ping event source in a batch of notifiers.
+    ///
+    /// Each ping is notified at most once, no matter how many of its notifiers are in the
+    /// batch. A ping that already has notifications pending is not written to at all, since
+    /// its event is on its way already; the batch is merged into those notifications.
+    pub fn notify_batch(notifiers: &[Notifier]) -> Result<()> {
+        let mut seen = HashSet::new();
+        for notifier in notifiers {
+            if !seen.insert(notifier.id()) || notifier.notifier.pending() > 0 {
+                continue;
+            }
+
+            notifier.notifier.notify()?;
+            notifier.waiters.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Get an id for the ping event source this notifier wakes up.
+    ///
+    /// Every notifier for the same ping has the same id while the ping is alive. This is
+    /// useful for logging.
+    pub fn id(&self) -> usize {
+        self.notifier.id()
+    }
+
+    /// Convert this notifier into a [`Waker`] that notifies the ping event source.
+    ///
+    /// Errors that occur while notifying are ignored when the waker is woken.
+    pub fn into_waker(self) -> Waker {
+        Waker::from(Arc::new(self))
+    }
+
+    /// Create a [`Waker`] that notifies the ping event source.
+    ///
+    /// Errors that occur while notifying are ignored when the waker is woken.
+    pub fn to_waker(&self) -> Waker {
+        self.clone().into_waker()
+    }
+}
+
+impl PartialEq for Notifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Notifier {}
+
+impl Hash for Notifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+/// Make sure the poller supports the given polling mode.
+fn check_mode(poller: &Arc<Poller>, mode: PollMode) -> Result<()> {
+    if Ping::supports_mode(poller, mode) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unsupported polling mode for this poller",
+        ))
+    }
+}
+
+/// Whether the ping was registered in an edge-triggered mode.
+///
+/// Edge-triggered sources are not woken again for notifications left behind, so they must be
+/// fully drained.
+#[cfg(any(unix, polling_utils_socket_ping))]
+fn is_edge(mode: Option<PollMode>) -> bool {
+    matches!(mode, Some(PollMode::Edge) | Some(PollMode::EdgeOneshot))
+}
+
+impl Waiters {
+    /// Wake up every waiting task.
+    fn wake(&self) {
+        // Pairs with the fence in `register`, so either we see the waker or it sees the
+        // notification.
+        atomic::fence(Ordering::SeqCst);
+        if !self.waiting.load(Ordering::Acquire) {
+            return;
+        }
+
+        let wakers = {
+            let mut wakers = self.wakers.lock().unwrap_or_else(|e| e.into_inner());
+            self.waiting.store(false, Ordering::Release);
+            std::mem::take(&mut *wakers)
+        };
+
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Register a waker to be woken on the next notification.
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(|e| e.into_inner());
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+        self.waiting.store(true, Ordering::Release);
+        drop(wakers);
+        atomic::fence(Ordering::SeqCst);
+    }
+}
+
+impl Future for Wait<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.ping.pending() > 0 {
+            return Poll::Ready(());
+        }
+
+        // Check again after registering, in case a notification came in between.
+        self.ping.waiters.register(cx.waker());
+        if self.ping.pending() > 0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Wake for Notifier {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let _ = self.notify();
     }
 }
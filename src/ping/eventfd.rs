@@ -1,10 +1,12 @@
 //! A ping event source built on a Linux eventfd.
 
+use rustix::event::{eventfd, EventfdFlags};
 use rustix::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
-use rustix::io::{eventfd, read, write, EventfdFlags};
+use rustix::io::{read, write};
 
 use crate::{Event, PollMode, Poller, Result, Socket, Source};
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -14,31 +16,58 @@ pub(super) struct Ping {
 }
 
 #[derive(Debug, Clone)]
-pub(super) struct Notify(Arc<OwnedFd>);
+pub(super) struct Notify(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    /// The eventfd.
+    eventfd: OwnedFd,
+
+    /// The number of notifications that have not been handled yet.
+    pending: AtomicUsize,
+}
 
 impl AsRawFd for Notify {
     fn as_raw_fd(&self) -> RawFd {
-        self.0.as_raw_fd()
+        self.0.eventfd.as_raw_fd()
     }
 }
 
 impl AsFd for Notify {
     fn as_fd(&self) -> BorrowedFd<'_> {
-        self.0.as_fd()
+        self.0.eventfd.as_fd()
     }
 }
 
 impl Ping {
-    pub(super) fn new() -> Result<Self> {
-        let efd = eventfd(
-            0,
-            EventfdFlags::CLOEXEC | EventfdFlags::NONBLOCK | EventfdFlags::SEMAPHORE,
-        )?;
+    pub(super) fn new(semaphore: bool) -> Result<Self> {
+        let mut flags = EventfdFlags::CLOEXEC | EventfdFlags::NONBLOCK;
+        if semaphore {
+            flags |= EventfdFlags::SEMAPHORE;
+        }
+
+        let efd = eventfd(0, flags)?;
         Ok(Self {
-            eventfd: Socket::new(Notify(Arc::new(efd))),
+            eventfd: Socket::new(Notify(Arc::new(Inner {
+                eventfd: efd,
+                pending: AtomicUsize::new(0),
+            }))),
         })
     }
 
+    pub(super) fn set_pipe_capacity(&self, _capacity: usize) -> Result<()> {
+        // There is no pipe to resize.
+        Ok(())
+    }
+
+    pub(super) fn pending(&self) -> usize {
+        self.notify().0.pending.load(Ordering::Acquire)
+    }
+
+    pub(super) fn as_fd(&self) -> BorrowedFd<'_> {
+        self.eventfd.socket().as_fd()
+    }
+
     pub(super) fn notify(&self) -> &Notify {
         self.eventfd.socket()
     }
@@ -61,20 +90,110 @@ impl Ping {
         self.eventfd.reregister(poller, interest, mode)
     }
 
-    pub(super) fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+    pub(super) fn deregister(&mut self, poller: &Poller) -> Result<()> {
         self.eventfd.deregister(poller)
     }
 
-    pub(super) fn handle_event(&mut self, poller: &Arc<Poller>, interest: Event) -> Result<()> {
-        // Drain the eventfd.
-        read(self.eventfd.socket(), &mut [0u8; 8])?;
-        self.eventfd.handle_event(poller, interest)
+    pub(super) fn event_key(&self) -> Option<usize> {
+        self.eventfd.key()
+    }
+
+    pub(super) fn registration(&self) -> Option<(Event, PollMode)> {
+        self.eventfd.registration()
+    }
+
+    pub(super) fn is_for(&self, poller: &Poller, event: &Event) -> bool {
+        self.eventfd.is_for_in(poller, event)
+    }
+
+    /// Handle an event, returning the number of notifications consumed.
+    pub(super) fn handle_event(&mut self, poller: &Poller, interest: Event) -> Result<u64> {
+        let consumed = self.drain(super::is_edge(self.eventfd.mode()))?;
+        self.notify().consume(consumed);
+
+        self.eventfd.handle_event(poller, interest)?;
+        Ok(consumed)
+    }
+
+    /// Drop every pending notification by emptying the eventfd.
+    pub(super) fn clear(&mut self) -> Result<()> {
+        let consumed = self.drain(true)?;
+        self.notify().consume(consumed);
+        Ok(())
+    }
+
+    /// Drain the eventfd, returning the number of notifications consumed.
+    ///
+    /// In semaphore mode a single read consumes one notification; otherwise it consumes the
+    /// whole counter. In edge mode we keep reading until the eventfd is empty.
+    fn drain(&self, edge: bool) -> Result<u64> {
+        let mut buf = [0u8; 8];
+        let mut consumed = match read(self.eventfd.socket(), &mut buf) {
+            Ok(_) => u64::from_ne_bytes(buf),
+
+            // A spurious wakeup leaves nothing to drain.
+            Err(rustix::io::Errno::AGAIN) => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+
+        if edge {
+            loop {
+                match read(self.eventfd.socket(), &mut buf) {
+                    Ok(_) => consumed += u64::from_ne_bytes(buf),
+                    Err(rustix::io::Errno::AGAIN) => break,
+                    Err(rustix::io::Errno::INTR) => continue,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        }
+
+        Ok(consumed)
     }
 }
 
 impl Notify {
-    pub(super) fn notify(&self) -> Result<()> {
-        write(self, &1u64.to_ne_bytes())?;
-        Ok(())
+    /// Get an id that is shared by every clone of this notifier.
+    pub(super) fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Get the number of notifications that have not been handled yet.
+    pub(super) fn pending(&self) -> usize {
+        self.0.pending.load(Ordering::Acquire)
+    }
+
+    pub(super) fn notify(&self) -> Result<bool> {
+        self.notify_n(1)
+    }
+
+    /// Add `count` notifications with a single write.
+    ///
+    /// If the counter would overflow, the eventfd is already readable, so the notifications
+    /// are coalesced into the ones that are pending instead of failing, and `false` is
+    /// returned.
+    pub(super) fn notify_n(&self, count: usize) -> Result<bool> {
+        // Count the notifications before they can be observed by the reader.
+        self.0.pending.fetch_add(count, Ordering::AcqRel);
+        match write(self, &(count as u64).to_ne_bytes()) {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                self.0.pending.fetch_sub(count, Ordering::AcqRel);
+                if err == rustix::io::Errno::AGAIN {
+                    Ok(false)
+                } else {
+                    Err(err.into())
+                }
+            }
+        }
+    }
+
+    /// Mark the given number of notifications as handled.
+    fn consume(&self, consumed: u64) {
+        let _ = self
+            .0
+            .pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                Some(pending.saturating_sub(consumed as usize))
+            });
     }
 }
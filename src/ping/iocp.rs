@@ -1,10 +1,11 @@
 //! A "ping" event source that uses IOCP to wake up the event loop.
+//!
+//! `polling` always uses IOCP on Windows, and `PollerIocpExt` only exists there, so every
+//! poller this backend is built against supports posting packets. There is nothing to check
+//! at registration time; errors from `post` are real I/O errors and are returned as such.
 
 use crate::{Event, PollMode, Poller, Result};
-use std::{
-    io,
-    sync::{Arc, Mutex, MutexGuard, Weak},
-};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
 
 use polling::os::iocp::{CompletionPacket, PollerIocpExt};
 
@@ -24,35 +25,49 @@ pub(super) struct Notify(Arc<Mutex<Inner>>);
 
 #[derive(Debug)]
 struct Inner {
-    /// The registered interest.
-    interest: Option<Interest>,
+    /// The interest registered in each poller.
+    interests: Vec<Interest>,
 
     /// The number of times we have been notified.
     notified: usize,
-}
 
-// TODO: CompletionPacket is thread safe
-unsafe impl Send for Inner {}
-unsafe impl Sync for Inner {}
+    /// Whether each event consumes a single notification.
+    semaphore: bool,
+}
 
 #[derive(Debug)]
 struct Interest {
     /// The underlying poller.
     poller: Weak<Poller>,
 
-    /// The underlying completion packet.
-    packet: CompletionPacket,
+    /// The event to post to the poller.
+    ///
+    /// `CompletionPacket` is not thread safe, so the packet is created when it is posted.
+    event: Event,
 
     /// The polling mode we are using.
     mode: PollMode,
+
+    /// Whether a packet has been posted to the poller but not handled yet.
+    ///
+    /// Notifications that arrive in the meantime are reported by that packet, so no more
+    /// packets are posted until it is handled.
+    in_flight: bool,
+
+    /// Whether packets are posted to the poller at all.
+    ///
+    /// A oneshot interest is disarmed once its packet is posted. It is kept around until the
+    /// ping is reregistered, so that the event it delivers is still recognized.
+    armed: bool,
 }
 
 impl Ping {
     /// Creates a new ping event source.
-    pub fn new() -> Result<Self> {
+    pub fn new(semaphore: bool) -> Result<Self> {
         Ok(Self(Notify(Arc::new(Mutex::new(Inner {
-            interest: None,
+            interests: Vec::new(),
             notified: 0,
+            semaphore,
         })))))
     }
 
@@ -60,6 +75,15 @@ impl Ping {
         &self.0
     }
 
+    pub(super) fn set_pipe_capacity(&self, _capacity: usize) -> Result<()> {
+        // There is no pipe to resize.
+        Ok(())
+    }
+
+    pub(super) fn pending(&self) -> usize {
+        self.lock().notified
+    }
+
     pub(super) fn register(
         &mut self,
         poller: &Arc<Poller>,
@@ -75,39 +99,89 @@ impl Ping {
         interest: Event,
         mode: PollMode,
     ) -> Result<()> {
-        match mode {
-            PollMode::Oneshot | PollMode::Level => {}
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "unsupported polling mode for IOCP",
-                ))
-            }
-        }
-
-        // Set the interest.
+        // Set the interest for this poller, keeping the interests for any others.
         let mut inner = self.lock();
-        inner.interest = Some(Interest {
-            packet: CompletionPacket::new(interest),
+        inner.interests.retain(|interest| !interest.is_for(poller));
+        inner.interests.push(Interest {
+            event: interest,
             poller: Arc::downgrade(poller),
             mode,
+            in_flight: false,
+            armed: true,
         });
 
+        // Like a readable fd, a ping with pending notifications is reported right away.
+        if inner.notified > 0 {
+            poller.post(CompletionPacket::new(interest))?;
+            if let Some(interest) = inner.interests.last_mut() {
+                interest.posted();
+            }
+        }
+
         Ok(())
     }
 
-    pub(super) fn deregister(&mut self, _poller: &Arc<Poller>) -> Result<()> {
-        // Clear the interest.
+    pub(super) fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        // Clear the interest for this poller.
         let mut inner = self.lock();
-        inner.interest = None;
+        inner.interests.retain(|interest| !interest.is_for(poller));
 
         Ok(())
     }
 
-    pub(super) fn handle_event(&mut self, _poller: &Arc<Poller>, _event: Event) -> Result<()> {
+    pub(super) fn event_key(&self) -> Option<usize> {
+        self.registration().map(|(event, _)| event.key)
+    }
+
+    pub(super) fn registration(&self) -> Option<(Event, PollMode)> {
+        self.lock()
+            .interests
+            .last()
+            .map(|interest| (interest.event, interest.mode))
+    }
+
+    pub(super) fn is_for(&self, poller: &Poller, event: &Event) -> bool {
+        let inner = self.lock();
+        let interest = inner
+            .interests
+            .iter()
+            .find(|interest| interest.is_for(poller));
+        match interest {
+            Some(interest) => interest.event.key == event.key,
+            None => inner.interests.is_empty(),
+        }
+    }
+
+    /// Handle an event, returning the number of notifications consumed.
+    pub(super) fn handle_event(&mut self, poller: &Poller, _event: Event) -> Result<u64> {
         // We are no longer in port.
         let mut inner = self.lock();
-        inner.notified = inner.notified.saturating_sub(1);
+        let consumed = if inner.semaphore {
+            inner.notified.min(1)
+        } else {
+            inner.notified
+        };
+        inner.notified -= consumed;
+
+        if let Some(interest) = inner
+            .interests
+            .iter_mut()
+            .find(|interest| interest.is_for(poller))
+        {
+            interest.in_flight = false;
+        }
+
+        // Report the notifications that are left with a new packet.
+        if inner.notified > 0 {
+            inner.wake()?;
+        }
+
+        Ok(consumed as u64)
+    }
+
+    /// Drop every pending notification.
+    pub(super) fn clear(&mut self) -> Result<()> {
+        self.lock().notified = 0;
         Ok(())
     }
 
@@ -117,29 +191,79 @@ impl Ping {
 }
 
 impl Notify {
-    pub(super) fn notify(&self) -> Result<()> {
+    /// Get an id that is shared by every clone of this notifier.
+    pub(super) fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Get the number of notifications that have not been handled yet.
+    pub(super) fn pending(&self) -> usize {
+        lock!(self.0.lock()).notified
+    }
+
+    pub(super) fn notify(&self) -> Result<bool> {
+        self.notify_n(1)
+    }
+
+    /// Add `count` notifications.
+    ///
+    /// Only one packet is posted to each poller at a time. In semaphore mode, handling the
+    /// packet posts another one for the notifications that are left. Notifications are only
+    /// counted, so they always go through.
+    pub(super) fn notify_n(&self, count: usize) -> Result<bool> {
         let mut inner = lock!(self.0.lock());
-        inner.notified = inner.notified.saturating_add(1);
-        inner.wake()
+        inner.notified = inner.notified.saturating_add(count);
+        inner.wake()?;
+        Ok(true)
     }
 }
 
 impl Inner {
     fn wake(&mut self) -> Result<()> {
-        if let Some(interest) = &self.interest {
+        let mut result = Ok(());
+
+        self.interests.retain_mut(|interest| {
+            // Forget about pollers that have been dropped.
             let poller = match interest.poller.upgrade() {
                 Some(poller) => poller,
-                None => return Ok(()),
+                None => return false,
             };
 
-            poller.post(interest.packet.clone())?;
+            // The packet that is already posted reports this notification too, and a disarmed
+            // interest waits to be reregistered.
+            if interest.in_flight || !interest.armed {
+                return true;
+            }
 
-            // If we are in oneshot mode, remove future interest.
-            if matches!(interest.mode, PollMode::EdgeOneshot | PollMode::Oneshot) {
-                self.interest = None;
+            // Report the first error, but still wake the other pollers.
+            if let Err(err) = poller.post(CompletionPacket::new(interest.event)) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+                return true;
             }
-        }
+            interest.posted();
 
-        Ok(())
+            true
+        });
+
+        result
+    }
+}
+
+impl Interest {
+    /// Whether this interest is registered in the given poller.
+    fn is_for(&self, poller: &Poller) -> bool {
+        std::ptr::eq(Weak::as_ptr(&self.poller), poller)
+    }
+
+    /// Record that a packet was posted for this interest.
+    fn posted(&mut self) {
+        self.in_flight = true;
+
+        // If we are in oneshot mode, stop posting until we are reregistered.
+        if matches!(self.mode, PollMode::EdgeOneshot | PollMode::Oneshot) {
+            self.armed = false;
+        }
     }
 }
@@ -0,0 +1,205 @@
+//! A ping event source that uses an eventfd, or a pipe where eventfds are unavailable.
+//!
+//! Sandboxes sometimes block `eventfd` with seccomp while still allowing pipes, so the backend
+//! is chosen when the ping is created rather than when the crate is compiled.
+
+use super::{eventfd, pipe};
+
+use crate::{Event, PollMode, Poller, Result};
+
+use rustix::fd::BorrowedFd;
+use rustix::io::Errno;
+
+use std::io;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub(super) struct Ping {
+    /// The backend the ping was created with.
+    backend: Backend,
+
+    /// The notifier for the backend.
+    notify: Notify,
+}
+
+#[derive(Debug)]
+enum Backend {
+    Eventfd(eventfd::Ping),
+    Pipe(pipe::Ping),
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Notify {
+    Eventfd(eventfd::Notify),
+    Pipe(pipe::Notify),
+}
+
+/// Call the same method on whichever backend is in use.
+macro_rules! dispatch {
+    ($backend:expr, $inner:ident => $e:expr) => {{
+        match $backend {
+            Backend::Eventfd($inner) => $e,
+            Backend::Pipe($inner) => $e,
+        }
+    }};
+}
+
+impl Ping {
+    pub(super) fn new(semaphore: bool) -> Result<Self> {
+        Self::or_pipe(Self::with_eventfd(semaphore), semaphore)
+    }
+
+    /// Fall back to a pipe if creating the eventfd failed because eventfds are unavailable.
+    pub(super) fn or_pipe(eventfd: Result<Self>, semaphore: bool) -> Result<Self> {
+        match eventfd {
+            Err(err) if eventfd_unavailable(&err) => Self::with_pipe(semaphore),
+            result => result,
+        }
+    }
+
+    /// Create a ping that is built on an eventfd, without falling back to a pipe.
+    pub(super) fn with_eventfd(semaphore: bool) -> Result<Self> {
+        let ping = eventfd::Ping::new(semaphore)?;
+        let notify = Notify::Eventfd(ping.notify().clone());
+        Ok(Self {
+            backend: Backend::Eventfd(ping),
+            notify,
+        })
+    }
+
+    /// Create a ping that is built on a pipe.
+    pub(super) fn with_pipe(semaphore: bool) -> Result<Self> {
+        let ping = pipe::Ping::new(semaphore)?;
+        let notify = Notify::Pipe(ping.notify().clone());
+        Ok(Self {
+            backend: Backend::Pipe(ping),
+            notify,
+        })
+    }
+
+    pub(super) fn set_pipe_capacity(&self, capacity: usize) -> Result<()> {
+        dispatch!(&self.backend, ping => ping.set_pipe_capacity(capacity))
+    }
+
+    pub(super) fn pending(&self) -> usize {
+        dispatch!(&self.backend, ping => ping.pending())
+    }
+
+    pub(super) fn as_fd(&self) -> BorrowedFd<'_> {
+        dispatch!(&self.backend, ping => ping.as_fd())
+    }
+
+    pub(super) fn notify(&self) -> &Notify {
+        &self.notify
+    }
+
+    pub(super) fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        dispatch!(&mut self.backend, ping => ping.register(poller, interest, mode))
+    }
+
+    pub(super) fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        dispatch!(&mut self.backend, ping => ping.reregister(poller, interest, mode))
+    }
+
+    pub(super) fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        dispatch!(&mut self.backend, ping => ping.deregister(poller))
+    }
+
+    pub(super) fn event_key(&self) -> Option<usize> {
+        dispatch!(&self.backend, ping => ping.event_key())
+    }
+
+    pub(super) fn registration(&self) -> Option<(Event, PollMode)> {
+        dispatch!(&self.backend, ping => ping.registration())
+    }
+
+    pub(super) fn is_for(&self, poller: &Poller, event: &Event) -> bool {
+        dispatch!(&self.backend, ping => ping.is_for(poller, event))
+    }
+
+    pub(super) fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<u64> {
+        dispatch!(&mut self.backend, ping => ping.handle_event(poller, event))
+    }
+
+    pub(super) fn clear(&mut self) -> Result<()> {
+        dispatch!(&mut self.backend, ping => ping.clear())
+    }
+}
+
+impl Notify {
+    /// Get an id that is shared by every clone of this notifier.
+    pub(super) fn id(&self) -> usize {
+        match self {
+            Notify::Eventfd(notify) => notify.id(),
+            Notify::Pipe(notify) => notify.id(),
+        }
+    }
+
+    pub(super) fn pending(&self) -> usize {
+        match self {
+            Notify::Eventfd(notify) => notify.pending(),
+            Notify::Pipe(notify) => notify.pending(),
+        }
+    }
+
+    pub(super) fn notify(&self) -> Result<bool> {
+        match self {
+            Notify::Eventfd(notify) => notify.notify(),
+            Notify::Pipe(notify) => notify.notify(),
+        }
+    }
+}
+
+/// Whether creating an eventfd failed because eventfds are not available.
+fn eventfd_unavailable(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(code) if code == Errno::NOSYS.raw_os_error() || code == Errno::PERM.raw_os_error()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::io::AsRawFd;
+    use std::time::Duration;
+
+    #[test]
+    fn eventfd_fallback() {
+        let poller = Arc::new(Poller::new().unwrap());
+        for errno in [Errno::NOSYS, Errno::PERM] {
+            // A sandbox that blocks eventfds gets a pipe instead.
+            let mut ping = Ping::or_pipe(Err(errno.into()), false).unwrap();
+            assert!(matches!(ping.backend, Backend::Pipe(_)));
+            let fd = ping.as_fd().as_raw_fd();
+            let link = std::fs::read_link(format!("/proc/self/fd/{}", fd)).unwrap();
+            assert!(link.to_string_lossy().starts_with("pipe:"), "{:?}", link);
+
+            ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+                .unwrap();
+            ping.notify().notify().unwrap();
+            let mut events = vec![];
+            poller
+                .wait(&mut events, Some(Duration::from_secs(1)))
+                .unwrap();
+            assert_eq!(events, [Event::readable(0)]);
+            assert_eq!(ping.handle_event(&poller, events[0]).unwrap(), 1);
+            ping.deregister(&poller).unwrap();
+        }
+
+        // Other errors are not papered over.
+        let err = Ping::or_pipe(Err(Errno::MFILE.into()), false).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(Errno::MFILE.raw_os_error()));
+    }
+}
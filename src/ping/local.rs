@@ -0,0 +1,112 @@
+//! A ping for loops where the notifier and the poller share a thread.
+
+use crate::{Event, PollMode, Poller, Result, Source};
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A ping event source that doesn't use a file descriptor.
+///
+/// The ping only sets a flag, so it can only be notified from the thread that polls it. It is
+/// reported through [`Source::pending_event`], which [`dispatch`](crate::dispatch) checks before
+/// waiting on the poller. The poller itself never reports it, so waiting on the poller directly
+/// doesn't wake up for it.
+///
+/// The ping is not `Send`, so it can't be inserted into a [`Reactor`](crate::reactor::Reactor);
+/// use it with [`dispatch`](crate::dispatch) or a [`Dispatcher`](crate::Dispatcher) on the
+/// polling thread instead.
+#[derive(Debug, Default)]
+pub struct LocalPing {
+    /// Whether the ping has been notified.
+    pending: Rc<Cell<bool>>,
+
+    /// The interest and mode the ping was registered with.
+    registration: Option<(Event, PollMode)>,
+
+    /// Whether the ping can be reported, which is false for a oneshot ping that has fired.
+    armed: bool,
+}
+
+/// Notifies a [`LocalPing`] from the same thread.
+#[derive(Debug, Clone)]
+pub struct LocalNotifier {
+    /// Whether the ping has been notified.
+    pending: Rc<Cell<bool>>,
+}
+
+impl LocalPing {
+    /// Creates a new local ping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a notifier for this ping.
+    pub fn notifier(&self) -> LocalNotifier {
+        LocalNotifier {
+            pending: self.pending.clone(),
+        }
+    }
+
+    /// Whether the ping has been notified without the event being handled.
+    pub fn is_pending(&self) -> bool {
+        self.pending.get()
+    }
+}
+
+impl LocalNotifier {
+    /// Notify the ping.
+    pub fn notify(&self) {
+        self.pending.set(true);
+    }
+}
+
+impl Source for LocalPing {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.registration
+    }
+
+    fn pending_event(&self) -> Option<Event> {
+        match self.registration {
+            Some((interest, _)) if self.armed && self.pending.get() => Some(interest),
+            _ => None,
+        }
+    }
+
+    fn register(&mut self, _poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.registration = Some((interest, mode));
+        self.armed = true;
+        Ok(())
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        if self.registration.is_none() {
+            return Err(crate::not_registered());
+        }
+
+        self.register(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, _poller: &Poller) -> Result<()> {
+        self.registration = None;
+        Ok(())
+    }
+
+    fn handle_event(&mut self, _poller: &Poller, event: Event) -> Result<()> {
+        let mode = match self.registration {
+            Some((interest, mode)) if interest.key == event.key => mode,
+            _ => return Ok(()),
+        };
+
+        self.pending.set(false);
+        if matches!(mode, PollMode::Oneshot | PollMode::EdgeOneshot) {
+            self.armed = false;
+        }
+
+        Ok(())
+    }
+
+    fn supports_mode(_poller: &Arc<Poller>, _mode: PollMode) -> bool {
+        true
+    }
+}
@@ -0,0 +1,165 @@
+//! A ping that is registered into several pollers at once.
+
+use super::Ping;
+use crate::{Event, PollMode, Poller, Result, Source};
+
+use std::io;
+use std::sync::{Arc, Weak};
+
+/// A ping event source that can be registered into several pollers at once.
+///
+/// Each [`register`](Source::register) call adds a target with its own ping, so one
+/// notification can be sent to a single target with [`MultiPing::notify_one`], or to every
+/// target with [`MultiPing::notify_all`]. A `MultiPing` can be registered into each poller
+/// once. Targets whose poller has been dropped are skipped and removed.
+#[derive(Debug, Default)]
+pub struct MultiPing {
+    /// The registered targets.
+    targets: Vec<Target>,
+
+    /// The index of the target to try first in `notify_one`.
+    next: usize,
+}
+
+/// A ping registered into one poller.
+#[derive(Debug)]
+struct Target {
+    /// The poller the ping is registered into.
+    poller: Weak<Poller>,
+
+    /// The ping for this poller.
+    ping: Ping,
+}
+
+impl MultiPing {
+    /// Creates a new ping with no targets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the number of targets this ping is registered with.
+    ///
+    /// Targets whose poller has been dropped are counted until the next notification prunes
+    /// them.
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether this ping has no targets.
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Notify a single target.
+    ///
+    /// Targets are tried in round-robin order, and the first one without pending notifications
+    /// is woken. If every target is busy, the next one in order is woken anyway. Returns
+    /// `false` if there are no targets to notify.
+    pub fn notify_one(&mut self) -> Result<bool> {
+        self.prune();
+
+        let len = self.targets.len();
+        if len == 0 {
+            return Ok(false);
+        }
+
+        let start = self.next % len;
+        let index = (0..len)
+            .map(|i| (start + i) % len)
+            .find(|&i| self.targets[i].ping.pending() == 0)
+            .unwrap_or(start);
+
+        self.targets[index].ping.notifier().notify()?;
+        self.next = index + 1;
+        Ok(true)
+    }
+
+    /// Notify every target.
+    ///
+    /// Every target is notified even if an earlier one fails, and the first error is returned.
+    pub fn notify_all(&mut self) -> Result<()> {
+        self.prune();
+
+        let mut result = Ok(());
+        for target in &self.targets {
+            if let Err(err) = target.ping.notifier().notify() {
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+
+    /// Remove the targets whose poller has been dropped.
+    fn prune(&mut self) {
+        self.targets
+            .retain(|target| target.poller.strong_count() > 0);
+    }
+
+    /// Get the target registered into the given poller.
+    fn target_mut(&mut self, poller: &Poller) -> Option<&mut Target> {
+        self.targets
+            .iter_mut()
+            .find(|target| std::ptr::eq(target.poller.as_ptr(), poller))
+    }
+}
+
+impl Source for MultiPing {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.prune();
+        if self.target_mut(poller).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the ping is already registered into this poller",
+            ));
+        }
+
+        let ping = Ping::registered(poller, interest, mode)?;
+        self.targets.push(Target {
+            poller: Arc::downgrade(poller),
+            ping,
+        });
+
+        Ok(())
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.target_mut(poller)
+            .ok_or_else(crate::not_registered)?
+            .ping
+            .reregister(poller, interest, mode)
+    }
+
+    fn rearm(&mut self, poller: &Arc<Poller>) -> Result<()> {
+        self.target_mut(poller)
+            .ok_or_else(crate::not_registered)?
+            .ping
+            .rearm(poller)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        // Like a `Ping`, deregistering from a poller we aren't registered into does nothing.
+        let index = match self
+            .targets
+            .iter()
+            .position(|target| std::ptr::eq(target.poller.as_ptr(), poller))
+        {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+
+        self.targets[index].ping.deregister(poller)?;
+        self.targets.remove(index);
+        Ok(())
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        match self.target_mut(poller) {
+            Some(target) => target.ping.handle_event(poller, event),
+            None => Ok(()),
+        }
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        Ping::supports_mode(poller, mode)
+    }
+}
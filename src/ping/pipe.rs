@@ -1,9 +1,11 @@
 //! A ping event source built on a pipe.
 
-use rustix::fd::OwnedFd;
-use rustix::io::{fcntl_getfd, fcntl_setfd, pipe, pipe_with, read, write, FdFlags, PipeFlags};
+use rustix::fd::{AsFd, BorrowedFd, OwnedFd};
+use rustix::io::{fcntl_getfd, fcntl_setfd, ioctl_fionbio, read, write, FdFlags};
+use rustix::pipe::{pipe, pipe_with, PipeFlags};
 
 use crate::{Event, PollMode, Poller, Result, Socket, Source};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 #[derive(Debug)]
@@ -13,13 +15,25 @@ pub(super) struct Ping {
 
     /// The write end of the pipe.
     writer: Notify,
+
+    /// Whether each event consumes a single notification.
+    semaphore: bool,
 }
 
 #[derive(Debug, Clone)]
-pub(super) struct Notify(Arc<OwnedFd>);
+pub(super) struct Notify(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    /// The write end of the pipe.
+    writer: OwnedFd,
+
+    /// The number of notifications that have not been handled yet.
+    pending: AtomicUsize,
+}
 
 impl Ping {
-    pub(super) fn new() -> Result<Self> {
+    pub(super) fn new(semaphore: bool) -> Result<Self> {
         // Create a new pipe.
         let (reader, writer) = pipe_with(PipeFlags::CLOEXEC).or_else(|_| {
             // If we failed to atomically create a pipe with the `CLOEXEC` flag, we try to
@@ -35,16 +49,45 @@ impl Ping {
             Result::Ok((reader, writer))
         })?;
 
+        // Draining the pipe in counter or edge mode reads until it would block.
+        ioctl_fionbio(&reader, true)?;
+
+        // Notifying should never block, even if the pipe is full.
+        ioctl_fionbio(&writer, true)?;
+
         Ok(Self {
             reader: Socket::new(reader),
-            writer: Notify(Arc::new(writer)),
+            writer: Notify(Arc::new(Inner {
+                writer,
+                pending: AtomicUsize::new(0),
+            })),
+            semaphore,
         })
     }
 
+    pub(super) fn as_fd(&self) -> BorrowedFd<'_> {
+        self.reader.socket().as_fd()
+    }
+
     pub(super) fn notify(&self) -> &Notify {
         &self.writer
     }
 
+    pub(super) fn set_pipe_capacity(&self, capacity: usize) -> Result<()> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        rustix::pipe::fcntl_setpipe_size(&self.writer.0.writer, capacity)?;
+
+        // Other platforms can't resize their pipes.
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let _ = capacity;
+
+        Ok(())
+    }
+
+    pub(super) fn pending(&self) -> usize {
+        self.writer.0.pending.load(Ordering::Acquire)
+    }
+
     pub(super) fn register(
         &mut self,
         poller: &Arc<Poller>,
@@ -63,19 +106,117 @@ impl Ping {
         self.reader.reregister(poller, interest, mode)
     }
 
-    pub(super) fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+    pub(super) fn deregister(&mut self, poller: &Poller) -> Result<()> {
         self.reader.deregister(poller)
     }
 
-    pub(super) fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
-        read(self.reader.socket(), &mut [0u8])?;
-        self.reader.handle_event(poller, event)
+    pub(super) fn event_key(&self) -> Option<usize> {
+        self.reader.key()
+    }
+
+    pub(super) fn registration(&self) -> Option<(Event, PollMode)> {
+        self.reader.registration()
+    }
+
+    pub(super) fn is_for(&self, poller: &Poller, event: &Event) -> bool {
+        self.reader.is_for_in(poller, event)
+    }
+
+    /// Handle an event, returning the number of notifications consumed.
+    pub(super) fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<u64> {
+        let consumed = self.drain(super::is_edge(self.reader.mode()))?;
+        self.writer.consume(consumed);
+
+        self.reader.handle_event(poller, event)?;
+        Ok(consumed)
+    }
+
+    /// Drop every pending notification by emptying the pipe.
+    pub(super) fn clear(&mut self) -> Result<()> {
+        let consumed = self.drain(true)?;
+        self.writer.consume(consumed);
+        Ok(())
+    }
+
+    /// Drain the pipe, returning the number of notifications consumed.
+    ///
+    /// In semaphore mode a single notification is consumed, unless we are in edge mode and
+    /// have to empty the pipe.
+    fn drain(&self, edge: bool) -> Result<u64> {
+        if self.semaphore && !edge {
+            // A spurious wakeup leaves nothing to drain.
+            return match read(self.reader.socket(), &mut [0u8]) {
+                Ok(n) => Ok(n as u64),
+                Err(rustix::io::Errno::AGAIN) => Ok(0),
+                Err(err) => Err(err.into()),
+            };
+        }
+
+        let mut buf = [0u8; 64];
+        let mut consumed = 0;
+        loop {
+            match read(self.reader.socket(), &mut buf) {
+                Ok(0) => break,
+                Ok(n) => consumed += n as u64,
+                Err(rustix::io::Errno::AGAIN) => break,
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(consumed)
     }
 }
 
 impl Notify {
-    pub(super) fn notify(&self) -> Result<()> {
-        write(&self.0, &[0u8])?;
-        Ok(())
+    /// Get an id that is shared by every clone of this notifier.
+    pub(super) fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Get the number of notifications that have not been handled yet.
+    pub(super) fn pending(&self) -> usize {
+        self.0.pending.load(Ordering::Acquire)
+    }
+
+    pub(super) fn notify(&self) -> Result<bool> {
+        self.notify_n(1)
+    }
+
+    /// Add `count` notifications, writing one byte for each.
+    ///
+    /// Returns `false` if the pipe filled up first. The pipe is readable then, so the
+    /// notifications that did not fit are merged into the pending ones.
+    pub(super) fn notify_n(&self, count: usize) -> Result<bool> {
+        // Count the notifications before they can be observed by the reader.
+        self.0.pending.fetch_add(count, Ordering::AcqRel);
+
+        let buf = [0u8; 64];
+        let mut remaining = count;
+        while remaining > 0 {
+            match write(&self.0.writer, &buf[..remaining.min(buf.len())]) {
+                Ok(n) => remaining -= n,
+                Err(err) => {
+                    self.0.pending.fetch_sub(remaining, Ordering::AcqRel);
+                    return if err == rustix::io::Errno::AGAIN {
+                        Ok(false)
+                    } else {
+                        Err(err.into())
+                    };
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Mark the given number of notifications as handled.
+    fn consume(&self, consumed: u64) {
+        let _ = self
+            .0
+            .pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                Some(pending.saturating_sub(consumed as usize))
+            });
     }
 }
@@ -0,0 +1,188 @@
+//! A pool of pings that are reused instead of being closed.
+
+use super::Ping;
+use crate::{Event, PollMode, Poller, Result, Source};
+
+use std::ops;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+/// The number of idle pings a [`PingPool`] keeps by default.
+const DEFAULT_MAX_IDLE: usize = 64;
+
+/// A pool that recycles pings, so that creating many short-lived pings doesn't open and close
+/// a file descriptor for each one.
+///
+/// [`PingPool::acquire`] hands out a [`PooledPing`], which goes back into the pool when it is
+/// dropped. A ping is only put back if it was deregistered and every [`Notifier`] for it was
+/// dropped, since those could otherwise wake up whoever gets the ping next. Its pending
+/// notifications are drained before it is reused. Other pings are closed as usual.
+///
+/// Cloning the pool gives another handle to the same pings.
+///
+/// [`Notifier`]: super::Notifier
+#[derive(Debug, Clone)]
+pub struct PingPool {
+    /// The pings shared by every handle to the pool.
+    shared: Arc<Shared>,
+}
+
+/// The state of a [`PingPool`].
+#[derive(Debug)]
+struct Shared {
+    /// The pings that are ready to be handed out.
+    idle: Mutex<Vec<Ping>>,
+
+    /// The most idle pings to keep around.
+    max_idle: usize,
+}
+
+/// A [`Ping`] that goes back into its [`PingPool`] when it is dropped.
+///
+/// This dereferences to the ping, and registers into a poller like one.
+#[derive(Debug)]
+pub struct PooledPing {
+    /// The ping, which is only taken when this is dropped or unwrapped.
+    ping: Option<Ping>,
+
+    /// The pool to return the ping to.
+    ///
+    /// This is weak so that dropping the pool closes its pings, even if some are still out.
+    pool: Weak<Shared>,
+}
+
+impl Default for PingPool {
+    fn default() -> Self {
+        Self::with_max_idle(DEFAULT_MAX_IDLE)
+    }
+}
+
+impl PingPool {
+    /// Creates a new, empty pool that keeps up to 64 idle pings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty pool that keeps up to `max_idle` idle pings.
+    ///
+    /// Pings that are returned to a full pool are closed.
+    pub fn with_max_idle(max_idle: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                idle: Mutex::new(Vec::new()),
+                max_idle,
+            }),
+        }
+    }
+
+    /// Get a ping from the pool, creating a new one if there are no idle pings.
+    ///
+    /// Like [`Ping::new`], the ping is in semaphore mode and is not registered.
+    pub fn acquire(&self) -> Result<PooledPing> {
+        let ping = match self.shared.lock().pop() {
+            Some(ping) => ping,
+            None => Ping::new()?,
+        };
+
+        Ok(PooledPing {
+            ping: Some(ping),
+            pool: Arc::downgrade(&self.shared),
+        })
+    }
+
+    /// Get the number of idle pings in the pool.
+    pub fn idle(&self) -> usize {
+        self.shared.lock().len()
+    }
+}
+
+impl Shared {
+    /// Lock the idle pings.
+    fn lock(&self) -> MutexGuard<'_, Vec<Ping>> {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Put a ping back into the pool, if it can be reused and there is room.
+    fn release(&self, mut ping: Ping) {
+        if !matches!(ping.reset(), Ok(true)) {
+            return;
+        }
+
+        let mut idle = self.lock();
+        if idle.len() < self.max_idle {
+            idle.push(ping);
+        }
+    }
+}
+
+impl PooledPing {
+    /// Take the ping out of the pool, so that it is closed when dropped.
+    pub fn into_inner(mut self) -> Ping {
+        self.ping.take().expect("the ping is only taken on drop")
+    }
+
+    /// Get the ping.
+    fn ping(&self) -> &Ping {
+        self.ping.as_ref().expect("the ping is only taken on drop")
+    }
+
+    /// Get the ping mutably.
+    fn ping_mut(&mut self) -> &mut Ping {
+        self.ping.as_mut().expect("the ping is only taken on drop")
+    }
+}
+
+impl ops::Deref for PooledPing {
+    type Target = Ping;
+
+    fn deref(&self) -> &Ping {
+        self.ping()
+    }
+}
+
+impl ops::DerefMut for PooledPing {
+    fn deref_mut(&mut self) -> &mut Ping {
+        self.ping_mut()
+    }
+}
+
+impl Drop for PooledPing {
+    fn drop(&mut self) {
+        if let (Some(ping), Some(pool)) = (self.ping.take(), self.pool.upgrade()) {
+            pool.release(ping);
+        }
+    }
+}
+
+impl Source for PooledPing {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.ping().registration()
+    }
+
+    fn default_interest(&self) -> Option<(Event, PollMode)> {
+        self.ping().default_interest()
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping_mut().register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping_mut().reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping_mut().deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.ping_mut().handle_event(poller, event)
+    }
+
+    fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+        self.ping_mut().handle_event_progress(poller, event)
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        Ping::supports_mode(poller, mode)
+    }
+}
@@ -0,0 +1,233 @@
+//! A ping event source built on a loopback TCP socket pair.
+//!
+//! This is the Windows equivalent of the Unix self-pipe, for when notifications cannot be
+//! posted directly to the poller's completion port.
+
+use crate::{Event, PollMode, Poller, Result, Socket, Source};
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub(super) struct Ping {
+    /// The read end of the socket pair.
+    reader: Socket<TcpStream>,
+
+    /// The write end of the socket pair.
+    writer: Notify,
+
+    /// Whether each event consumes a single notification.
+    semaphore: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Notify(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    /// The write end of the socket pair.
+    writer: TcpStream,
+
+    /// The number of notifications that have not been handled yet.
+    pending: AtomicUsize,
+}
+
+impl Ping {
+    pub(super) fn new(semaphore: bool) -> Result<Self> {
+        let (reader, writer) = socket_pair()?;
+
+        // Draining the socket in counter or edge mode reads until it would block.
+        reader.set_nonblocking(true)?;
+
+        // Notifying should never block, even if the socket buffer is full.
+        writer.set_nonblocking(true)?;
+
+        Ok(Self {
+            reader: Socket::new(reader),
+            writer: Notify(Arc::new(Inner {
+                writer,
+                pending: AtomicUsize::new(0),
+            })),
+            semaphore,
+        })
+    }
+
+    #[cfg(unix)]
+    pub(super) fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        use std::os::unix::io::AsFd;
+        self.reader.socket().as_fd()
+    }
+
+    #[cfg(windows)]
+    pub(super) fn as_socket(&self) -> std::os::windows::io::BorrowedSocket<'_> {
+        use std::os::windows::io::AsSocket;
+        self.reader.socket().as_socket()
+    }
+
+    pub(super) fn notify(&self) -> &Notify {
+        &self.writer
+    }
+
+    pub(super) fn set_pipe_capacity(&self, _capacity: usize) -> Result<()> {
+        // There is no pipe to resize.
+        Ok(())
+    }
+
+    pub(super) fn pending(&self) -> usize {
+        self.writer.0.pending.load(Ordering::Acquire)
+    }
+
+    pub(super) fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        self.reader.register(poller, interest, mode)
+    }
+
+    pub(super) fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        self.reader.reregister(poller, interest, mode)
+    }
+
+    pub(super) fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.reader.deregister(poller)
+    }
+
+    pub(super) fn event_key(&self) -> Option<usize> {
+        self.reader.key()
+    }
+
+    pub(super) fn registration(&self) -> Option<(Event, PollMode)> {
+        self.reader.registration()
+    }
+
+    pub(super) fn is_for(&self, poller: &Poller, event: &Event) -> bool {
+        self.reader.is_for_in(poller, event)
+    }
+
+    /// Handle an event, returning the number of notifications consumed.
+    pub(super) fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<u64> {
+        let consumed = self.drain(super::is_edge(self.reader.mode()))?;
+        self.writer.consume(consumed);
+
+        self.reader.handle_event(poller, event)?;
+        Ok(consumed)
+    }
+
+    /// Drop every pending notification by emptying the socket.
+    pub(super) fn clear(&mut self) -> Result<()> {
+        let consumed = self.drain(true)?;
+        self.writer.consume(consumed);
+        Ok(())
+    }
+
+    /// Drain the socket, returning the number of notifications consumed.
+    ///
+    /// In semaphore mode a single notification is consumed, unless we are in edge mode and
+    /// have to empty the socket.
+    fn drain(&self, edge: bool) -> Result<u64> {
+        let mut reader = self.reader.socket();
+
+        if self.semaphore && !edge {
+            // A spurious wakeup leaves nothing to drain.
+            loop {
+                match reader.read(&mut [0u8]) {
+                    Ok(n) => return Ok(n as u64),
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(0),
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+        }
+
+        let mut buf = [0u8; 64];
+        let mut consumed = 0;
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => consumed += n as u64,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(consumed)
+    }
+}
+
+impl Notify {
+    /// Get an id that is shared by every clone of this notifier.
+    pub(super) fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
+    }
+
+    /// Get the number of notifications that have not been handled yet.
+    pub(super) fn pending(&self) -> usize {
+        self.0.pending.load(Ordering::Acquire)
+    }
+
+    pub(super) fn notify(&self) -> Result<bool> {
+        self.notify_n(1)
+    }
+
+    /// Add `count` notifications, writing one byte for each.
+    ///
+    /// Returns `false` if the socket's buffer filled up first. The socket is readable then, so
+    /// the notifications that did not fit are merged into the pending ones.
+    pub(super) fn notify_n(&self, count: usize) -> Result<bool> {
+        // Count the notifications before they can be observed by the reader.
+        self.0.pending.fetch_add(count, Ordering::AcqRel);
+
+        let buf = [0u8; 64];
+        let mut remaining = count;
+        while remaining > 0 {
+            match (&self.0.writer).write(&buf[..remaining.min(buf.len())]) {
+                Ok(n) => remaining -= n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.0.pending.fetch_sub(remaining, Ordering::AcqRel);
+                    return if err.kind() == io::ErrorKind::WouldBlock {
+                        Ok(false)
+                    } else {
+                        Err(err)
+                    };
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Mark the given number of notifications as handled.
+    fn consume(&self, consumed: u64) {
+        let _ = self
+            .0
+            .pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                Some(pending.saturating_sub(consumed as usize))
+            });
+    }
+}
+
+/// Create a pair of connected loopback TCP sockets.
+fn socket_pair() -> Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let writer = TcpStream::connect(listener.local_addr()?)?;
+
+    // Make sure the connection we accept is the one we just made.
+    loop {
+        let (reader, addr) = listener.accept()?;
+        if addr == writer.local_addr()? {
+            writer.set_nodelay(true)?;
+            return Ok((reader, writer));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! An event source that is notified when a child process exits.
+
+use crate::{Event, PollMode, Poller, Result, Socket, Source};
+
+use rustix::fd::OwnedFd;
+use rustix::process::{pidfd_open, waitid, PidfdFlags, WaitId, WaitidOptions, WaitidStatus};
+
+use std::os::unix::io::AsFd;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use std::sync::Arc;
+
+pub use rustix::process::Pid;
+
+/// An event source that becomes readable once a child process exits.
+///
+/// This is built on a pidfd, which becomes readable exactly once. When the event is handled
+/// the child is reaped and the source deregisters itself from the poller.
+#[derive(Debug)]
+pub struct ChildExit {
+    /// The pidfd for the child process.
+    pidfd: Socket<OwnedFd>,
+
+    /// The exit status, once the child has been reaped.
+    status: Option<ExitStatus>,
+}
+
+impl ChildExit {
+    /// Creates a new event source for the child process with the given ID.
+    ///
+    /// The process must be a child of the current process. Once this source reaps it,
+    /// waiting on the corresponding [`std::process::Child`] will fail.
+    pub fn new(pid: Pid) -> Result<Self> {
+        let pidfd = pidfd_open(pid, PidfdFlags::empty())?;
+
+        Ok(Self {
+            pidfd: Socket::new(pidfd),
+            status: None,
+        })
+    }
+
+    /// Get the exit status of the child process, if it has exited.
+    pub fn status(&self) -> Option<ExitStatus> {
+        self.status
+    }
+}
+
+impl Source for ChildExit {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.pidfd.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.pidfd.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.pidfd.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, interest: Event) -> Result<()> {
+        // Don't reap the child for an event that was meant for another source.
+        if self.status.is_some() || !self.pidfd.is_for_in(poller, &interest) {
+            return Ok(());
+        }
+
+        let id = WaitId::PidFd(self.pidfd.socket().as_fd());
+        let status = match waitid(id, WaitidOptions::EXITED | WaitidOptions::NOHANG)? {
+            Some(status) => status,
+            None => return Ok(()),
+        };
+
+        self.status = Some(exit_status(&status));
+
+        // The pidfd will never fire again.
+        self.pidfd.deregister(poller)
+    }
+}
+
+/// Convert the status reported by `waitid` into an `ExitStatus`.
+fn exit_status(status: &WaitidStatus) -> ExitStatus {
+    let raw = if let Some(code) = status.exit_status() {
+        (code & 0xff) << 8
+    } else if let Some(signal) = status.terminating_signal() {
+        if status.dumped() {
+            signal | 0x80
+        } else {
+            signal
+        }
+    } else {
+        0
+    };
+
+    ExitStatus::from_raw(raw as i32)
+}
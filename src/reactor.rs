@@ -0,0 +1,218 @@
+//! An event loop that dispatches events to registered sources.
+
+use crate::{is_secondary_key, primary_key, BoxSource, Event, PollMode, Poller, Result, Source};
+
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+/// Owns a [`Poller`] and dispatches its events to the sources registered into it.
+pub struct Reactor {
+    /// The underlying poller.
+    poller: Arc<Poller>,
+
+    /// The registered sources, keyed by their event key.
+    sources: HashMap<usize, BoxSource>,
+
+    /// The buffer of events returned by the poller.
+    events: Vec<Event>,
+
+    /// Whether to rotate the order events are dispatched in.
+    fair: bool,
+
+    /// The number of polls that have dispatched events, used to pick where to start.
+    rotation: usize,
+}
+
+/// A handle that wakes up a [`Reactor`] from another thread.
+///
+/// This is returned by [`Reactor::waker`].
+#[derive(Debug, Clone)]
+pub struct ReactorWaker {
+    /// The poller of the reactor.
+    poller: Weak<Poller>,
+}
+
+impl ReactorWaker {
+    /// Wake up the reactor.
+    ///
+    /// If the reactor is waiting in [`Reactor::poll`], it returns without dispatching any
+    /// events. Otherwise its next call to `poll` returns right away. Waking a reactor whose
+    /// poller has been dropped does nothing.
+    pub fn wake(&self) -> Result<()> {
+        match self.poller.upgrade() {
+            Some(poller) => poller.notify(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for Reactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reactor")
+            .field("poller", &self.poller)
+            .field("keys", &self.sources.keys())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Reactor {
+    /// Creates a new reactor with a new poller.
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_poller(Arc::new(Poller::new()?)))
+    }
+
+    /// Creates a new reactor around an existing poller.
+    pub fn with_poller(poller: Arc<Poller>) -> Self {
+        Self {
+            poller,
+            sources: HashMap::new(),
+            events: Vec::new(),
+            fair: false,
+            rotation: 0,
+        }
+    }
+
+    /// Get a reference to the underlying poller.
+    pub fn poller(&self) -> &Arc<Poller> {
+        &self.poller
+    }
+
+    /// Get a handle that wakes up the reactor from another thread.
+    ///
+    /// This can be used to break out of [`Reactor::poll`] to inject work or shut down.
+    pub fn waker(&self) -> ReactorWaker {
+        ReactorWaker {
+            poller: Arc::downgrade(&self.poller),
+        }
+    }
+
+    /// Set whether events are dispatched fairly.
+    ///
+    /// By default events are dispatched in the order the poller returned them. In fair mode,
+    /// each poll starts dispatching one event further into the ready set, so sources that are
+    /// always ready cannot keep the others waiting behind them.
+    pub fn set_fair(&mut self, fair: bool) {
+        self.fair = fair;
+    }
+
+    /// Register a source into the reactor under the given key.
+    ///
+    /// The key of `interest` is replaced with `key`. Returns an error if a source is already
+    /// registered under `key`, or if `key` is a [secondary key](crate::secondary_key).
+    pub fn insert(
+        &mut self,
+        key: usize,
+        source: impl Source + Send + 'static,
+        mut interest: Event,
+        mode: PollMode,
+    ) -> Result<()> {
+        if is_secondary_key(key) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "secondary keys cannot be used to insert sources",
+            ));
+        }
+
+        let entry = match self.sources.entry(key) {
+            Entry::Occupied(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "a source is already registered under this key",
+                ))
+            }
+            Entry::Vacant(entry) => entry,
+        };
+
+        let mut source: BoxSource = Box::new(source);
+        interest.key = key;
+        source.register(&self.poller, interest, mode)?;
+        entry.insert(source);
+
+        Ok(())
+    }
+
+    /// Deregister the source under the given key and return it.
+    ///
+    /// If deregistering the source fails, it stays in the reactor under its key and the error
+    /// is returned.
+    pub fn remove(&mut self, key: usize) -> Result<Option<BoxSource>> {
+        match self.sources.remove(&key) {
+            Some(mut source) => match source.deregister(&self.poller) {
+                Ok(()) => Ok(Some(source)),
+                Err(err) => {
+                    self.sources.insert(key, source);
+                    Err(err)
+                }
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Deregister every source and return them, ordered by their keys.
+    ///
+    /// Every source is deregistered even if deregistering another one fails. In that case the
+    /// first error is returned once all of them have been deregistered.
+    pub fn shutdown(mut self) -> Result<Vec<(usize, BoxSource)>> {
+        let mut sources = self.sources.drain().collect::<Vec<_>>();
+        sources.sort_by_key(|(key, _)| *key);
+
+        let mut result = Ok(());
+        for (_, source) in &mut sources {
+            if let Err(err) = source.deregister(&self.poller) {
+                result = result.and(Err(err));
+            }
+        }
+
+        result.map(|()| sources)
+    }
+
+    /// Wait for events and dispatch them to their sources.
+    ///
+    /// Returns the number of events that were dispatched. Every event is dispatched even if a
+    /// source fails to handle its event, so one failing source cannot starve the others. The
+    /// first error is returned once all events have been dispatched. If a source has a
+    /// [pending event](Source::pending_event), the poller is checked without blocking.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> Result<usize> {
+        self.events.clear();
+
+        // Sources with an event ready don't need to wait for the poller.
+        let ready = self
+            .sources
+            .values()
+            .filter_map(|source| source.pending_event())
+            .collect::<Vec<_>>();
+        let timeout = if ready.is_empty() {
+            timeout
+        } else {
+            Some(Duration::ZERO)
+        };
+        self.poller.wait(&mut self.events, timeout)?;
+        self.events.extend(ready);
+
+        // Pick the event to start dispatching from.
+        let len = self.events.len();
+        let start = if self.fair && len > 0 {
+            self.rotation = self.rotation.wrapping_add(1);
+            self.rotation % len
+        } else {
+            0
+        };
+
+        let mut dispatched = 0;
+        let mut result = Ok(());
+        for i in 0..len {
+            let event = self.events[(start + i) % len];
+            if let Some(source) = self.sources.get_mut(&primary_key(event.key)) {
+                if let Err(err) = source.handle_event(&self.poller, event) {
+                    result = result.and(Err(err));
+                }
+                dispatched += 1;
+            }
+        }
+
+        result.map(|()| dispatched)
+    }
+}
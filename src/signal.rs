@@ -0,0 +1,133 @@
+//! An event source that receives Unix signals through a `signalfd`.
+
+use crate::{Event, PollMode, Poller, RecordReader, Result, Socket, Source};
+
+use nix::errno::Errno;
+use nix::sys::signal::SigSet;
+use nix::sys::signalfd::{siginfo, SfdFlags, SignalFd};
+use nix::unistd::read;
+
+use std::collections::VecDeque;
+use std::convert::{TryFrom, TryInto};
+use std::io;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::sync::Arc;
+
+pub use nix::sys::signal::Signal;
+
+/// The size of a `signalfd_siginfo` record.
+const SIGINFO_LEN: usize = mem::size_of::<siginfo>();
+
+/// An event source that becomes readable when one of a set of signals is delivered.
+///
+/// The signals are blocked in the thread that creates this source, so they are only
+/// delivered through the `signalfd`. Signals should be blocked in every other thread as
+/// well, or the kernel may deliver them to a thread that does not block them instead.
+///
+/// Like any other pending signal, a non-realtime signal that is raised several times before
+/// it is handled is only reported once.
+#[derive(Debug)]
+pub struct Signals {
+    /// The underlying signalfd.
+    signalfd: Socket<SignalFd>,
+
+    /// Bytes that have been read but not yet parsed into signals.
+    records: RecordReader,
+
+    /// Signals that have been read but not yet returned.
+    pending: VecDeque<Signal>,
+}
+
+impl Signals {
+    /// Creates a new signal source for the given signals.
+    ///
+    /// This blocks the signals in the calling thread.
+    pub fn new(signals: &[Signal]) -> Result<Self> {
+        let mut mask = SigSet::empty();
+        for &signal in signals {
+            mask.add(signal);
+        }
+
+        mask.thread_block().map_err(io::Error::from)?;
+
+        // The fd must be non-blocking so that draining it terminates.
+        let signalfd = SignalFd::with_flags(&mask, SfdFlags::SFD_NONBLOCK | SfdFlags::SFD_CLOEXEC)
+            .map_err(io::Error::from)?;
+
+        Ok(Self {
+            signalfd: Socket::new(signalfd),
+            records: RecordReader::new(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Read every signal that is currently pending on the signalfd.
+    ///
+    /// A read that ends partway through a `signalfd_siginfo` keeps the partial record until
+    /// the rest of it is read.
+    fn drain(&mut self) -> Result<()> {
+        let mut chunk = [0u8; SIGINFO_LEN * 16];
+
+        loop {
+            match read(self.signalfd.socket().as_raw_fd(), &mut chunk) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.records.extend(&chunk[..n]);
+                    self.parse();
+                }
+                Err(Errno::EINTR) => continue,
+                Err(Errno::EAGAIN) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse every complete `signalfd_siginfo` out of the buffer.
+    fn parse(&mut self) {
+        while let Some(record) = self.records.next_record(|_| Some(SIGINFO_LEN)) {
+            // `ssi_signo` is the first field.
+            let signo = u32::from_ne_bytes(record[..4].try_into().unwrap());
+            if let Ok(signal) = Signal::try_from(signo as i32) {
+                self.pending.push_back(signal);
+            }
+        }
+    }
+}
+
+impl Iterator for Signals {
+    type Item = Signal;
+
+    /// Get the next signal that was received, if any.
+    ///
+    /// Signals are only read from the signalfd in `handle_event`.
+    fn next(&mut self) -> Option<Signal> {
+        self.pending.pop_front()
+    }
+}
+
+impl Source for Signals {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.signalfd.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.signalfd.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.signalfd.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, interest: Event) -> Result<()> {
+        // Don't read the signals for an event that was meant for another source.
+        if !self.signalfd.is_for_in(poller, &interest) {
+            return Ok(());
+        }
+
+        self.drain()?;
+        self.signalfd.handle_event(poller, interest)
+    }
+}
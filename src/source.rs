@@ -0,0 +1,378 @@
+//! Wrappers that change how a [`Source`] is registered or waited on.
+
+use crate::{
+    is_secondary_key, primary_key, secondary_key, Event, PollMode, Poller, Result, Source,
+};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+
+/// A [`Source`] that re-registers itself after every event it handles.
+///
+/// This gives a source registered in [`PollMode::Oneshot`] level-like behavior without calling
+/// [`Source::reregister`] by hand, which is useful on backends that only support oneshot
+/// registrations.
+#[derive(Debug)]
+pub struct AutoRearm<S: Source> {
+    /// The wrapped source.
+    source: S,
+
+    /// The interest and mode the source was last registered with.
+    registration: Option<(Event, PollMode)>,
+
+    /// The poller the source is registered into.
+    poller: Weak<Poller>,
+}
+
+impl<S: Source> AutoRearm<S> {
+    /// Wraps a source that has not been registered yet.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            registration: None,
+            poller: Weak::new(),
+        }
+    }
+
+    /// Get a reference to the wrapped source.
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    /// Get a mutable reference to the wrapped source.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Unwrap the source.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: Source> Source for AutoRearm<S> {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.registration
+    }
+
+    fn pending_event(&self) -> Option<Event> {
+        self.source.pending_event()
+    }
+
+    fn default_interest(&self) -> Option<(Event, PollMode)> {
+        self.source.default_interest()
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.source.register(poller, interest, mode)?;
+        self.registration = Some((interest, mode));
+        self.poller = Arc::downgrade(poller);
+        Ok(())
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.source.reregister(poller, interest, mode)?;
+        self.registration = Some((interest, mode));
+        self.poller = Arc::downgrade(poller);
+        Ok(())
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.source.deregister(poller)?;
+        self.registration = None;
+        self.poller = Weak::new();
+        Ok(())
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.handle_event_progress(poller, event).map(drop)
+    }
+
+    fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+        let progress = self.source.handle_event_progress(poller, event)?;
+
+        // Only re-arm for our own events, so other sources' events don't re-arm us early.
+        let (interest, mode) = match self.registration {
+            Some((interest, mode)) if interest.key == primary_key(event.key) => (interest, mode),
+            _ => return Ok(progress),
+        };
+
+        // Re-registering needs the owned poller, which we kept when we were registered.
+        match self.poller.upgrade() {
+            Some(registered) if std::ptr::eq(&*registered, poller) => {
+                self.source.reregister(&registered, interest, mode)?;
+            }
+            _ => {}
+        }
+
+        Ok(progress)
+    }
+}
+
+/// A [`Source`] that is known by one key on the outside and another on the inside.
+///
+/// This lets a component hand out a single key while the source it wraps keeps its own. The
+/// wrapped source is registered with the outer key `to`, so the events the poller reports are
+/// routed to it like those of any other source. Events that carry the inner key `from`, such
+/// as those produced by the component itself, are translated to the outer key before the
+/// wrapped source sees them, and so are the interests and events it reports. Use
+/// [`Remap::map_event`] to translate such events ahead of time.
+#[derive(Debug)]
+pub struct Remap<S> {
+    /// The wrapped source.
+    inner: S,
+
+    /// The key the wrapped source uses.
+    from: usize,
+
+    /// The key the outside uses.
+    to: usize,
+}
+
+impl<S> Remap<S> {
+    /// Wraps a source that uses the key `from`, so that it is known by the key `to`.
+    pub fn new(inner: S, from: usize, to: usize) -> Self {
+        Self { inner, from, to }
+    }
+
+    /// Get a reference to the wrapped source.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the wrapped source.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Unwrap the source.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Rewrite an event that carries the inner key to carry the outer key.
+    ///
+    /// Events for other keys, including the events the poller reports for the wrapped source,
+    /// are returned unchanged.
+    pub fn map_event(&self, event: Event) -> Event {
+        Self::rewrite(event, self.from, self.to)
+    }
+
+    /// Rewrite an event's key from `from` to `to`, leaving other keys alone.
+    ///
+    /// The [secondary key](crate::secondary_key) of `from` is rewritten to that of `to`.
+    fn rewrite(mut event: Event, from: usize, to: usize) -> Event {
+        if primary_key(event.key) == from {
+            event.key = if is_secondary_key(event.key) {
+                secondary_key(to)
+            } else {
+                to
+            };
+        }
+        event
+    }
+}
+
+impl<S: Source> Source for Remap<S> {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.inner
+            .registration()
+            .map(|(interest, mode)| (self.map_event(interest), mode))
+    }
+
+    fn pending_event(&self) -> Option<Event> {
+        self.inner
+            .pending_event()
+            .map(|event| self.map_event(event))
+    }
+
+    fn default_interest(&self) -> Option<(Event, PollMode)> {
+        self.inner
+            .default_interest()
+            .map(|(interest, mode)| (self.map_event(interest), mode))
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        let interest = self.map_event(interest);
+        self.inner.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        let interest = self.map_event(interest);
+        self.inner.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.inner.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        let event = self.map_event(event);
+        self.inner.handle_event(poller, event)
+    }
+
+    fn handle_event_progress(&mut self, poller: &Poller, event: Event) -> Result<bool> {
+        let event = self.map_event(event);
+        self.inner.handle_event_progress(poller, event)
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        S::supports_mode(poller, mode)
+    }
+}
+
+/// Wait for the first event of a source in async code.
+///
+/// The source is registered into `poller` right away, and the wait is split into two halves
+/// that share it. The [`NextEventSource`] is handed to the loop that already drives the poller,
+/// such as [`dispatch`](crate::dispatch), like any other source. The [`NextEvent`] future is
+/// awaited by a task, possibly on another thread. Once the loop hands the source its event,
+/// the source is deregistered and the task is woken up with the event. Dropping the future
+/// before the event arrives deregisters the source as well.
+pub fn next_event<S: Source>(
+    mut source: S,
+    poller: &Arc<Poller>,
+    interest: Event,
+    mode: PollMode,
+) -> Result<(NextEventSource<S>, NextEvent<S>)> {
+    source.register(poller, interest, mode)?;
+
+    let shared = Arc::new(Mutex::new(Shared {
+        source,
+        poller: poller.clone(),
+        state: State::Waiting(None),
+    }));
+    let source = NextEventSource {
+        shared: shared.clone(),
+    };
+    let future = NextEvent { shared };
+
+    Ok((source, future))
+}
+
+/// The half of a [`next_event`] wait that is driven by the poll loop.
+#[derive(Debug)]
+pub struct NextEventSource<S: Source> {
+    shared: Arc<Mutex<Shared<S>>>,
+}
+
+/// A future that resolves to the first event of a source, returned by [`next_event`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct NextEvent<S: Source> {
+    shared: Arc<Mutex<Shared<S>>>,
+}
+
+#[derive(Debug)]
+struct Shared<S> {
+    /// The source to wait on.
+    source: S,
+
+    /// The poller the source is registered into.
+    poller: Arc<Poller>,
+
+    /// How far along the wait is.
+    state: State,
+}
+
+#[derive(Debug)]
+enum State {
+    /// The source is registered, and the task to wake once its event arrives.
+    Waiting(Option<Waker>),
+
+    /// The event arrived, but the future has not returned it yet.
+    Ready(Event),
+
+    /// The future has returned the event or was dropped.
+    Done,
+}
+
+impl<S: Source> Future for NextEvent<S> {
+    type Output = Event;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = lock(&self.shared);
+
+        match std::mem::replace(&mut shared.state, State::Done) {
+            State::Waiting(_) => {
+                shared.state = State::Waiting(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+            State::Ready(event) => Poll::Ready(event),
+            State::Done => panic!("`NextEvent` polled after completion"),
+        }
+    }
+}
+
+impl<S: Source> Drop for NextEvent<S> {
+    fn drop(&mut self) {
+        let shared = &mut *lock(&self.shared);
+        if let State::Waiting(_) = shared.state {
+            shared.source.deregister(&shared.poller).ok();
+        }
+        shared.state = State::Done;
+    }
+}
+
+impl<S: Source> Source for NextEventSource<S> {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        lock(&self.shared).source.registration()
+    }
+
+    fn pending_event(&self) -> Option<Event> {
+        let shared = lock(&self.shared);
+        match shared.state {
+            State::Waiting(_) => shared.source.pending_event(),
+            _ => None,
+        }
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        lock(&self.shared).source.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        lock(&self.shared).source.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        lock(&self.shared).source.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        let mut shared = lock(&self.shared);
+
+        // Only the first event of the wait's own registration completes it.
+        let ours = matches!(
+            shared.source.registration(),
+            Some((interest, _)) if interest.key == primary_key(event.key)
+        );
+        if !ours || !matches!(shared.state, State::Waiting(_)) {
+            return shared.source.handle_event(poller, event);
+        }
+
+        let result = shared.source.handle_event(poller, event);
+        let result = shared.source.deregister(poller).and(result);
+        let waker = match std::mem::replace(&mut shared.state, State::Ready(event)) {
+            State::Waiting(waker) => waker,
+            _ => None,
+        };
+        drop(shared);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+
+        result
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        S::supports_mode(poller, mode)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
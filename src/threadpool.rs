@@ -1,17 +1,33 @@
 //! Access to a thread pool.
 
-use crate::future::{PollFuture, PollRead, PollWrite};
+use crate::future::{PollFuture, PollRead, PollSeek, PollWrite};
 use crate::{Event, PollMode, Poller, Result, Source};
 
 use blocking::{Task, Unblock};
 
-use std::io;
-use std::sync::Arc;
-use std::task::Poll;
+use std::future::Future;
+use std::io::{self, IoSlice, IoSliceMut, SeekFrom};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+#[cfg(feature = "timer")]
+use crate::timer::{Timer, TimerWheel};
+#[cfg(feature = "timer")]
+use crate::{is_secondary_key, secondary_interest};
+#[cfg(feature = "timer")]
+use std::time::{Duration, Instant};
 
 /// Wait for a function to complete in a thread pool.
 pub struct UnblockFn<T> {
-    inner: PollFuture<Task<T>>,
+    inner: PollFuture<Work<T>>,
+
+    /// The output of the function, if it completed while handling an event.
+    output: Option<T>,
+
+    /// Whether the task has completed.
+    completed: bool,
 }
 
 impl<T: Send + 'static> UnblockFn<T> {
@@ -21,14 +37,166 @@ impl<T: Send + 'static> UnblockFn<T> {
         F: FnOnce() -> T + Send + 'static,
     {
         let task = blocking::unblock(f);
+        Self::from_work(Work::Task(task))
+    }
+
+    /// Create a new `UnblockFn` that runs the given function with a custom spawner.
+    ///
+    /// `spawn` is called once with a job that runs `f`, and should run it on a thread of its
+    /// choosing, such as a dedicated thread pool. This keeps the work off the global pool used
+    /// by [`UnblockFn::new`].
+    ///
+    /// If the job is dropped without being run, or `f` panics, the function never completes.
+    pub fn with_spawner<F, S>(spawn: S, f: F) -> Result<Self>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        S: FnOnce(Box<dyn FnOnce() + Send>),
+    {
+        let slot = Arc::new(Mutex::new(Slot {
+            output: None,
+            waker: None,
+        }));
+
+        let job = {
+            let slot = slot.clone();
+            move || {
+                let output = f();
+                let waker = {
+                    let mut slot = slot.lock().unwrap_or_else(|x| x.into_inner());
+                    slot.output = Some(output);
+                    slot.waker.take()
+                };
+
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            }
+        };
+        spawn(Box::new(job));
+
+        Self::from_work(Work::Spawned(slot))
+    }
+
+    fn from_work(work: Work<T>) -> Result<Self> {
         Ok(Self {
-            inner: PollFuture::new(task)?,
+            inner: PollFuture::new(work)?,
+            output: None,
+            completed: false,
         })
     }
 
     /// Get the result of the function.
+    ///
+    /// Once the result has been returned, this returns `Poll::Pending`.
     pub fn result(&mut self) -> Poll<T> {
-        self.inner.poll_unpin()
+        if let Some(output) = self.output.take() {
+            return Poll::Ready(output);
+        }
+
+        if self.completed {
+            return Poll::Pending;
+        }
+
+        let output = self.inner.poll_unpin();
+        self.completed = output.is_ready();
+        output
+    }
+
+    /// Cancel the function, returning its result if it has already completed.
+    ///
+    /// If the function has not started yet it will never run, unless it was handed to a custom
+    /// spawner. If it is currently running, it runs to completion but its result is discarded.
+    pub fn cancel(mut self) -> Option<T> {
+        match self.result() {
+            Poll::Ready(output) => Some(output),
+
+            // Dropping the task cancels it.
+            Poll::Pending => None,
+        }
+    }
+
+    /// Let the function run to completion in the background, discarding its result.
+    ///
+    /// Dropping an `UnblockFn` cancels the function if it has not started yet. After this call
+    /// the function runs even though the handle is gone. Functions handed to a custom spawner
+    /// are always run by that spawner.
+    pub fn detach(mut self) {
+        let placeholder = Work::Spawned(Arc::new(Mutex::new(Slot {
+            output: None,
+            waker: None,
+        })));
+
+        if let Work::Task(task) = std::mem::replace(self.inner.future_mut(), placeholder) {
+            task.detach();
+        }
+    }
+
+    /// Block the current thread until the function completes, returning its result.
+    ///
+    /// This does not need the poller, which makes it useful on shutdown paths.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the result was already returned by [`UnblockFn::result`].
+    pub fn join(mut self) -> T {
+        if let Some(output) = self.output.take() {
+            return output;
+        }
+
+        assert!(!self.completed, "the result has already been taken");
+        block_on(self.inner.future_mut())
+    }
+
+    /// Poll the task, storing its output if it has completed.
+    ///
+    /// Returns the error that occurred while waking up the poll loop, if any.
+    fn poll_output(&mut self) -> Result<()> {
+        if !self.completed {
+            if let Poll::Ready(output) = self.inner.try_poll_unpin()? {
+                self.output = Some(output);
+                self.completed = true;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The work behind an [`UnblockFn`].
+enum Work<T> {
+    /// A task in the global thread pool.
+    Task(Task<T>),
+
+    /// A job that was handed to a custom spawner.
+    Spawned(Arc<Mutex<Slot<T>>>),
+}
+
+/// Where a job from a custom spawner stores its output.
+struct Slot<T> {
+    /// The output of the function, once it has completed.
+    output: Option<T>,
+
+    /// The waker to wake once the output is stored.
+    waker: Option<Waker>,
+}
+
+impl<T> Future for Work<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        match self.get_mut() {
+            Work::Task(task) => Pin::new(task).poll(cx),
+            Work::Spawned(slot) => {
+                let mut slot = slot.lock().unwrap_or_else(|x| x.into_inner());
+                match slot.output.take() {
+                    Some(output) => Poll::Ready(output),
+                    None => {
+                        slot.waker = Some(cx.waker().clone());
+                        Poll::Pending
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -41,52 +209,328 @@ impl<T: Send + 'static> Source for UnblockFn<T> {
         self.inner.reregister(poller, interest, mode)
     }
 
-    fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
         self.inner.deregister(poller)
     }
 
-    fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
-        self.inner.handle_event(poller, event)
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.inner.handle_event(poller, event)?;
+
+        // Poll the task so that we are woken up once it completes.
+        self.poll_output()
+    }
+}
+
+/// The function run by an [`UnblockInterval`].
+#[cfg(feature = "timer")]
+type IntervalFn<T> = Box<dyn FnMut() -> T + Send + 'static>;
+
+/// Run a function in a thread pool over and over, waiting for an interval between runs.
+///
+/// The function is not run again until both the previous run has completed and the interval
+/// timer has fired since that run started. Like [`Timer`], the timer has to be put back into
+/// its wheel with [`UnblockInterval::handle_wheel`] after events are handled.
+///
+/// The task is registered with the given interest, and the timer under its
+/// [secondary key](crate::secondary_key).
+#[cfg(feature = "timer")]
+pub struct UnblockInterval<T> {
+    /// The current run of the function.
+    task: PollFuture<Task<(IntervalFn<T>, T)>>,
+
+    /// The function, while it is not running.
+    f: Option<IntervalFn<T>>,
+
+    /// The timer that schedules the next run.
+    timer: Timer,
+
+    /// Whether the timer has fired since the last run started.
+    elapsed: bool,
+
+    /// The output of the last run, if it has not been returned yet.
+    output: Option<T>,
+}
+
+#[cfg(feature = "timer")]
+impl<T: Send + 'static> UnblockInterval<T> {
+    /// Create a new `UnblockInterval` that starts running the function right away.
+    pub fn new<F>(f: F, wheel: &mut TimerWheel, interval: Duration) -> Result<Self>
+    where
+        F: FnMut() -> T + Send + 'static,
+    {
+        Ok(Self {
+            task: PollFuture::new(run(Box::new(f)))?,
+            f: None,
+            timer: match Instant::now().checked_add(interval) {
+                Some(start) => wheel.interval_at(start, interval)?,
+                None => Timer::never()?,
+            },
+            elapsed: false,
+            output: None,
+        })
+    }
+
+    /// Get the output of the last run.
+    ///
+    /// Each output is only returned once.
+    pub fn poll(&mut self) -> Poll<T> {
+        self.poll_task();
+        match self.output.take() {
+            Some(output) => {
+                // The next run may have been waiting for this output to be taken.
+                self.poll_task();
+                Poll::Ready(output)
+            }
+            None => Poll::Pending,
+        }
+    }
+
+    /// Insert the interval timer back into the timer wheel.
+    pub fn handle_wheel(&mut self, wheel: &mut TimerWheel) -> Result<()> {
+        self.timer.handle_wheel(wheel)
+    }
+
+    /// Poll the current run, and start the next one if it is due.
+    fn poll_task(&mut self) {
+        if self.f.is_none() {
+            if let Poll::Ready((f, output)) = self.task.poll_unpin() {
+                self.f = Some(f);
+                self.output = Some(output);
+            }
+        }
+
+        if self.elapsed && self.output.is_none() {
+            if let Some(f) = self.f.take() {
+                self.elapsed = false;
+                *self.task.future_mut() = run(f);
+
+                // Poll the new run so that we are woken up once it completes.
+                if let Poll::Ready((f, output)) = self.task.poll_unpin() {
+                    self.f = Some(f);
+                    self.output = Some(output);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "timer")]
+impl<T: Send + 'static> Source for UnblockInterval<T> {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.task.register(poller, interest, mode)?;
+        if let Err(err) = self
+            .timer
+            .register(poller, secondary_interest(interest), mode)
+        {
+            let _ = self.task.deregister(poller);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.task.reregister(poller, interest, mode)?;
+        self.timer
+            .reregister(poller, secondary_interest(interest), mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        let result = self.task.deregister(poller);
+        self.timer.deregister(poller).and(result)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        if is_secondary_key(event.key) {
+            self.timer.handle_event(poller, event)?;
+            self.elapsed = true;
+        } else {
+            self.task.handle_event(poller, event)?;
+        }
+
+        self.poll_task();
+
+        // The next run is only picked up through our event if its waker got through.
+        match self.task.take_error() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Block the current thread on a future.
+fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+    /// Wakes up a parked thread.
+    struct Unparker(Thread);
+
+    impl Wake for Unparker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unparker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = Pin::new(&mut future).poll(&mut cx) {
+            return output;
+        }
+
+        thread::park();
     }
 }
 
+/// Run the function once in the thread pool, handing it back along with its output.
+#[cfg(feature = "timer")]
+fn run<T: Send + 'static>(mut f: IntervalFn<T>) -> Task<(IntervalFn<T>, T)> {
+    blocking::unblock(move || {
+        let output = f();
+        (f, output)
+    })
+}
+
+/// The size of the buffer that [`UnblockReader::poll_fill`] reads into.
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
 /// Waits for data to be read from a reader in a threadpool.
-pub struct UnblockReader<R>(PollRead<Unblock<R>>);
+pub struct UnblockReader<R> {
+    /// The reader.
+    inner: PollRead<Unblock<R>>,
+
+    /// Data that was read by `poll_fill` and has not been consumed yet, starting at `pos`.
+    buf: Vec<u8>,
+
+    /// The position of the first unconsumed byte in `buf`.
+    pos: usize,
+}
 
 impl<R: io::Read + Send + 'static> UnblockReader<R> {
     /// Create a new `UnblockReader` that will read from the given reader in a threadpool.
     pub fn new(reader: R) -> Result<Self> {
         let unblock = Unblock::new(reader);
-        Ok(Self(PollRead::new(unblock)?))
+        Self::from_unblock(unblock)
     }
 
     /// Create a new `UnblockReader` with a given pipe capacity.
     pub fn with_capacity(reader: R, capacity: usize) -> Result<Self> {
         let unblock = Unblock::with_capacity(capacity, reader);
-        Ok(Self(PollRead::new(unblock)?))
+        Self::from_unblock(unblock)
+    }
+
+    fn from_unblock(unblock: Unblock<R>) -> Result<Self> {
+        Ok(Self {
+            inner: PollRead::new(unblock)?,
+            buf: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Stop reading in the thread pool and get the reader back.
+    ///
+    /// The returned future waits for the task in the thread pool to stop. Data that was
+    /// already read from the reader but not returned yet is discarded.
+    pub fn into_inner(self) -> impl Future<Output = R> {
+        self.inner.into_reader().into_inner()
+    }
+
+    /// Stop reading in the thread pool and get the reader back, blocking until the task in
+    /// the thread pool stops.
+    ///
+    /// This does not need the poller, which makes it useful on shutdown paths. Data that was
+    /// already read from the reader but not returned yet is discarded.
+    pub fn into_inner_blocking(self) -> R {
+        block_on(Box::pin(self.into_inner()))
     }
 
     /// Read from the reader.
     pub fn read(&mut self, buf: &mut [u8]) -> Poll<io::Result<usize>> {
-        self.0.poll_unpin(buf)
+        if self.buffered().is_empty() {
+            return self.inner.poll_unpin(buf);
+        }
+
+        let n = io::Read::read(&mut self.buffered(), buf)?;
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+
+    /// Read from the reader into several buffers.
+    pub fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> Poll<io::Result<usize>> {
+        if self.buffered().is_empty() {
+            return self.inner.poll_read_vectored_unpin(bufs);
+        }
+
+        let n = io::Read::read_vectored(&mut self.buffered(), bufs)?;
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+
+    /// Read until the reader reaches EOF, appending the bytes to `buf`.
+    ///
+    /// Once EOF is reached, returns the number of bytes this call appended to `buf`.
+    pub fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Poll<io::Result<usize>> {
+        let buffered = self.buffered().len();
+        buf.extend_from_slice(self.buffered());
+        self.consume(usize::MAX);
+        self.inner
+            .poll_read_to_end_unpin(buf)
+            .map_ok(|n| buffered + n)
+    }
+
+    /// Get the buffered data, reading more from the reader if the buffer is empty.
+    ///
+    /// An empty slice means the reader has reached EOF. Call [`consume`] to mark bytes as read
+    /// so that they are not returned again.
+    ///
+    /// [`consume`]: Self::consume
+    pub fn poll_fill(&mut self) -> Poll<io::Result<&[u8]>> {
+        if self.buffered().is_empty() {
+            self.buf.resize(READ_BUFFER_SIZE, 0);
+            self.pos = 0;
+
+            match self.inner.poll_unpin(&mut self.buf) {
+                Poll::Ready(Ok(n)) => self.buf.truncate(n),
+                Poll::Ready(Err(err)) => {
+                    self.buf.clear();
+                    return Poll::Ready(Err(err));
+                }
+                Poll::Pending => {
+                    self.buf.clear();
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        Poll::Ready(Ok(self.buffered()))
+    }
+
+    /// Mark `amt` bytes of the buffered data as read.
+    ///
+    /// `amt` is clamped to the amount of data that is buffered.
+    pub fn consume(&mut self, amt: usize) {
+        self.pos = self.pos.saturating_add(amt).min(self.buf.len());
+    }
+
+    /// Get the data that has been buffered but not consumed.
+    fn buffered(&self) -> &[u8] {
+        &self.buf[self.pos..]
     }
 }
 
 impl<R: io::Read + Send + 'static> Source for UnblockReader<R> {
-    fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
-        self.0.deregister(poller)
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.inner.deregister(poller)
     }
 
-    fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
-        self.0.handle_event(poller, event)
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.inner.handle_event(poller, event)
     }
 
     fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
-        self.0.register(poller, interest, mode)
+        self.inner.register(poller, interest, mode)
     }
 
     fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
-        self.0.reregister(poller, interest, mode)
+        self.inner.reregister(poller, interest, mode)
     }
 }
 
@@ -110,14 +554,111 @@ impl<W: io::Write + Send + 'static> UnblockWriter<W> {
     pub fn write(&mut self, buf: &[u8]) -> Poll<io::Result<usize>> {
         self.0.poll_unpin(buf)
     }
+
+    /// Write to the writer from several buffers.
+    pub fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        self.0.poll_write_vectored_unpin(bufs)
+    }
+
+    /// Write the whole buffer to the writer.
+    ///
+    /// `offset` is the number of bytes at the start of `buf` that have already been written,
+    /// and is advanced as more bytes are written, so writing can resume after `Pending`.
+    pub fn poll_write_all(&mut self, buf: &[u8], offset: &mut usize) -> Poll<io::Result<()>> {
+        self.0.poll_write_all_unpin(buf, offset)
+    }
+
+    /// Flush buffered data to the writer.
+    pub fn poll_flush(&mut self) -> Poll<io::Result<()>> {
+        self.0.poll_flush_unpin()
+    }
+
+    /// Wait until everything that was written has reached the underlying writer.
+    ///
+    /// Data written to an `UnblockWriter` passes through a pipe to a task in the thread pool.
+    /// This returns `Ready` once that pipe is empty, the task has written everything to the
+    /// writer and flushed it, and the writer is back in this thread's hands. After that it is
+    /// safe to drop the `UnblockWriter` without losing data. A closed writer is always drained.
+    pub fn poll_drained(&mut self) -> Poll<io::Result<()>> {
+        // Flushing an `Unblock` stops its writing task, which first drains the pipe.
+        self.0.poll_flush_unpin()
+    }
+
+    /// Flush buffered data and close the writer.
+    ///
+    /// Once this returns `Ready(Ok(()))`, everything written has reached the underlying
+    /// writer and it has been dropped, so a peer waiting for the writer to close sees EOF.
+    /// Writing after the writer has been closed returns an error.
+    pub fn poll_close(&mut self) -> Poll<io::Result<()>> {
+        self.0.poll_close_unpin()
+    }
+
+    /// Stop writing in the thread pool and get the writer back.
+    ///
+    /// The returned future waits for the task in the thread pool to write everything and
+    /// flush the writer. Errors from flushing are ignored.
+    ///
+    /// # Panics
+    ///
+    /// The future panics if the writer was closed with [`UnblockWriter::poll_close`], which
+    /// drops the writer.
+    pub fn into_inner(self) -> impl Future<Output = W> {
+        self.0.into_writer().into_inner()
+    }
+
+    /// Stop writing in the thread pool and get the writer back, blocking until everything
+    /// has been written and flushed.
+    ///
+    /// This does not need the poller, which makes it useful on shutdown paths.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the writer was closed with [`UnblockWriter::poll_close`].
+    pub fn into_inner_blocking(self) -> W {
+        block_on(Box::pin(self.into_inner()))
+    }
 }
 
 impl<W: io::Write + Send + 'static> Source for UnblockWriter<W> {
-    fn deregister(&mut self, poller: &Arc<Poller>) -> Result<()> {
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.0.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.0.handle_event(poller, event)
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.0.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.0.reregister(poller, interest, mode)
+    }
+}
+
+/// Waits for a seek to complete on a seeker in a threadpool.
+pub struct UnblockSeek<S>(PollSeek<Unblock<S>>);
+
+impl<S: io::Seek + Send + 'static> UnblockSeek<S> {
+    /// Create a new `UnblockSeek` that will seek the given seeker in a threadpool.
+    pub fn new(seeker: S) -> Result<Self> {
+        let unblock = Unblock::new(seeker);
+        Ok(Self(PollSeek::new(unblock)?))
+    }
+
+    /// Seek to the given position.
+    pub fn seek(&mut self, pos: SeekFrom) -> Poll<io::Result<u64>> {
+        self.0.poll_unpin(pos)
+    }
+}
+
+impl<S: io::Seek + Send + 'static> Source for UnblockSeek<S> {
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
         self.0.deregister(poller)
     }
 
-    fn handle_event(&mut self, poller: &Arc<Poller>, event: Event) -> Result<()> {
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
         self.0.handle_event(poller, event)
     }
 
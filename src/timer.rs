@@ -1,52 +1,199 @@
 //! Timer wheels.
 
+mod alarm;
+
+mod driver;
+pub use driver::TimerDriver;
+
+mod hierarchical;
+pub use hierarchical::HierarchicalTimerWheel;
+
+mod oneshot;
+pub use oneshot::OneShot;
+
+mod shared;
+pub use shared::{SharedTimerWheel, TimerId};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod timerfd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use timerfd::TimerFd;
+
 use crate::ping::{Notifier, Ping};
 use crate::{Event, PollMode, Poller, Result, Source};
 
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io;
 use std::time::{Duration, Instant};
 
 /// A timer wheel that contains timers.
+///
+/// By default the wheel notifies the [`Ping`] of each [`Timer`] when it expires. A wheel with
+/// another [`TimerTarget`] fires that target instead, which lets the expiry logic be reused
+/// with callbacks or queues.
 #[derive(Debug)]
-pub struct TimerWheel {
+pub struct TimerWheel<N = Notifier> {
     /// The timers in the wheel.
-    timers: BTreeMap<(Instant, usize), Notifier>,
+    timers: BTreeMap<(Instant, usize), N>,
 
     /// The last ID that was assigned to a timer.
     last_id: usize,
 }
 
+/// Something that a [`TimerWheel`] or [`HierarchicalTimerWheel`] fires when a timer expires.
+pub trait TimerTarget {
+    /// Fire the target.
+    fn fire(&self) -> Result<()>;
+
+    /// Fire every target in a batch.
+    ///
+    /// By default this fires each target in turn. Every target is fired even if an earlier one
+    /// fails, and the first error is returned.
+    fn fire_all(targets: &[Self]) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let mut result = Ok(());
+        for target in targets {
+            if let Err(err) = target.fire() {
+                result = result.and(Err(err));
+            }
+        }
+        result
+    }
+}
+
+impl TimerTarget for Notifier {
+    fn fire(&self) -> Result<()> {
+        self.notify()
+    }
+
+    fn fire_all(targets: &[Self]) -> Result<()> {
+        Notifier::notify_batch(targets)
+    }
+}
+
+impl<F: Fn() -> Result<()>> TimerTarget for F {
+    fn fire(&self) -> Result<()> {
+        self()
+    }
+}
+
+/// A timer wheel that [`Timer`]s can be inserted back into with [`Timer::handle_wheel`].
+///
+/// This is implemented by [`TimerWheel`] and [`HierarchicalTimerWheel`], and cannot be
+/// implemented outside of this crate.
+pub trait Wheel: __private::WheelSealed {}
+
+impl Wheel for TimerWheel {}
+impl Wheel for HierarchicalTimerWheel {}
+
+mod __private {
+    use super::Notifier;
+    use std::time::Instant;
+
+    #[doc(hidden)]
+    pub trait WheelSealed {
+        /// Insert the notifier for a timer with the given deadline and ID.
+        fn insert(&mut self, deadline: Instant, id: usize, notifier: Notifier);
+    }
+}
+
+/// What happened when a timer wheel fired its expired timers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FireOutcome {
+    /// The number of timers that were fired.
+    pub fired: usize,
+
+    /// When the wheel should be fired again, if it has timers left.
+    ///
+    /// Compute the timeout from this right before waiting on the poller, so that time spent
+    /// in between is not waited twice.
+    pub next: Option<Instant>,
+}
+
+/// How an interval timer schedules its next deadline after it fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntervalPolicy {
+    /// The next deadline is one interval after the previous deadline.
+    ///
+    /// If the timer is handled late, it fires again right away until it has caught up.
+    #[default]
+    FixedRate,
+
+    /// The next deadline is one interval after the timer is handled.
+    ///
+    /// A late timer does not catch up, so there is no burst of fires after a stall.
+    FixedDelay,
+}
+
 /// A timer that can be used to wake up the timer wheel.
+///
+/// Interval timers are best registered in [`PollMode::Level`]. A fire leaves a notification
+/// unless one is still pending, and handling an event consumes one, so a timer that is handled
+/// late has one event waiting rather than one per missed interval. In [`PollMode::Oneshot`], which is the only mode some pollers support, the timer
+/// has to be re-armed with [`Source::rearm`] after each event. Edge-triggered modes merge fires
+/// that happen before the timer is handled, so interval timers refuse to register in them.
 #[derive(Debug)]
 pub struct Timer {
     /// The current ID of the timer.
     id: usize,
 
     /// The underlying ping event source.
-    ping: Ping,
+    ///
+    /// A timer that never fires doesn't create its ping until it is registered, so that it
+    /// doesn't hold on to a file descriptor.
+    ping: Option<Ping>,
 
     /// The timeout of the timer.
     deadline: Option<Instant>,
 
     /// The interval of the timer.
     interval: Duration,
+
+    /// How the next deadline is computed after the timer fires.
+    policy: IntervalPolicy,
 }
 
-impl Default for TimerWheel {
+impl<N> Default for TimerWheel<N> {
     fn default() -> Self {
-        Self::new()
+        Self {
+            timers: BTreeMap::new(),
+            last_id: 1,
+        }
     }
 }
 
-impl TimerWheel {
-    /// Creates a new timer wheel.
-    pub fn new() -> Self {
+impl<N> TimerWheel<N> {
+    /// Creates a new timer wheel that assigns IDs starting at `start`.
+    ///
+    /// This is useful for keeping IDs unique when merging wheels, or across restarts.
+    pub fn with_id_start(start: usize) -> Self {
         Self {
             timers: BTreeMap::new(),
-            last_id: 1,
+            last_id: start,
         }
     }
 
+    /// Remove every pending timer.
+    ///
+    /// IDs keep counting up from where they were, so they are not reused. A [`Timer`] that is
+    /// still around is put back into the wheel by [`Timer::handle_wheel`], so timers that
+    /// should stay cancelled need to be dropped as well.
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+}
+
+impl TimerWheel {
+    /// Creates a new timer wheel.
+    ///
+    /// Use [`TimerWheel::default`] to create a wheel with another [`TimerTarget`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
     /// Create a new timer that fires after the given duration.
     pub fn after(&mut self, duration: Duration) -> Result<Timer> {
         Instant::now()
@@ -60,88 +207,191 @@ impl TimerWheel {
         self.interval_at(deadline, Duration::MAX)
     }
 
-    /// Create a timer that fires on an interval.
+    /// Create a timer that fires right away, and then on an interval.
     pub fn interval(&mut self, interval: Duration) -> Result<Timer> {
         self.interval_at(Instant::now(), interval)
     }
 
-    /// Create a new timer that fires after the given duration, at the given interval.
+    /// Create a timer that fires right away, and then on an interval scheduled by `policy`.
+    pub fn interval_with_policy(
+        &mut self,
+        interval: Duration,
+        policy: IntervalPolicy,
+    ) -> Result<Timer> {
+        self.interval_at_with_policy(Instant::now(), interval, policy)
+    }
+
+    /// Create a timer that fires on an interval, first firing after a random delay of up to
+    /// `jitter`.
+    ///
+    /// Many timers created with the same interval would otherwise fire in bursts. Offsetting
+    /// their first deadlines spreads them out, and since later deadlines follow the first one,
+    /// they stay spread out. The delay is picked by a simple generator seeded with the timer's
+    /// ID, so it is deterministic for a given wheel.
+    pub fn interval_with_jitter(&mut self, interval: Duration, jitter: Duration) -> Result<Timer> {
+        let offset = jitter_offset(self.last_id, jitter);
+        match Instant::now().checked_add(offset) {
+            Some(start) => self.interval_at(start, interval),
+            None => Timer::never(),
+        }
+    }
+
+    /// Create a new timer that first fires at `start`, and then every `interval` after that.
     pub fn interval_at(&mut self, start: Instant, interval: Duration) -> Result<Timer> {
-        // Create a new ping event source.
-        let ping = Ping::new()?;
-        let notifier = ping.notifier();
-
-        // Create a new timer.
-        let timer = Timer {
-            id: self.last_id,
-            ping,
-            deadline: start.checked_add(interval),
-            interval,
-        };
+        self.interval_at_with_policy(start, interval, IntervalPolicy::FixedRate)
+    }
+
+    /// Create a new timer that first fires at `start`, and then on an interval scheduled by
+    /// `policy`.
+    pub fn interval_at_with_policy(
+        &mut self,
+        start: Instant,
+        interval: Duration,
+        policy: IntervalPolicy,
+    ) -> Result<Timer> {
+        let timer = Timer::new(self.last_id, start, interval, policy)?;
         self.last_id += 1;
 
         // Register the timer.
-        if let Some(deadline) = timer.deadline {
-            self.timers.insert((deadline, timer.id), notifier);
-        }
+        self.timers.insert((start, timer.id), timer.notifier());
 
         Ok(timer)
     }
+}
+
+impl<N: TimerTarget> TimerWheel<N> {
+    /// Insert a target that is fired at the given deadline.
+    ///
+    /// Returns the ID assigned to the timer.
+    pub fn insert_at(&mut self, deadline: Instant, target: N) -> usize {
+        let id = self.last_id;
+        self.last_id += 1;
+        self.timers.insert((deadline, id), target);
+        id
+    }
 
     /// Fire all pending timers.
+    ///
+    /// Returns how long until the next timer is due.
     pub fn fire_timers(&mut self) -> Result<Option<Duration>> {
+        let outcome = self.fire_expired()?;
+        Ok(outcome
+            .next
+            .map(|next| next.saturating_duration_since(Instant::now())))
+    }
+
+    /// Fire all pending timers, returning how many fired and when the next one is due.
+    pub fn fire_expired(&mut self) -> Result<FireOutcome> {
         // Get the current time.
         let now = Instant::now();
-        let mut notifiers = vec![];
+        let mut targets = vec![];
 
         // Get all timers that have expired.
         let mut expired = self.timers.split_off(&(now, 0));
         std::mem::swap(&mut self.timers, &mut expired);
 
         // Fire all expired timers.
-        notifiers.extend(expired.into_values());
-
-        // See how long we need to wait for the next timer.
-        let next = if self.timers.is_empty() {
-            None
-        } else {
-            Some(
-                self.timers
-                    .keys()
-                    .next()
-                    .unwrap()
-                    .0
-                    .saturating_duration_since(now),
-            )
-        };
+        targets.extend(expired.into_values());
+
+        // See when the next timer is due.
+        let next = self.timers.keys().next().map(|&(deadline, _)| deadline);
 
         // Notify all expired timers.
-        for notifier in notifiers {
-            notifier.notify()?;
-        }
+        N::fire_all(&targets)?;
+
+        Ok(FireOutcome {
+            fired: targets.len(),
+            next,
+        })
+    }
+}
 
-        Ok(next)
+/// Pick a delay in `[0, jitter)` for the timer with the given ID.
+fn jitter_offset(id: usize, jitter: Duration) -> Duration {
+    let range = u64::try_from(jitter.as_nanos()).unwrap_or(u64::MAX);
+    if range == 0 {
+        return Duration::ZERO;
+    }
+
+    // SplitMix64, which scatters consecutive IDs.
+    let mut z = (id as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+
+    Duration::from_nanos(z % range)
+}
+
+impl __private::WheelSealed for TimerWheel {
+    fn insert(&mut self, deadline: Instant, id: usize, notifier: Notifier) {
+        self.timers.insert((deadline, id), notifier);
     }
 }
 
 impl Timer {
+    /// Create a timer that first fires at `start`, and then every `interval` after that.
+    fn new(id: usize, start: Instant, interval: Duration, policy: IntervalPolicy) -> Result<Self> {
+        Ok(Self {
+            id,
+            ping: Some(Ping::new()?),
+            deadline: Some(start),
+            interval,
+            policy,
+        })
+    }
+
     /// Create a timer that never fires.
+    ///
+    /// No file descriptor is used until the timer is registered into a poller.
     pub fn never() -> Result<Self> {
         Ok(Self {
             id: 0,
-            ping: Ping::new()?,
+            ping: None,
             deadline: None,
             interval: Duration::MAX,
+            policy: IntervalPolicy::FixedRate,
         })
     }
 
+    /// Get how long until this timer fires next, or `None` if it never will.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the deadline of this timer has passed.
+    ///
+    /// A timer that never fires is never expired.
+    pub fn is_expired(&self) -> bool {
+        matches!(self.deadline, Some(deadline) if deadline <= Instant::now())
+    }
+
+    /// Create a notifier for the ping of a timer that fires.
+    pub(crate) fn notifier(&self) -> Notifier {
+        self.ping
+            .as_ref()
+            .expect("timers that fire always have a ping")
+            .notifier()
+    }
+
+    /// Make sure this timer can produce one event per fire in the given mode.
+    fn check_mode(&self, mode: PollMode) -> Result<()> {
+        let interval = self.interval != Duration::MAX;
+        if interval && matches!(mode, PollMode::Edge | PollMode::EdgeOneshot) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "interval timers cannot be registered in an edge-triggered mode",
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Insert this timer back into the timer wheel.
-    pub fn handle_wheel(&mut self, wheel: &mut TimerWheel) -> Result<()> {
+    pub fn handle_wheel(&mut self, wheel: &mut impl Wheel) -> Result<()> {
         // Re-insert the timer into the wheel.
         if let Some(deadline) = self.deadline {
-            wheel
-                .timers
-                .insert((deadline, self.id), self.ping.notifier());
+            wheel.insert(deadline, self.id, self.notifier());
         }
 
         Ok(())
@@ -149,18 +399,33 @@ impl Timer {
 }
 
 impl Source for Timer {
-    fn deregister(&mut self, poller: &std::sync::Arc<Poller>) -> Result<()> {
-        self.ping.deregister(poller)
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.ping.as_ref().and_then(Source::registration)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping
+            .as_mut()
+            .ok_or_else(crate::not_registered)?
+            .deregister(poller)
     }
 
-    fn handle_event(&mut self, poller: &std::sync::Arc<Poller>, event: Event) -> Result<()> {
-        self.ping.handle_event(poller, event)?;
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        // Events for other sources neither drain the ping nor advance the interval.
+        let ping = match &mut self.ping {
+            Some(ping) if ping.is_for(poller, &event) => ping,
+            _ => return Ok(()),
+        };
+
+        ping.handle_event(poller, event)?;
 
         // If this is a timer that fires on an interval, bump up the duration.
-        if self.deadline.is_some() {
-            self.deadline = self
-                .deadline
-                .and_then(|deadline| deadline.checked_add(self.interval));
+        if let Some(deadline) = self.deadline {
+            let base = match self.policy {
+                IntervalPolicy::FixedRate => deadline,
+                IntervalPolicy::FixedDelay => Instant::now(),
+            };
+            self.deadline = base.checked_add(self.interval);
         }
 
         Ok(())
@@ -172,7 +437,13 @@ impl Source for Timer {
         interest: Event,
         mode: PollMode,
     ) -> Result<()> {
-        self.ping.register(poller, interest, mode)
+        self.check_mode(mode)?;
+        let ping = match &mut self.ping {
+            Some(ping) => ping,
+            None => self.ping.insert(Ping::new()?),
+        };
+
+        ping.register(poller, interest, mode)
     }
 
     fn reregister(
@@ -181,6 +452,10 @@ impl Source for Timer {
         interest: Event,
         mode: PollMode,
     ) -> Result<()> {
-        self.ping.reregister(poller, interest, mode)
+        self.check_mode(mode)?;
+        self.ping
+            .as_mut()
+            .ok_or_else(crate::not_registered)?
+            .reregister(poller, interest, mode)
     }
 }
@@ -0,0 +1,141 @@
+//! A thread that notifies pings once their deadlines pass.
+//!
+//! This wakes up the poller for a [`TimerDriver`](super::TimerDriver), as well as for
+//! [`OneShot`](super::OneShot) timers on platforms without a kernel timer that can be polled.
+//! One thread serves every alarm and sleeps until the next deadline, so an alarm that is dropped
+//! before it fires doesn't leave a sleeping thread behind.
+
+use crate::ping::Notifier;
+use crate::Result;
+
+use std::collections::BTreeMap;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+/// A request to notify a ping at a deadline.
+///
+/// Dropping the alarm cancels it.
+#[derive(Debug)]
+pub(super) struct Alarm {
+    /// The deadline and ID of the alarm.
+    key: (Instant, u64),
+}
+
+/// The state shared with the timer thread.
+struct Shared {
+    /// The alarms that have not fired yet.
+    state: Mutex<State>,
+
+    /// Wakes up the timer thread when an earlier alarm is added.
+    condvar: Condvar,
+}
+
+/// The alarms that have not fired yet.
+struct State {
+    /// The notifier of each alarm, keyed by its deadline and ID.
+    ///
+    /// This is created along with the timer thread.
+    alarms: Option<BTreeMap<(Instant, u64), Notifier>>,
+
+    /// The ID of the next alarm.
+    next_id: u64,
+}
+
+static SHARED: Shared = Shared {
+    state: Mutex::new(State {
+        alarms: None,
+        next_id: 0,
+    }),
+    condvar: Condvar::new(),
+};
+
+/// Start the timer thread, if it isn't running yet.
+///
+/// Once started, the thread runs for the rest of the process.
+pub(super) fn start() -> Result<()> {
+    let mut state = lock();
+    if state.alarms.is_none() {
+        thread::Builder::new()
+            .name("polling-utils-timer".into())
+            .spawn(run)?;
+        state.alarms = Some(BTreeMap::new());
+    }
+
+    Ok(())
+}
+
+impl Alarm {
+    /// Notify the ping once `deadline` has passed.
+    ///
+    /// The alarm only fires once the timer thread has been [started](start).
+    pub(super) fn new(deadline: Instant, notifier: Notifier) -> Self {
+        let mut state = lock();
+        let key = (deadline, state.next_id);
+        state.next_id += 1;
+        let alarms = state.alarms.get_or_insert_with(BTreeMap::new);
+        let earliest = match alarms.keys().next() {
+            Some(&next) => key < next,
+            None => true,
+        };
+        alarms.insert(key, notifier);
+        drop(state);
+
+        // The thread may be sleeping until a later deadline.
+        if earliest {
+            SHARED.condvar.notify_one();
+        }
+
+        Self { key }
+    }
+
+    /// Get the deadline of the alarm.
+    pub(super) fn deadline(&self) -> Instant {
+        self.key.0
+    }
+}
+
+impl Drop for Alarm {
+    fn drop(&mut self) {
+        if let Some(alarms) = &mut lock().alarms {
+            alarms.remove(&self.key);
+        }
+    }
+}
+
+/// Lock the shared state.
+fn lock() -> MutexGuard<'static, State> {
+    SHARED.state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Notify every alarm as its deadline passes.
+fn run() {
+    let mut state = lock();
+    loop {
+        let now = Instant::now();
+        let alarms = state.alarms.get_or_insert_with(BTreeMap::new);
+
+        // Fire every alarm that is due.
+        let later = alarms.split_off(&(now, u64::MAX));
+        for notifier in std::mem::replace(alarms, later).into_values() {
+            // The ping may have been dropped in the meantime.
+            notifier.notify().ok();
+        }
+
+        let next = alarms.keys().next().map(|&(deadline, _)| deadline);
+        state = match next {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(now);
+                SHARED
+                    .condvar
+                    .wait_timeout(state, timeout)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0
+            }
+            None => SHARED
+                .condvar
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner()),
+        };
+    }
+}
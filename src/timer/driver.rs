@@ -0,0 +1,215 @@
+//! A shared timer wheel that fires its own timers.
+
+use super::alarm::{self, Alarm};
+use super::{SharedTimerWheel, TimerId};
+use crate::{Event, PollMode, Poller, Result, Source};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A set of timers that is registered into a poller as a single source.
+///
+/// This builds on a [`SharedTimerWheel`], so every timer shares one ping, but the driver fires
+/// its timers itself: a timer thread that is shared by every driver notifies the ping once the
+/// next deadline passes, so the poller wakes up even when it waits without a timeout. Handling
+/// the driver's event then fires every timer that is due. Once a deadline has passed, the driver
+/// also reports a [pending event](Source::pending_event), so [`dispatch`](crate::dispatch) and
+/// [`Reactor::poll`](crate::reactor::Reactor::poll) hand it the event without waiting for the
+/// ping. The timers that fired are then returned by [`TimerDriver::next_expired`].
+///
+/// Timers can carry a payload of type `T`, which comes back with the timer's ID from
+/// [`TimerDriver::drain_fired`] each time the timer fires.
+#[derive(Debug)]
+pub struct TimerDriver<T = ()> {
+    /// The timers.
+    wheel: SharedTimerWheel,
+
+    /// The payloads of the timers that have one.
+    payloads: HashMap<TimerId, T>,
+
+    /// Notifies the ping when the next timer is due.
+    alarm: Option<Alarm>,
+}
+
+impl TimerDriver {
+    /// Creates a new driver with no timers.
+    ///
+    /// Use [`TimerDriver::with_payloads`] to create a driver whose timers carry payloads.
+    pub fn new() -> Result<Self> {
+        Self::with_payloads()
+    }
+}
+
+impl<T> TimerDriver<T> {
+    /// Creates a new driver with no timers, whose timers can carry a payload of type `T`.
+    pub fn with_payloads() -> Result<Self> {
+        alarm::start()?;
+
+        Ok(Self {
+            wheel: SharedTimerWheel::new()?,
+            payloads: HashMap::new(),
+            alarm: None,
+        })
+    }
+
+    /// Add a timer that fires after the given duration.
+    pub fn after(&mut self, duration: Duration) -> TimerId {
+        let id = self.wheel.after(duration);
+        self.rearm();
+        id
+    }
+
+    /// Add a timer that fires at this instant.
+    pub fn at(&mut self, deadline: Instant) -> TimerId {
+        let id = self.wheel.at(deadline);
+        self.rearm();
+        id
+    }
+
+    /// Add a timer that fires right away, and then on an interval.
+    pub fn interval(&mut self, interval: Duration) -> TimerId {
+        let id = self.wheel.interval(interval);
+        self.rearm();
+        id
+    }
+
+    /// Add a timer that first fires at `start`, and then every `interval` after that.
+    pub fn interval_at(&mut self, start: Instant, interval: Duration) -> TimerId {
+        let id = self.wheel.interval_at(start, interval);
+        self.rearm();
+        id
+    }
+
+    /// Add a timer with a payload that fires after the given duration.
+    pub fn after_with(&mut self, duration: Duration, payload: T) -> TimerId {
+        let id = self.after(duration);
+        self.payloads.insert(id, payload);
+        id
+    }
+
+    /// Add a timer with a payload that fires right away, and then on an interval.
+    pub fn interval_with(&mut self, interval: Duration, payload: T) -> TimerId {
+        let id = self.interval(interval);
+        self.payloads.insert(id, payload);
+        id
+    }
+
+    /// Remove a timer so that it no longer fires, dropping its payload.
+    ///
+    /// Returns `false` if the timer was not in the driver.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        self.payloads.remove(&id);
+        let removed = self.wheel.cancel(id);
+        self.rearm();
+        removed
+    }
+
+    /// Get the number of timers in the driver.
+    pub fn len(&self) -> usize {
+        self.wheel.len()
+    }
+
+    /// Whether the driver has no timers.
+    pub fn is_empty(&self) -> bool {
+        self.wheel.is_empty()
+    }
+
+    /// Get how long to wait on the poller before the next timer is due.
+    ///
+    /// Returns `None` if there are no timers, in which case the wait can block indefinitely.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.wheel
+            .next_deadline()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Get the next timer that fired, if any.
+    ///
+    /// The payload of a timer that will not fire again is dropped; use
+    /// [`TimerDriver::drain_fired`] to get the payloads as well.
+    pub fn next_expired(&mut self) -> Option<TimerId> {
+        let id = self.wheel.next_expired()?;
+        if !self.wheel.contains(id) {
+            self.payloads.remove(&id);
+        }
+        Some(id)
+    }
+
+    /// Set the alarm for the next deadline, if it changed.
+    fn rearm(&mut self) {
+        let next = self.wheel.next_deadline();
+        if self.alarm.as_ref().map(Alarm::deadline) != next {
+            // Dropping the old alarm cancels it.
+            self.alarm = next.map(|deadline| Alarm::new(deadline, self.wheel.notifier().clone()));
+        }
+    }
+}
+
+impl<T: Clone> TimerDriver<T> {
+    /// Get every timer that fired along with its payload.
+    ///
+    /// A timer that fires once hands over its payload. An interval timer keeps its payload, and
+    /// a copy is returned each time it fires. Timers without a payload are skipped.
+    pub fn drain_fired(&mut self) -> Vec<(TimerId, T)> {
+        let mut fired = vec![];
+        while let Some(id) = self.wheel.next_expired() {
+            let payload = if self.wheel.contains(id) {
+                self.payloads.get(&id).cloned()
+            } else {
+                self.payloads.remove(&id)
+            };
+
+            if let Some(payload) = payload {
+                fired.push((id, payload));
+            }
+        }
+
+        fired
+    }
+}
+
+impl<T> Source for TimerDriver<T> {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.wheel.registration()
+    }
+
+    fn pending_event(&self) -> Option<Event> {
+        // A timer that is due has an event, even though nothing notified the ping.
+        match self.wheel.next_deadline() {
+            Some(deadline) if deadline <= Instant::now() => {
+                self.registration().map(|(interest, _)| interest)
+            }
+            _ => None,
+        }
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.wheel.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.wheel.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.wheel.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        if !matches!(self.registration(), Some((interest, _)) if interest.key == event.key) {
+            return Ok(());
+        }
+
+        // Drain the alarm's notification. There is none if the event was reported as pending
+        // before the alarm went off.
+        self.wheel.handle_event(poller, event)?;
+        self.wheel.expire();
+        self.rearm();
+        Ok(())
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        SharedTimerWheel::supports_mode(poller, mode)
+    }
+}
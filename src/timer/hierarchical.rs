@@ -0,0 +1,343 @@
+//! A timer wheel built from levels of time slots.
+
+use super::__private::WheelSealed;
+use super::{FireOutcome, IntervalPolicy, Timer, TimerTarget};
+use crate::ping::Notifier;
+use crate::Result;
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The number of bits of a tick that pick the slot in each level.
+const SLOT_BITS: u32 = 6;
+
+/// The number of slots in each level.
+const SLOTS: usize = 1 << SLOT_BITS;
+
+/// The number of levels in the wheel.
+const LEVELS: usize = 6;
+
+/// A timer wheel that keeps timers in levels of increasingly coarse time slots.
+///
+/// Inserting and firing a timer takes amortized constant time, where [`TimerWheel`] takes
+/// logarithmic time. This can be used in place of [`TimerWheel`] when there are many timers.
+///
+/// Time is measured in ticks of one millisecond, so timers may fire up to a millisecond late,
+/// but never early. Timers that are further out than the wheel covers, about two years, are
+/// kept in a sorted list until they come into range. Timers that fire together are fired in
+/// the order of their deadlines.
+///
+/// Like [`TimerWheel`], the wheel notifies the [`Ping`] of each [`Timer`] by default, and can
+/// fire another [`TimerTarget`] instead.
+///
+/// [`TimerWheel`]: super::TimerWheel
+/// [`Ping`]: crate::ping::Ping
+pub struct HierarchicalTimerWheel<N = Notifier> {
+    /// The instant that tick zero refers to.
+    start: Instant,
+
+    /// The tick that the wheel has been advanced to.
+    elapsed: u64,
+
+    /// The levels of the wheel, from the finest to the coarsest.
+    levels: Vec<Level<N>>,
+
+    /// Timers that are too far out to fit into the wheel, keyed by their tick and ID.
+    overflow: BTreeMap<(u64, usize), N>,
+
+    /// Timers that were already due when they were inserted.
+    expired: Vec<Entry<N>>,
+
+    /// The last ID that was assigned to a timer.
+    last_id: usize,
+}
+
+/// A level of the wheel.
+struct Level<N> {
+    /// A bitmask of the slots that contain timers.
+    occupied: u64,
+
+    /// The timers in each slot.
+    slots: Vec<Vec<Entry<N>>>,
+}
+
+/// A timer in the wheel.
+struct Entry<N> {
+    /// The tick the timer fires at.
+    tick: u64,
+
+    /// The ID of the timer.
+    id: usize,
+
+    /// The target that is fired.
+    target: N,
+}
+
+impl<N> fmt::Debug for HierarchicalTimerWheel<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HierarchicalTimerWheel")
+            .field("start", &self.start)
+            .field("elapsed", &self.elapsed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<N> Default for HierarchicalTimerWheel<N> {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: 0,
+            levels: (0..LEVELS).map(|_| Level::new()).collect(),
+            overflow: BTreeMap::new(),
+            expired: Vec::new(),
+            last_id: 1,
+        }
+    }
+}
+
+impl HierarchicalTimerWheel {
+    /// Creates a new timer wheel.
+    ///
+    /// Use [`HierarchicalTimerWheel::default`] to create a wheel with another
+    /// [`TimerTarget`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new timer that fires after the given duration.
+    pub fn after(&mut self, duration: Duration) -> Result<Timer> {
+        Instant::now()
+            .checked_add(duration)
+            .map(|deadline| self.at(deadline))
+            .unwrap_or_else(Timer::never)
+    }
+
+    /// Create a new timer that fires at this instant.
+    pub fn at(&mut self, deadline: Instant) -> Result<Timer> {
+        self.interval_at(deadline, Duration::MAX)
+    }
+
+    /// Create a timer that fires right away, and then on an interval.
+    pub fn interval(&mut self, interval: Duration) -> Result<Timer> {
+        self.interval_at(Instant::now(), interval)
+    }
+
+    /// Create a timer that fires right away, and then on an interval scheduled by `policy`.
+    pub fn interval_with_policy(
+        &mut self,
+        interval: Duration,
+        policy: IntervalPolicy,
+    ) -> Result<Timer> {
+        self.interval_at_with_policy(Instant::now(), interval, policy)
+    }
+
+    /// Create a new timer that first fires at `start`, and then every `interval` after that.
+    pub fn interval_at(&mut self, start: Instant, interval: Duration) -> Result<Timer> {
+        self.interval_at_with_policy(start, interval, IntervalPolicy::FixedRate)
+    }
+
+    /// Create a new timer that first fires at `start`, and then on an interval scheduled by
+    /// `policy`.
+    pub fn interval_at_with_policy(
+        &mut self,
+        start: Instant,
+        interval: Duration,
+        policy: IntervalPolicy,
+    ) -> Result<Timer> {
+        let timer = Timer::new(self.last_id, start, interval, policy)?;
+        self.last_id += 1;
+
+        // Register the timer.
+        self.insert(start, timer.id, timer.notifier());
+
+        Ok(timer)
+    }
+}
+
+impl<N: TimerTarget> HierarchicalTimerWheel<N> {
+    /// Insert a target that is fired at the given deadline.
+    ///
+    /// Returns the ID assigned to the timer.
+    pub fn insert_at(&mut self, deadline: Instant, target: N) -> usize {
+        let id = self.last_id;
+        self.last_id += 1;
+
+        // Round up so that timers never fire early.
+        let tick = self.tick_ceil(deadline);
+        self.insert_entry(Entry { tick, id, target });
+        id
+    }
+
+    /// Fire all pending timers.
+    ///
+    /// Returns how long to wait before calling this again. For timers in the coarser levels
+    /// of the wheel this can be earlier than the next timer is due.
+    pub fn fire_timers(&mut self) -> Result<Option<Duration>> {
+        let outcome = self.fire_expired()?;
+        Ok(outcome
+            .next
+            .map(|next| next.saturating_duration_since(Instant::now())))
+    }
+
+    /// Fire all pending timers, returning how many fired and when to fire the wheel again.
+    ///
+    /// For timers in the coarser levels of the wheel, `next` can be earlier than the next
+    /// timer is due.
+    pub fn fire_expired(&mut self) -> Result<FireOutcome> {
+        let now = Instant::now();
+        let now_tick = self.tick_floor(now);
+
+        // Fire or cascade every slot that starts before now, in order.
+        let mut expired = std::mem::take(&mut self.expired);
+        while let Some((level, slot, start)) = self.next_slot() {
+            if start > now_tick {
+                break;
+            }
+
+            self.elapsed = self.elapsed.max(start);
+            for entry in self.levels[level].take(slot) {
+                if entry.tick <= now_tick {
+                    expired.push(entry);
+                } else {
+                    self.insert_entry(entry);
+                }
+            }
+        }
+
+        // No slot starts before now, so no timer is skipped by advancing the wheel.
+        self.elapsed = self.elapsed.max(now_tick);
+
+        // Move timers that came into range out of the overflow list.
+        while let Some(&(tick, id)) = self.overflow.keys().next() {
+            if tick > now_tick && level_for(self.elapsed, tick) >= LEVELS {
+                break;
+            }
+
+            let target = self.overflow.remove(&(tick, id)).unwrap();
+            self.insert_entry(Entry { tick, id, target });
+        }
+        expired.append(&mut self.expired);
+
+        // Cascaded slots hold timers for many ticks, so put them back in deadline order.
+        expired.sort_unstable_by_key(|entry| (entry.tick, entry.id));
+        let targets = expired
+            .into_iter()
+            .map(|entry| entry.target)
+            .collect::<Vec<_>>();
+
+        // See when the next timer is due.
+        let next = self
+            .next_slot()
+            .map(|(_, _, start)| start)
+            .into_iter()
+            .chain(self.overflow.keys().next().map(|&(tick, _)| tick))
+            .min()
+            .and_then(|tick| self.start.checked_add(Duration::from_millis(tick)));
+
+        // Fire all expired timers.
+        N::fire_all(&targets)?;
+
+        Ok(FireOutcome {
+            fired: targets.len(),
+            next,
+        })
+    }
+
+    /// Insert a timer into the level and slot it belongs to.
+    fn insert_entry(&mut self, entry: Entry<N>) {
+        if entry.tick <= self.elapsed {
+            self.expired.push(entry);
+            return;
+        }
+
+        let level = level_for(self.elapsed, entry.tick);
+        if level >= LEVELS {
+            self.overflow.insert((entry.tick, entry.id), entry.target);
+            return;
+        }
+
+        let slot = slot_for(entry.tick, level);
+        self.levels[level].push(slot, entry);
+    }
+
+    /// Find the next slot that contains timers, returning its level, index and first tick.
+    ///
+    /// Every timer in a level is due after every timer in the levels below it, so the first
+    /// occupied slot in the lowest occupied level comes first.
+    fn next_slot(&self) -> Option<(usize, usize, u64)> {
+        self.levels.iter().enumerate().find_map(|(level, slots)| {
+            let current = slot_for(self.elapsed, level);
+            let occupied = slots.occupied & (u64::MAX << current);
+            if occupied == 0 {
+                return None;
+            }
+
+            let slot = occupied.trailing_zeros() as usize;
+            let slot_bits = SLOT_BITS * level as u32;
+            let cycle_bits = slot_bits + SLOT_BITS;
+            let cycle_start = self.elapsed >> cycle_bits << cycle_bits;
+            Some((level, slot, cycle_start + ((slot as u64) << slot_bits)))
+        })
+    }
+
+    /// Get the tick that an instant falls into.
+    fn tick_floor(&self, instant: Instant) -> u64 {
+        let since = instant.saturating_duration_since(self.start);
+        u64::try_from(since.as_millis()).unwrap_or(u64::MAX)
+    }
+
+    /// Get the first tick that is not before an instant.
+    fn tick_ceil(&self, instant: Instant) -> u64 {
+        let since = instant.saturating_duration_since(self.start);
+        let millis = since.as_millis();
+        let partial = since.as_nanos() > millis * 1_000_000;
+        u64::try_from(millis + u128::from(partial)).unwrap_or(u64::MAX)
+    }
+}
+
+impl WheelSealed for HierarchicalTimerWheel {
+    fn insert(&mut self, deadline: Instant, id: usize, notifier: Notifier) {
+        // Round up so that timers never fire early.
+        let tick = self.tick_ceil(deadline);
+        self.insert_entry(Entry {
+            tick,
+            id,
+            target: notifier,
+        });
+    }
+}
+
+impl<N> Level<N> {
+    fn new() -> Self {
+        Self {
+            occupied: 0,
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    fn push(&mut self, slot: usize, entry: Entry<N>) {
+        self.occupied |= 1 << slot;
+        self.slots[slot].push(entry);
+    }
+
+    fn take(&mut self, slot: usize) -> Vec<Entry<N>> {
+        self.occupied &= !(1 << slot);
+        std::mem::take(&mut self.slots[slot])
+    }
+}
+
+/// Get the level a timer due at `tick` belongs in, when the wheel is at `elapsed`.
+///
+/// This is the level of the most significant slot index where the two ticks differ.
+fn level_for(elapsed: u64, tick: u64) -> usize {
+    let masked = (elapsed ^ tick) | (SLOTS as u64 - 1);
+    let significant = u64::BITS - 1 - masked.leading_zeros();
+    (significant / SLOT_BITS) as usize
+}
+
+/// Get the slot a tick falls into at the given level.
+fn slot_for(tick: u64, level: usize) -> usize {
+    ((tick >> (SLOT_BITS * level as u32)) as usize) & (SLOTS - 1)
+}
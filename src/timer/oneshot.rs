@@ -0,0 +1,125 @@
+//! A timer that fires once without a timer wheel.
+
+use crate::{Event, PollMode, Poller, Result, Source};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use super::TimerFd;
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+use super::alarm::{self, Alarm};
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+use crate::ping::Ping;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+use std::time::Instant;
+
+/// A timer that fires once, without needing a [`TimerWheel`](super::TimerWheel).
+///
+/// On Linux and Android this arms a kernel timer. Elsewhere a timer thread that is shared by
+/// every `OneShot` pings the poller once the duration has passed, and dropping the timer
+/// cancels it. Either way, registering the timer is all it takes for the poller to report it.
+#[derive(Debug)]
+pub struct OneShot {
+    /// The kernel timer.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    timerfd: TimerFd,
+
+    /// The ping that the timer thread notifies.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    ping: Ping,
+
+    /// The request for the timer thread to notify the ping.
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    _alarm: Option<Alarm>,
+
+    /// Whether the timer has fired.
+    fired: bool,
+}
+
+impl OneShot {
+    /// Create a new timer that fires once after the given duration.
+    pub fn after(duration: Duration) -> Result<Self> {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        {
+            Ok(Self {
+                timerfd: TimerFd::after(duration)?,
+                fired: false,
+            })
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let ping = Ping::new()?;
+            alarm::start()?;
+
+            // A deadline that cannot be represented is never reached.
+            let alarm = Instant::now()
+                .checked_add(duration)
+                .map(|deadline| Alarm::new(deadline, ping.notifier()));
+
+            Ok(Self {
+                ping,
+                _alarm: alarm,
+                fired: false,
+            })
+        }
+    }
+
+    /// Whether the timer has fired, as of the last call to `handle_event`.
+    pub fn has_fired(&self) -> bool {
+        self.fired
+    }
+}
+
+impl Source for OneShot {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.timerfd.register(poller, interest, mode)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.register(poller, interest, mode)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.timerfd.reregister(poller, interest, mode)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.reregister(poller, interest, mode)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.timerfd.deregister(poller)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        if self.fired {
+            return Ok(());
+        }
+
+        // Events for other keys are ignored, and leave the timer unfired.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let fired = {
+            self.timerfd.handle_event(poller, event)?;
+            self.timerfd.expirations() > 0
+        };
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let fired = self.ping.handle_event_progress(poller, event)?;
+
+        self.fired = fired;
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! A timer wheel whose timers share a single ping.
+
+use super::FireOutcome;
+use crate::ping::{Notifier, Ping};
+use crate::{Event, PollMode, Poller, Result, Source};
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The identifier of a timer in a [`SharedTimerWheel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimerId(usize);
+
+/// A timer wheel whose timers share a single [`Ping`].
+///
+/// Every [`Timer`](super::Timer) from a [`TimerWheel`](super::TimerWheel) has its own ping,
+/// which costs a file descriptor per timer. The timers in this wheel are only IDs: the wheel
+/// itself is registered into the poller, and firing timers notifies its one ping. After the
+/// wheel's event is handled, [`SharedTimerWheel::next_expired`] returns the timers that fired.
+#[derive(Debug)]
+pub struct SharedTimerWheel {
+    /// The interval of each timer, keyed by its deadline and ID.
+    timers: BTreeMap<(Instant, usize), Duration>,
+
+    /// The deadline of each timer in the wheel.
+    deadlines: HashMap<usize, Instant>,
+
+    /// The last ID that was assigned to a timer.
+    last_id: usize,
+
+    /// Timers that have fired but not yet been returned.
+    expired: VecDeque<TimerId>,
+
+    /// The ping shared by every timer.
+    ping: Ping,
+
+    /// The notifier for `ping`.
+    notifier: Notifier,
+}
+
+impl SharedTimerWheel {
+    /// Creates a new timer wheel with no timers.
+    pub fn new() -> Result<Self> {
+        let ping = Ping::new()?;
+        let notifier = ping.notifier();
+
+        Ok(Self {
+            timers: BTreeMap::new(),
+            deadlines: HashMap::new(),
+            last_id: 0,
+            expired: VecDeque::new(),
+            ping,
+            notifier,
+        })
+    }
+
+    /// Add a timer that fires after the given duration.
+    ///
+    /// A timer whose deadline cannot be represented never fires.
+    pub fn after(&mut self, duration: Duration) -> TimerId {
+        match Instant::now().checked_add(duration) {
+            Some(deadline) => self.at(deadline),
+            None => self.next_id(),
+        }
+    }
+
+    /// Add a timer that fires at this instant.
+    pub fn at(&mut self, deadline: Instant) -> TimerId {
+        self.interval_at(deadline, Duration::MAX)
+    }
+
+    /// Add a timer that fires right away, and then on an interval.
+    pub fn interval(&mut self, interval: Duration) -> TimerId {
+        self.interval_at(Instant::now(), interval)
+    }
+
+    /// Add a timer that first fires at `start`, and then every `interval` after that.
+    ///
+    /// The next deadline is one interval after the previous deadline, like
+    /// [`IntervalPolicy::FixedRate`](super::IntervalPolicy::FixedRate).
+    pub fn interval_at(&mut self, start: Instant, interval: Duration) -> TimerId {
+        let id = self.next_id();
+        self.timers.insert((start, id.0), interval);
+        self.deadlines.insert(id.0, start);
+        id
+    }
+
+    /// Remove a timer so that it no longer fires.
+    ///
+    /// Returns `false` if the timer was not in the wheel. A timer that has already fired but
+    /// has not been returned by [`SharedTimerWheel::next_expired`] is still returned.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        match self.deadlines.remove(&id.0) {
+            Some(deadline) => {
+                self.timers.remove(&(deadline, id.0));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the number of timers in the wheel.
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Whether the wheel has no timers.
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Fire all pending timers.
+    ///
+    /// The shared ping is notified once if any timer fired. Returns how long until the next
+    /// timer fires, if any.
+    pub fn fire_timers(&mut self) -> Result<Option<Duration>> {
+        let outcome = self.fire_expired()?;
+        Ok(outcome
+            .next
+            .map(|next| next.saturating_duration_since(Instant::now())))
+    }
+
+    /// Fire all pending timers, returning how many fired and when the next one is due.
+    ///
+    /// The shared ping is notified once if any timer fired.
+    pub fn fire_expired(&mut self) -> Result<FireOutcome> {
+        let fired = self.expire();
+        if fired > 0 {
+            self.notifier.notify()?;
+        }
+
+        Ok(FireOutcome {
+            fired,
+            next: self.next_deadline(),
+        })
+    }
+
+    /// Move every timer that has expired to the queue, without notifying the ping.
+    ///
+    /// Returns the number of timers that expired.
+    pub(super) fn expire(&mut self) -> usize {
+        let now = Instant::now();
+
+        // Get all timers that have expired.
+        let mut expired = self.timers.split_off(&(now, 0));
+        std::mem::swap(&mut self.timers, &mut expired);
+
+        let fired = expired.len();
+        for ((deadline, id), interval) in expired {
+            self.expired.push_back(TimerId(id));
+
+            // Put interval timers back in at their next deadline.
+            match deadline.checked_add(interval) {
+                Some(next) if interval != Duration::MAX => {
+                    self.timers.insert((next, id), interval);
+                    self.deadlines.insert(id, next);
+                }
+                _ => {
+                    self.deadlines.remove(&id);
+                }
+            }
+        }
+
+        fired
+    }
+
+    /// Whether the timer is still scheduled to fire.
+    pub(super) fn contains(&self, id: TimerId) -> bool {
+        self.deadlines.contains_key(&id.0)
+    }
+
+    /// Get the notifier for the shared ping.
+    pub(super) fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    /// Get the deadline of the next timer, if any.
+    pub(super) fn next_deadline(&self) -> Option<Instant> {
+        self.timers.keys().next().map(|&(deadline, _)| deadline)
+    }
+
+    /// Get the next timer that fired, if any.
+    pub fn next_expired(&mut self) -> Option<TimerId> {
+        self.expired.pop_front()
+    }
+
+    /// Assign a new timer ID.
+    fn next_id(&mut self) -> TimerId {
+        self.last_id += 1;
+        TimerId(self.last_id)
+    }
+}
+
+impl Source for SharedTimerWheel {
+    fn registration(&self) -> Option<(Event, PollMode)> {
+        self.ping.registration()
+    }
+
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.ping.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.ping.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> Result<()> {
+        self.ping.handle_event(poller, event)
+    }
+
+    fn supports_mode(poller: &Arc<Poller>, mode: PollMode) -> bool {
+        Ping::supports_mode(poller, mode)
+    }
+}
@@ -0,0 +1,107 @@
+//! A timer source backed by a Linux `timerfd`.
+
+use crate::{Event, PollMode, Poller, Result, Socket, Source};
+
+use rustix::fd::OwnedFd;
+use rustix::io::{read, Errno};
+use rustix::time::{
+    timerfd_create, timerfd_settime, Itimerspec, TimerfdClockId, TimerfdFlags, TimerfdTimerFlags,
+    Timespec,
+};
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A timer that is managed by the kernel.
+///
+/// Unlike a [`Timer`](super::Timer), this does not need a [`TimerWheel`](super::TimerWheel);
+/// the timerfd becomes readable on its own once it expires.
+#[derive(Debug)]
+pub struct TimerFd {
+    /// The underlying timerfd.
+    timerfd: Socket<OwnedFd>,
+
+    /// The number of expirations read by the last call to `handle_event`.
+    expirations: u64,
+}
+
+impl TimerFd {
+    /// Create a new timer that fires once after the given duration.
+    pub fn after(duration: Duration) -> Result<Self> {
+        Self::new(duration, Duration::ZERO)
+    }
+
+    /// Create a new timer that fires every `interval`, starting one interval from now.
+    pub fn interval(interval: Duration) -> Result<Self> {
+        Self::new(interval, interval)
+    }
+
+    fn new(value: Duration, interval: Duration) -> Result<Self> {
+        let timerfd = timerfd_create(
+            TimerfdClockId::Monotonic,
+            TimerfdFlags::NONBLOCK | TimerfdFlags::CLOEXEC,
+        )?;
+
+        // A zero value would disarm the timer, so fire as soon as possible instead.
+        let value = value.max(Duration::from_nanos(1));
+        let spec = Itimerspec {
+            it_interval: timespec(interval),
+            it_value: timespec(value),
+        };
+        timerfd_settime(&timerfd, TimerfdTimerFlags::empty(), &spec)?;
+
+        Ok(Self {
+            timerfd: Socket::new(timerfd),
+            expirations: 0,
+        })
+    }
+
+    /// Get the number of times the timer expired, as of the last call to `handle_event`.
+    ///
+    /// This can be more than one if the event was not handled before the next tick.
+    pub fn expirations(&self) -> u64 {
+        self.expirations
+    }
+}
+
+impl Source for TimerFd {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.timerfd.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.timerfd.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.timerfd.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, interest: Event) -> Result<()> {
+        // Don't read the expirations for an event that was meant for another source.
+        if !self.timerfd.is_for_in(poller, &interest) {
+            self.expirations = 0;
+            return Ok(());
+        }
+
+        // The expirations may already have been read, for instance while handling the same
+        // tick for another poller.
+        let mut buf = [0u8; 8];
+        self.expirations = match read(self.timerfd.socket(), &mut buf) {
+            Ok(_) => u64::from_ne_bytes(buf),
+            Err(Errno::AGAIN) => 0,
+            Err(err) => return Err(err.into()),
+        };
+
+        self.timerfd.handle_event(poller, interest)
+    }
+}
+
+/// Convert a `Duration` into a `Timespec`, saturating if it is too large.
+fn timespec(duration: Duration) -> Timespec {
+    Timespec {
+        tv_sec: TryFrom::try_from(duration.as_secs()).unwrap_or(i64::MAX as _),
+        tv_nsec: duration.subsec_nanos() as _,
+    }
+}
@@ -0,0 +1,187 @@
+//! An event source that watches the filesystem through `inotify`.
+
+use crate::{Event, PollMode, Poller, RecordReader, Result, Socket, Source};
+
+use rustix::fd::{AsFd, OwnedFd};
+use rustix::fs::inotify::{inotify_add_watch, inotify_init, CreateFlags};
+use rustix::io::{read, Errno};
+
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+use std::path::Path;
+use std::sync::Arc;
+
+pub use rustix::fs::inotify::WatchFlags;
+
+/// The size of the fixed part of an `inotify_event` record.
+const HEADER_LEN: usize = 16;
+
+/// `IN_Q_OVERFLOW`: the event queue overflowed and events were lost.
+const Q_OVERFLOW: u32 = 0x4000;
+
+/// `IN_IGNORED`: the watch was removed.
+const IGNORED: u32 = 0x8000;
+
+/// An event source that becomes readable when a watched file changes.
+#[derive(Debug)]
+pub struct Inotify {
+    /// The underlying inotify instance.
+    inotify: Socket<OwnedFd>,
+
+    /// Bytes that have been read but not yet parsed into events.
+    records: RecordReader,
+
+    /// Events that have been parsed but not yet returned.
+    events: VecDeque<InotifyEvent>,
+}
+
+/// The identifier of a watch added to an [`Inotify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WatchId(i32);
+
+/// An event read from an [`Inotify`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InotifyEvent {
+    /// The watch this event is for.
+    pub watch: WatchId,
+
+    /// The kind of event.
+    ///
+    /// Bits that are only ever reported by the kernel, like `IN_ISDIR`, are not guaranteed to
+    /// be included; see `raw_mask` for those.
+    pub mask: WatchFlags,
+
+    /// The mask as reported by the kernel, including bits like `IN_Q_OVERFLOW` and
+    /// `IN_IGNORED`.
+    pub raw_mask: u32,
+
+    /// Connects related `MOVED_FROM` and `MOVED_TO` events.
+    pub cookie: u32,
+
+    /// The name of the file inside of a watched directory, if any.
+    pub name: Option<OsString>,
+}
+
+impl Inotify {
+    /// Creates a new inotify instance with no watches.
+    pub fn new() -> Result<Self> {
+        let inotify = inotify_init(CreateFlags::NONBLOCK | CreateFlags::CLOEXEC)?;
+
+        Ok(Self {
+            inotify: Socket::new(inotify),
+            records: RecordReader::new(),
+            events: VecDeque::new(),
+        })
+    }
+
+    /// Start watching the given path for the events in `mask`.
+    ///
+    /// Watching a path that is already watched updates its mask and returns the same ID.
+    pub fn add_watch(&mut self, path: impl AsRef<Path>, mask: WatchFlags) -> Result<WatchId> {
+        let wd = inotify_add_watch(self.inotify.socket().as_fd(), path.as_ref(), mask)?;
+        Ok(WatchId(wd))
+    }
+
+    /// Get the next event that was read, if any.
+    ///
+    /// Events are only read from the inotify instance in `handle_event`.
+    pub fn read_event(&mut self) -> Option<InotifyEvent> {
+        self.events.pop_front()
+    }
+
+    /// Read every pending record from the inotify instance.
+    fn drain(&mut self) -> Result<()> {
+        // Large enough for at least one record with the longest possible name.
+        let mut chunk = [0u8; 4096];
+
+        loop {
+            match read(self.inotify.socket(), &mut chunk) {
+                Ok(0) => break,
+                Ok(n) => {
+                    self.records.extend(&chunk[..n]);
+                    self.parse();
+                }
+                Err(Errno::INTR) => continue,
+                Err(Errno::AGAIN) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse every complete record out of the buffer.
+    fn parse(&mut self) {
+        while let Some(record) = self.records.next_record(record_len) {
+            let field = |i: usize| record[i * 4..i * 4 + 4].try_into().unwrap();
+            let wd = i32::from_ne_bytes(field(0));
+            let mask = u32::from_ne_bytes(field(1));
+            let cookie = u32::from_ne_bytes(field(2));
+
+            // The name is padded with trailing nul bytes.
+            let name = &record[HEADER_LEN..];
+            let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            let name = if name_len == 0 {
+                None
+            } else {
+                Some(OsString::from_vec(name[..name_len].to_vec()))
+            };
+
+            self.events.push_back(InotifyEvent {
+                watch: WatchId(wd),
+                mask: WatchFlags::from_bits_truncate(mask),
+                raw_mask: mask,
+                cookie,
+                name,
+            });
+        }
+    }
+}
+
+impl InotifyEvent {
+    /// Whether the kernel's event queue overflowed, so some events were lost.
+    ///
+    /// This event is not for any particular watch.
+    pub fn is_queue_overflow(&self) -> bool {
+        self.raw_mask & Q_OVERFLOW != 0
+    }
+
+    /// Whether the watch was removed, for instance because the watched file was deleted.
+    ///
+    /// No more events are reported for the watch after this one.
+    pub fn is_watch_removed(&self) -> bool {
+        self.raw_mask & IGNORED != 0
+    }
+}
+
+/// Get the length of the `inotify_event` record at the start of `bytes`.
+fn record_len(bytes: &[u8]) -> Option<usize> {
+    let len = bytes.get(12..HEADER_LEN)?;
+    Some(HEADER_LEN + u32::from_ne_bytes(len.try_into().unwrap()) as usize)
+}
+
+impl Source for Inotify {
+    fn register(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.inotify.register(poller, interest, mode)
+    }
+
+    fn reregister(&mut self, poller: &Arc<Poller>, interest: Event, mode: PollMode) -> Result<()> {
+        self.inotify.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> Result<()> {
+        self.inotify.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, interest: Event) -> Result<()> {
+        // Don't read the file events for an event that was meant for another source.
+        if !self.inotify.is_for_in(poller, &interest) {
+            return Ok(());
+        }
+
+        self.drain()?;
+        self.inotify.handle_event(poller, interest)
+    }
+}
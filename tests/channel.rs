@@ -0,0 +1,568 @@
+use polling_utils::channel::{self, RecvTimeoutError, TryRecvError, TrySendError};
+use polling_utils::timer::TimerWheel;
+use polling_utils::{Event, EventSource, PollMode, Poller, Source};
+
+use std::sync::Arc;
+use std::task::Poll;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn watch_latest() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::watch(0).unwrap();
+    assert_eq!(receiver.borrow(), 0);
+
+    // Register the source in the poller.
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Send several values rapidly.
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+    sender.send(3).unwrap();
+    assert!(receiver.has_changed());
+
+    // A single event delivers the newest value.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.borrow(), 3);
+    assert!(!receiver.has_changed());
+
+    // No more events.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn broadcast_two_receivers() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut first) = channel::broadcast(8).unwrap();
+    let mut second = sender.subscribe().unwrap();
+
+    // Register both receivers in the poller.
+    first
+        .register(&poller, Event::readable(1), PollMode::Level)
+        .unwrap();
+    second
+        .register(&poller, Event::readable(2), PollMode::Level)
+        .unwrap();
+
+    // Send two messages.
+    sender.send("hello").unwrap();
+    sender.send("world").unwrap();
+
+    // Both receivers are woken up.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    events.sort_by_key(|event| event.key);
+    assert_eq!(events, [Event::readable(1), Event::readable(2)]);
+    first.handle_event(&poller, events[0]).unwrap();
+    second.handle_event(&poller, events[1]).unwrap();
+
+    // Both receivers see both messages.
+    for receiver in [&mut first, &mut second] {
+        assert_eq!(receiver.recv(), Ok(Some("hello")));
+        assert_eq!(receiver.recv(), Ok(Some("world")));
+        assert_eq!(receiver.recv(), Ok(None));
+    }
+}
+
+#[test]
+fn broadcast_lagged() {
+    let (sender, mut receiver) = channel::broadcast(2).unwrap();
+    for i in 0..5 {
+        sender.send(i).unwrap();
+    }
+
+    // The three oldest values were dropped.
+    assert_eq!(receiver.recv(), Err(channel::Lagged(3)));
+    assert_eq!(receiver.recv(), Ok(Some(3)));
+    assert_eq!(receiver.recv(), Ok(Some(4)));
+    assert_eq!(receiver.recv(), Ok(None));
+}
+
+#[test]
+fn unbounded_many_items() {
+    const COUNT: usize = 10_000;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded().unwrap();
+
+    // Register the source in the poller.
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    for i in 0..COUNT {
+        sender.send(i).unwrap();
+    }
+
+    // Every item is received in order through the same future.
+    let mut received = 0;
+    let mut events = vec![];
+    while received < COUNT {
+        while let Some(value) = receiver.recv() {
+            assert_eq!(value, received);
+            received += 1;
+        }
+
+        if received == COUNT {
+            break;
+        }
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert!(!events.is_empty());
+        for event in &events {
+            receiver.handle_event(&poller, *event).unwrap();
+        }
+    }
+
+    assert_eq!(receiver.recv(), None);
+}
+
+#[test]
+fn ping_receiver() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded_ping().unwrap();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(receiver.try_recv(), None);
+
+    // Sending wakes up the receiver's ping.
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+
+    // Both items can be received.
+    assert_eq!(receiver.try_recv(), Some(1));
+    assert_eq!(receiver.try_recv(), Some(2));
+    assert_eq!(receiver.try_recv(), None);
+
+    receiver.deregister(&poller).unwrap();
+}
+
+#[test]
+fn ping_receiver_no_spurious_wakeup() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded_ping().unwrap();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    // Handle the event, then drain the channel.
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.try_recv(), Some(1));
+    assert_eq!(receiver.try_recv(), Some(2));
+    assert_eq!(receiver.try_recv(), None);
+
+    // Draining the channel doesn't leave a wakeup behind.
+    receiver
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // The next send wakes the receiver up again.
+    sender.send(3).unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.try_recv(), Some(3));
+    assert_eq!(receiver.try_recv(), None);
+
+    receiver.deregister(&poller).unwrap();
+}
+
+#[test]
+fn ping_receiver_rapid_sends() {
+    const COUNT: usize = 100_000;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded_ping().unwrap();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Far more sends than the ping could hold if each one were written to it.
+    let sending = thread::spawn(move || {
+        for i in 0..COUNT {
+            sender.send(i).unwrap();
+        }
+    });
+
+    let mut received = vec![];
+    let mut events = vec![];
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while received.len() < COUNT {
+        assert!(Instant::now() < deadline, "values were lost");
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        for event in &events {
+            receiver.handle_event(&poller, *event).unwrap();
+            receiver
+                .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+                .unwrap();
+        }
+
+        while let Some(value) = receiver.try_recv() {
+            received.push(value);
+        }
+    }
+
+    sending.join().unwrap();
+    assert!(received.iter().copied().eq(0..COUNT));
+}
+
+#[test]
+fn bounded_len() {
+    let (sender, receiver) = channel::bounded(4).unwrap();
+    assert_eq!(sender.capacity(), Some(4));
+    assert_eq!(receiver.capacity(), Some(4));
+    assert!(receiver.is_empty());
+
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+    assert_eq!(sender.len(), 2);
+    assert_eq!(receiver.len(), 2);
+
+    // The channel is full after two more values.
+    sender.send(3).unwrap();
+    sender.send(4).unwrap();
+    assert!(sender.send(5).is_err());
+
+    let (sender, _receiver) = channel::unbounded::<i32>().unwrap();
+    assert_eq!(sender.capacity(), None);
+}
+
+#[test]
+fn try_send_returns_value() {
+    let (sender, mut receiver) = channel::bounded(1).unwrap();
+    sender.try_send(String::from("first")).unwrap();
+
+    // A full channel hands the value back.
+    let err = sender.try_send(String::from("second")).unwrap_err();
+    assert!(err.is_full());
+    assert_eq!(err, TrySendError::Full(String::from("second")));
+    assert_eq!(err.into_inner(), "second");
+
+    // So does a closed one.
+    assert_eq!(receiver.recv().as_deref(), Some("first"));
+    sender.close();
+    let err = sender.try_send(String::from("third")).unwrap_err();
+    assert!(err.is_closed());
+    assert_eq!(err.into_inner(), "third");
+}
+
+#[test]
+fn poll_recv_states() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded().unwrap();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Nothing has been sent yet.
+    assert_eq!(receiver.poll_recv(), Poll::Pending);
+
+    // Sending wakes the receiver up, and the value is ready.
+    sender.send(1).unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.poll_recv(), Poll::Ready(Some(1)));
+    assert_eq!(receiver.poll_recv(), Poll::Pending);
+
+    // Dropping the sender disconnects the channel.
+    drop(sender);
+    assert_eq!(receiver.poll_recv(), Poll::Ready(None));
+    receiver.deregister(&poller).unwrap();
+}
+
+#[test]
+fn closed_when_sender_dropped() {
+    let (sender, receiver) = channel::unbounded::<i32>().unwrap();
+    assert!(!sender.is_closed());
+    assert!(!receiver.is_closed());
+
+    drop(sender);
+    assert!(receiver.is_closed());
+}
+
+#[test]
+fn close_wakes_receiver() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded::<i32>().unwrap();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Handle the initial wakeup so the receiver is parked on an empty channel.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    receiver
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Closing the channel wakes it up.
+    assert!(sender.close());
+    assert!(!sender.close());
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn cloned_senders() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (first, mut receiver) = channel::unbounded::<i32>().unwrap();
+    let second = first.clone();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Handle the initial wakeup so the receiver is parked on an empty channel.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    receiver
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Both senders feed the same receiver.
+    first.send(1).unwrap();
+    second.send(2).unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.drain().collect::<Vec<_>>(), [1, 2]);
+    receiver
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Dropping one sender keeps the channel open.
+    drop(first);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+    assert!(!receiver.is_closed());
+
+    // Dropping the last one wakes the receiver with the disconnect.
+    drop(second);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn drain() {
+    let (sender, mut receiver) = channel::unbounded().unwrap();
+    for i in 0..3 {
+        sender.send(i).unwrap();
+    }
+
+    // Draining stops once the channel is empty.
+    let mut drain = receiver.drain();
+    assert_eq!(drain.by_ref().collect::<Vec<_>>(), [0, 1, 2]);
+    assert!(!drain.is_disconnected());
+
+    // After the sender is dropped, draining reports the disconnect.
+    sender.send(3).unwrap();
+    drop(sender);
+    let mut drain = receiver.drain();
+    assert_eq!(drain.by_ref().collect::<Vec<_>>(), [3]);
+    assert!(drain.is_disconnected());
+}
+
+#[test]
+fn recv_timeout() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let (sender, mut receiver) = channel::unbounded().unwrap();
+
+    // Wait for a receive, firing the wheel as it asks.
+    let mut recv = |receiver: &mut channel::Receiver<i32>, timeout| {
+        let mut recv = receiver.recv_timeout(&mut wheel, timeout).unwrap();
+        recv.register(&poller, Event::readable(0), PollMode::Level)
+            .unwrap();
+
+        let mut events = vec![];
+        let result = loop {
+            if let Poll::Ready(result) = recv.poll() {
+                break result;
+            }
+
+            let next = wheel.fire_timers().unwrap();
+            events.clear();
+            poller.wait(&mut events, next).unwrap();
+            for event in &events {
+                recv.handle_event(&poller, *event).unwrap();
+            }
+        };
+
+        recv.deregister(&poller).unwrap();
+        result
+    };
+
+    // A message that arrives in time is received.
+    let send = thread::spawn({
+        let sender = sender.clone();
+        move || {
+            thread::sleep(Duration::from_millis(20));
+            sender.send(1).unwrap();
+        }
+    });
+    assert_eq!(recv(&mut receiver, Duration::from_secs(5)), Ok(1));
+    send.join().unwrap();
+
+    // Without a message, the timer fires.
+    let start = Instant::now();
+    assert_eq!(
+        recv(&mut receiver, Duration::from_millis(50)),
+        Err(RecvTimeoutError::Timeout)
+    );
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // A closed channel is reported right away.
+    drop(sender);
+    assert_eq!(
+        recv(&mut receiver, Duration::from_secs(5)),
+        Err(RecvTimeoutError::Disconnected)
+    );
+}
+
+#[test]
+fn recv_timeout_value_wins() {
+    use polling_utils::secondary_key;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let (sender, mut receiver) = channel::unbounded().unwrap();
+    let mut recv = receiver
+        .recv_timeout(&mut wheel, Duration::from_millis(10))
+        .unwrap();
+    recv.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // The timer expires and a value is queued before the receive is polled.
+    thread::sleep(Duration::from_millis(20));
+    wheel.fire_timers().unwrap();
+    sender.send(7).unwrap();
+
+    // Both arrive in the same wakeup, each under its own key.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut keys = events.iter().map(|event| event.key).collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(keys, [0, secondary_key(0)]);
+    for event in &events {
+        recv.handle_event(&poller, *event).unwrap();
+    }
+
+    // The value wins over the timeout.
+    assert_eq!(recv.poll(), Poll::Ready(Ok(7)));
+    recv.deregister(&poller).unwrap();
+}
+
+/// Take every value that is ready, stopping once the source is finished.
+fn drain_ready<S: EventSource>(source: &mut S) -> (Vec<S::Item>, bool) {
+    let mut items = vec![];
+    loop {
+        match source.poll_next() {
+            Poll::Ready(Some(item)) => items.push(item),
+            Poll::Ready(None) => return (items, true),
+            Poll::Pending => return (items, false),
+        }
+    }
+}
+
+#[test]
+fn event_source() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (sender, mut receiver) = channel::unbounded::<i32>().unwrap();
+    receiver
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Nothing has been sent yet.
+    assert_eq!(drain_ready(&mut receiver), (vec![], false));
+
+    sender.send(1).unwrap();
+    sender.send(2).unwrap();
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    receiver.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(drain_ready(&mut receiver), (vec![1, 2], false));
+
+    // Once the sender is gone, the source is finished.
+    sender.send(3).unwrap();
+    drop(sender);
+    let mut boxed = Box::new(receiver);
+    assert_eq!(drain_ready(&mut boxed), (vec![3], true));
+}
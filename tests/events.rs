@@ -0,0 +1,53 @@
+use polling_utils::event;
+use polling_utils::ping::Ping;
+use polling_utils::{Event, Events, PollMode, Poller, Source};
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn constructors() {
+    let event = event::both(3);
+    assert_eq!(event.key, 3);
+    assert!(event.readable && event.writable);
+
+    let event = event::none(4);
+    assert_eq!(event.key, 4);
+    assert!(!event.readable && !event.writable);
+
+    assert_eq!(event::with(5, true, false), Event::readable(5));
+    assert_eq!(event::with(5, false, true), Event::writable(5));
+}
+
+#[test]
+fn lookup() {
+    let mut events = Events::from(vec![Event::readable(1), Event::writable(2), Event::all(3)]);
+
+    assert_eq!(events.by_key(2), Some(Event::writable(2)));
+    assert_eq!(events.by_key(4), None);
+    assert_eq!(events.readable_keys().collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(events.writable_keys().collect::<Vec<_>>(), [2, 3]);
+    assert_eq!(events.len(), 3);
+
+    events.clear();
+    assert!(events.is_empty());
+    assert_eq!(events.by_key(1), None);
+}
+
+#[test]
+fn wait() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    ping.register(&poller, Event::readable(7), PollMode::Oneshot)
+        .unwrap();
+    ping.notifier().notify().unwrap();
+
+    // The buffer can be passed to the poller directly.
+    let mut events = Events::with_capacity(8);
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.by_key(7), Some(Event::readable(7)));
+    assert_eq!(events.readable_keys().collect::<Vec<_>>(), [7]);
+    ping.handle_event(&poller, events[0]).unwrap();
+}
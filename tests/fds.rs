@@ -0,0 +1,81 @@
+//! Tests that count the file descriptors of the process.
+//!
+//! They live apart from the other tests, which open descriptors concurrently, and take a lock so
+//! that they don't run alongside each other either.
+#![cfg(target_os = "linux")]
+
+use polling_utils::{Event, PollMode, Poller, Source};
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Serializes the tests in this file.
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Take the lock, even if another test panicked while holding it.
+fn lock() -> MutexGuard<'static, ()> {
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Count the open file descriptors of the process.
+fn open_fds() -> usize {
+    std::fs::read_dir("/proc/self/fd").unwrap().count()
+}
+
+#[cfg(feature = "local-ping")]
+#[test]
+fn local_ping_has_no_fd() {
+    use polling_utils::dispatch;
+    use polling_utils::ping::LocalPing;
+
+    let _guard = lock();
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut events = vec![];
+    let before = open_fds();
+
+    // Notifying and dispatching the ping doesn't open a file descriptor.
+    let mut ping = LocalPing::new();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+    notifier.notify();
+    let timeout = Some(Duration::from_secs(1));
+    assert_eq!(
+        dispatch(&poller, &mut events, &mut [&mut ping], timeout).unwrap(),
+        1
+    );
+    assert_eq!(events, [Event::readable(0)]);
+    assert_eq!(open_fds(), before);
+}
+
+#[test]
+fn pool_reuses_fd() {
+    use polling_utils::ping::PingPool;
+
+    let _guard = lock();
+    let poller = Arc::new(Poller::new().unwrap());
+    let pool = PingPool::new();
+
+    // A ping opens a descriptor, which the pool keeps once the ping is dropped.
+    let before = open_fds();
+    let mut ping = pool.acquire().unwrap();
+    let opened = open_fds();
+    assert!(opened > before);
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    ping.notifier().notify().unwrap();
+    ping.deregister(&poller).unwrap();
+    drop(ping);
+    assert_eq!(pool.idle(), 1);
+    assert_eq!(open_fds(), opened);
+
+    // Acquiring it again doesn't open another descriptor.
+    let ping = pool.acquire().unwrap();
+    assert_eq!(open_fds(), opened);
+    assert_eq!(ping.pending(), 0);
+
+    // Once the pool is gone, dropping the ping closes its descriptor.
+    drop(pool);
+    drop(ping);
+    assert_eq!(open_fds(), before);
+}
@@ -0,0 +1,724 @@
+use futures_io::AsyncRead;
+use polling_utils::future::{
+    select, Either, PollBufRead, PollDuplex, PollFuture, PollRead, PollSeek, PollTake, TimedOut,
+    Timeout,
+};
+use polling_utils::timer::TimerWheel;
+use polling_utils::{Event, PollMode, Poller, Source};
+
+use std::future::{pending, poll_fn, ready};
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn buf_read_line() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut reader = PollBufRead::new(&b"hello\nworld\n"[..]).unwrap();
+
+    // Register the source in the poller.
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // One event to start polling.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    reader.handle_event(&poller, events[0]).unwrap();
+
+    // Read the first line out of the buffer.
+    let line = match reader.poll_fill_buf_unpin() {
+        Poll::Ready(Ok(buf)) => {
+            let end = buf.iter().position(|&b| b == b'\n').unwrap() + 1;
+            buf[..end].to_vec()
+        }
+        other => panic!("unexpected poll result: {:?}", other),
+    };
+    reader.consume_unpin(line.len());
+    assert_eq!(line, b"hello\n");
+
+    // The rest of the buffer is still available.
+    match reader.poll_fill_buf_unpin() {
+        Poll::Ready(Ok(buf)) => assert_eq!(buf, b"world\n"),
+        other => panic!("unexpected poll result: {:?}", other),
+    }
+}
+
+#[test]
+fn timeout_expires() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let start = Instant::now();
+    let mut timeout = Timeout::new(pending::<()>(), &mut wheel, Duration::from_millis(50)).unwrap();
+    timeout
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // The future never completes, so the timer wins.
+    let mut events = vec![];
+    let result = loop {
+        if let Poll::Ready(result) = timeout.poll_unpin() {
+            break result;
+        }
+
+        let next = wheel.fire_timers().unwrap();
+        events.clear();
+        poller.wait(&mut events, next).unwrap();
+        for event in &events {
+            timeout.handle_event(&poller, *event).unwrap();
+        }
+    };
+
+    assert_eq!(result, Err(TimedOut));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
+
+#[test]
+fn timeout_pinned() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+
+    // Async blocks are not `Unpin`, so the timeout is pinned to register it.
+    let future = async { pending::<()>().await };
+    let mut timeout =
+        Box::pin(Timeout::new(future, &mut wheel, Duration::from_millis(20)).unwrap());
+    timeout
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let mut events = vec![];
+    let mut timer_events = 0;
+    let result = loop {
+        if let Poll::Ready(result) = timeout.as_mut().poll() {
+            break result;
+        }
+
+        let next = wheel.fire_timers().unwrap();
+        events.clear();
+        poller.wait(&mut events, next).unwrap();
+        for event in &events {
+            // The timer reports its events under the secondary key.
+            if event.key == polling_utils::secondary_key(0) {
+                timer_events += 1;
+            }
+            timeout.handle_event(&poller, *event).unwrap();
+        }
+    };
+
+    assert_eq!(result, Err(TimedOut));
+    assert_eq!(timer_events, 1);
+}
+
+#[test]
+fn timeout_completes() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let mut timeout = Timeout::new(ready(5), &mut wheel, Duration::from_secs(10)).unwrap();
+    timeout
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // The future is polled right away and beats the timer.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    timeout.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(timeout.poll_unpin(), Poll::Ready(Ok(5)));
+}
+
+#[test]
+fn select_ready_side() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let a = PollFuture::new(pending::<i32>()).unwrap();
+    let b = PollFuture::new(ready("done")).unwrap();
+    let mut select = select(a, b);
+    select
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Both futures are woken once on registration, each under its own key.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    let mut keys = events.iter().map(|event| event.key).collect::<Vec<_>>();
+    keys.sort_unstable();
+    assert_eq!(keys, [0, polling_utils::secondary_key(0)]);
+    for event in &events {
+        select.handle_event(&poller, *event).unwrap();
+    }
+
+    // The right side is the one that is ready.
+    assert_eq!(select.poll_unpin(), Poll::Ready(Either::Right("done")));
+
+    // The left side can be recovered.
+    match select.into_remaining() {
+        Some(Either::Left(mut a)) => {
+            a.deregister(&poller).unwrap();
+            assert_eq!(a.poll_unpin(), Poll::Pending);
+        }
+        other => panic!("unexpected remaining future: {:?}", other),
+    }
+}
+
+#[test]
+fn select_routes_by_key() {
+    let poller = Arc::new(Poller::new().unwrap());
+
+    // Async blocks are not `Unpin`, so the select is pinned to register it.
+    let a = PollFuture::new(async { pending::<i32>().await }).unwrap();
+    let b = PollFuture::new(async { "done" }).unwrap();
+    let mut select = Box::pin(select(a, b));
+    select
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Handling only the right side's event leaves the left side's wakeup alone.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    let right = *events
+        .iter()
+        .find(|event| event.key == polling_utils::secondary_key(0))
+        .unwrap();
+    select.handle_event(&poller, right).unwrap();
+    assert_eq!(select.as_mut().poll(), Poll::Ready(Either::Right("done")));
+}
+
+#[test]
+fn read_exact_across_wakeups() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let staged = Staged::default();
+    let mut reader = PollRead::new(staged.clone()).unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    let mut buf = [0u8; 8];
+    let mut filled = 0;
+    let mut events = vec![];
+
+    // Only half of the bytes are available at first.
+    staged.push(b"abcd");
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    reader.handle_event(&poller, events[0]).unwrap();
+    assert!(reader
+        .poll_read_exact_unpin(&mut buf, &mut filled)
+        .is_pending());
+    assert_eq!(filled, 4);
+
+    // The rest of the bytes wake up the reader again.
+    reader
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    staged.push(b"efgh");
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    reader.handle_event(&poller, events[0]).unwrap();
+    match reader.poll_read_exact_unpin(&mut buf, &mut filled) {
+        Poll::Ready(Ok(())) => {}
+        other => panic!("unexpected poll result: {:?}", other),
+    }
+    assert_eq!(filled, 8);
+    assert_eq!(&buf, b"abcdefgh");
+}
+
+#[test]
+fn woken_until_polled() {
+    let slot = Arc::new(Mutex::new(None::<Waker>));
+    let mut future = PollFuture::new(poll_fn({
+        let slot = slot.clone();
+        move |cx| {
+            *slot.lock().unwrap() = Some(cx.waker().clone());
+            Poll::<()>::Pending
+        }
+    }))
+    .unwrap();
+    assert!(!future.woken());
+
+    // Polling hands out the waker.
+    assert!(future.poll_unpin().is_pending());
+    assert!(!future.woken());
+
+    // Waking is visible without polling.
+    slot.lock().unwrap().take().unwrap().wake();
+    assert!(future.woken());
+    assert!(future.woken());
+
+    // Polling clears it again.
+    assert!(future.poll_unpin().is_pending());
+    assert!(!future.woken());
+}
+
+#[test]
+fn read_async_io() {
+    use async_io::Async;
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut writer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let reader = Async::new(listener.accept().unwrap().0).unwrap();
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut reader = PollRead::new(reader).unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // async-io's reactor wakes up our poller once the data arrives.
+    writer.write_all(b"hello").unwrap();
+    let mut buf = [0u8; 5];
+    let mut filled = 0;
+    let mut events = vec![];
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        for event in &events {
+            reader.handle_event(&poller, *event).unwrap();
+        }
+
+        match reader.poll_read_exact_unpin(&mut buf, &mut filled) {
+            Poll::Ready(result) => break result.unwrap(),
+            Poll::Pending => assert!(Instant::now() < deadline),
+        }
+    }
+
+    assert_eq!(&buf, b"hello");
+}
+
+#[test]
+fn duplex_echo() {
+    use async_io::Async;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = Async::<TcpStream>::connect(listener.local_addr().unwrap());
+    let stream = async_io::block_on(stream).unwrap();
+
+    // Echo back everything the other side sends.
+    let echo = thread::spawn(move || {
+        let mut peer = listener.accept().unwrap().0;
+        let mut buf = [0u8; 64];
+        loop {
+            match peer.read(&mut buf).unwrap() {
+                0 => break,
+                n => peer.write_all(&buf[..n]).unwrap(),
+            }
+        }
+    });
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut duplex = PollDuplex::new(stream).unwrap();
+    duplex
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Write the message and read the echo through the same registration.
+    let message = b"hello, duplex";
+    let mut written = 0;
+    let mut echoed = vec![];
+    let mut buf = [0u8; 64];
+    let mut events = vec![];
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while echoed.len() < message.len() {
+        assert!(Instant::now() < deadline);
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        for event in &events {
+            duplex.handle_event(&poller, *event).unwrap();
+        }
+
+        while written < message.len() {
+            match duplex.poll_write_unpin(&message[written..]) {
+                Poll::Ready(n) => written += n.unwrap(),
+                Poll::Pending => break,
+            }
+        }
+
+        match duplex.poll_read_unpin(&mut buf) {
+            Poll::Ready(n) => echoed.extend_from_slice(&buf[..n.unwrap()]),
+            Poll::Pending => assert!(duplex.read_waiting()),
+        }
+    }
+
+    assert_eq!(echoed, message);
+    assert!(!duplex.write_waiting());
+
+    // Writes fail once the stream is closed.
+    assert!(matches!(duplex.poll_close_unpin(), Poll::Ready(Ok(()))));
+    assert!(matches!(
+        duplex.poll_write_unpin(b"late"),
+        Poll::Ready(Err(_))
+    ));
+
+    // Dropping the stream ends the echo loop.
+    drop(duplex);
+    echo.join().unwrap();
+}
+
+#[test]
+fn duplex_oneshot_rearm() {
+    use async_io::Async;
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = Async::<TcpStream>::connect(listener.local_addr().unwrap());
+    let stream = async_io::block_on(stream).unwrap();
+    let mut peer = listener.accept().unwrap().0;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut duplex = PollDuplex::new(stream).unwrap();
+    duplex
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    let mut buf = [0u8; 5];
+    let mut events = vec![];
+    let wait = |duplex: &mut PollDuplex<Async<TcpStream>>, events: &mut Vec<Event>| {
+        events.clear();
+        poller.wait(events, Some(Duration::from_secs(1))).unwrap();
+        for event in events.iter() {
+            duplex.handle_event(&poller, *event).unwrap();
+        }
+    };
+
+    // The first event comes from registering the duplex, and disarms the registration.
+    assert!(duplex.poll_read_unpin(&mut buf).is_pending());
+    wait(&mut duplex, &mut events);
+    assert_eq!(events, [Event::readable(0)]);
+
+    // Each read that has to wait re-arms the registration, without re-registering it.
+    for message in [b"hello", b"world"] {
+        assert!(duplex.poll_read_unpin(&mut buf).is_pending());
+        assert!(duplex.read_waiting());
+        peer.write_all(message).unwrap();
+        wait(&mut duplex, &mut events);
+        assert_eq!(events, [Event::readable(0)]);
+        match duplex.poll_read_unpin(&mut buf) {
+            Poll::Ready(n) => assert_eq!(&buf[..n.unwrap()], message),
+            Poll::Pending => panic!("the read was woken up without data"),
+        }
+    }
+
+    duplex.deregister(&poller).unwrap();
+}
+
+#[test]
+fn reset_after_completion() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut future = PollFuture::new(ready(1)).unwrap();
+    future
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Poll the first future to completion.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    future.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(future.poll_unpin(), Poll::Ready(1));
+
+    // Swap in a new future, which wakes up the poll loop again.
+    future.reset(ready(2));
+    future
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    future.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(future.poll_unpin(), Poll::Ready(2));
+}
+
+/// A reader whose bytes are pushed in by the test.
+#[derive(Clone, Default)]
+struct Staged(Arc<Mutex<(Vec<u8>, Option<Waker>)>>);
+
+impl Staged {
+    fn push(&self, bytes: &[u8]) {
+        let mut state = self.0.lock().unwrap();
+        state.0.extend_from_slice(bytes);
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl AsyncRead for Staged {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.0.lock().unwrap();
+        if state.0.is_empty() {
+            state.1 = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let n = buf.len().min(state.0.len());
+        buf[..n].copy_from_slice(&state.0[..n]);
+        state.0.drain(..n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+#[test]
+fn read_as_async_read() {
+    use futures_lite::io::AsyncReadExt;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut reader = PollRead::new(&b"hello world"[..]).unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Read through the poll loop first.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    reader.handle_event(&poller, events[0]).unwrap();
+
+    let mut buf = [0u8; 6];
+    match reader.poll_unpin(&mut buf) {
+        Poll::Ready(result) => assert_eq!(result.unwrap(), 6),
+        Poll::Pending => panic!("reader should be ready"),
+    }
+    assert_eq!(&buf, b"hello ");
+
+    // Then hand it to async code.
+    let n = futures_lite::future::block_on(reader.read(&mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"world");
+}
+
+#[test]
+fn seek_stream_position() {
+    use futures_lite::io::Cursor;
+    use std::io::SeekFrom;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut seeker = PollSeek::new(Cursor::new(vec![0u8; 16])).unwrap();
+    seeker
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    seeker.handle_event(&poller, events[0]).unwrap();
+
+    match seeker.poll_unpin(SeekFrom::Start(5)) {
+        Poll::Ready(result) => assert_eq!(result.unwrap(), 5),
+        Poll::Pending => panic!("seeker should be ready"),
+    }
+
+    // The position is relative to the last seek.
+    match seeker.poll_stream_position_unpin() {
+        Poll::Ready(result) => assert_eq!(result.unwrap(), 5),
+        Poll::Pending => panic!("seeker should be ready"),
+    }
+}
+
+#[test]
+fn poll_with_context() {
+    use std::task::Wake;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+
+    let slot = Arc::new(Mutex::new(None::<Waker>));
+    let mut polls = 0;
+    let mut future = PollFuture::new(poll_fn({
+        let slot = slot.clone();
+        move |cx| {
+            polls += 1;
+            *slot.lock().unwrap() = Some(cx.waker().clone());
+            if polls == 2 {
+                Poll::Ready(polls)
+            } else {
+                Poll::Pending
+            }
+        }
+    }))
+    .unwrap();
+
+    // The future sees the caller's waker rather than the internal one.
+    assert!(future.poll_with_context_unpin(&mut cx).is_pending());
+    let stored = slot.lock().unwrap().take().unwrap();
+    assert!(stored.will_wake(&waker));
+
+    // Waking it does not wake up the poll loop.
+    stored.wake();
+    assert!(!future.woken());
+
+    assert_eq!(future.poll_with_context_unpin(&mut cx), Poll::Ready(2));
+}
+
+#[test]
+fn write_owned() {
+    use futures_io::AsyncWrite;
+    use polling_utils::future::PollWrite;
+
+    /// Accepts a chunk of bytes every other poll.
+    struct Chunked {
+        data: Vec<u8>,
+        ready: bool,
+    }
+
+    impl AsyncWrite for Chunked {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            let n = buf.len().min(1000);
+            self.data.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut writer = PollWrite::new(Chunked {
+        data: vec![],
+        ready: false,
+    })
+    .unwrap();
+    writer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let data = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+    let mut buf = data.clone();
+    let mut polls = 0;
+    let mut events = vec![];
+    loop {
+        let len = buf.len();
+        match writer.poll_write_owned_unpin(&mut buf) {
+            Poll::Ready(result) => break result.unwrap(),
+            Poll::Pending => assert!(buf.len() < len || polls == 0),
+        }
+        polls += 1;
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert!(!events.is_empty(), "timed out waiting for an event");
+        for event in &events {
+            writer.handle_event(&poller, *event).unwrap();
+        }
+    }
+
+    assert!(buf.is_empty());
+    assert!(polls > 1);
+    assert_eq!(writer.writer().data, data);
+}
+
+#[test]
+fn take_limit() {
+    let data = vec![7u8; 1024];
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut reader: PollTake<&[u8]> = PollRead::new(&data[..]).unwrap().take(100);
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    reader.handle_event(&poller, events[0]).unwrap();
+
+    // Read in chunks that do not divide the limit evenly.
+    let mut total = 0;
+    let mut buf = [0u8; 64];
+    loop {
+        match reader.poll_unpin(&mut buf) {
+            Poll::Ready(Ok(0)) => break,
+            Poll::Ready(Ok(n)) => total += n,
+            Poll::Ready(Err(err)) => panic!("read failed: {}", err),
+            Poll::Pending => panic!("reader should be ready"),
+        }
+    }
+
+    assert_eq!(total, 100);
+    assert_eq!(reader.limit(), 0);
+    assert_eq!(reader.into_inner().into_reader().len(), 1024 - 100);
+}
+
+#[test]
+fn take_overlong_read() {
+    /// A reader that claims to have read more bytes than it was given room for.
+    struct Overlong;
+
+    impl AsyncRead for Overlong {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Ok(buf.len() + 1))
+        }
+    }
+
+    let mut reader = PollRead::new(Overlong).unwrap().take(10);
+    let mut buf = [0u8; 64];
+    match reader.poll_unpin(&mut buf) {
+        Poll::Ready(Err(err)) => assert_eq!(err.kind(), io::ErrorKind::InvalidData),
+        _ => panic!("the overlong read was not reported"),
+    }
+    assert_eq!(reader.limit(), 10);
+}
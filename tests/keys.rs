@@ -0,0 +1,63 @@
+use polling_utils::KeyAllocator;
+
+#[test]
+fn recycle() {
+    let mut keys = KeyAllocator::new();
+    assert_eq!(keys.alloc().unwrap(), 0);
+    assert_eq!(keys.alloc().unwrap(), 1);
+    assert_eq!(keys.alloc().unwrap(), 2);
+
+    // Freed keys are handed out again.
+    keys.free(1).unwrap();
+    assert_eq!(keys.alloc().unwrap(), 1);
+    assert_eq!(keys.alloc().unwrap(), 3);
+}
+
+#[test]
+fn default() {
+    let mut keys = KeyAllocator::default();
+    assert_eq!(keys.alloc().unwrap(), 0);
+    assert_eq!(keys.alloc().unwrap(), 1);
+}
+
+#[test]
+fn reserved_base() {
+    let mut keys = KeyAllocator::with_base(1);
+    let first = keys.alloc().unwrap();
+    assert_eq!(first, 1);
+    keys.free(first).unwrap();
+
+    for _ in 0..10 {
+        assert_ne!(keys.alloc().unwrap(), 0);
+    }
+}
+
+#[test]
+fn exhausted() {
+    let mut keys = KeyAllocator::with_base(usize::MAX - 1);
+    assert_eq!(keys.alloc().unwrap(), usize::MAX - 1);
+    assert_eq!(keys.alloc().unwrap(), usize::MAX);
+
+    // Every key is in use.
+    assert!(keys.alloc().is_err());
+
+    // Freeing one makes room again.
+    keys.free(usize::MAX).unwrap();
+    assert_eq!(keys.alloc().unwrap(), usize::MAX);
+    assert!(keys.alloc().is_err());
+}
+
+#[test]
+fn free_invalid() {
+    let mut keys = KeyAllocator::with_base(1);
+    let key = keys.alloc().unwrap();
+
+    // Keys below the base, or never handed out, are rejected.
+    assert!(keys.free(0).is_err());
+    assert!(keys.free(key + 1).is_err());
+
+    // So is freeing a key twice.
+    keys.free(key).unwrap();
+    assert!(keys.free(key).is_err());
+    assert_eq!(keys.alloc().unwrap(), key);
+}
@@ -0,0 +1,136 @@
+use polling_utils::net::{Acceptor, UdpSource};
+use polling_utils::{Event, PollMode, Poller, Source};
+
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+
+#[test]
+fn udp_send_recv() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut sender = UdpSource::bind("127.0.0.1:0").unwrap();
+    let mut receiver = UdpSource::bind("127.0.0.1:0").unwrap();
+    let sender_addr = sender.socket().local_addr().unwrap();
+    let receiver_addr = receiver.socket().local_addr().unwrap();
+
+    // Register both sources in the same poller.
+    sender
+        .register(&poller, Event::none(0), PollMode::Oneshot)
+        .unwrap();
+    receiver
+        .register(&poller, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Nothing has been sent yet.
+    let mut buf = [0u8; 16];
+    assert!(receiver.recv_from(&mut buf).is_pending());
+
+    // Send a datagram.
+    match sender.send_to(b"hello", receiver_addr) {
+        Poll::Ready(Ok(5)) => {}
+        other => panic!("unexpected send result: {:?}", other),
+    }
+
+    // The receiver is woken up.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+    receiver.handle_event(&poller, events[0]).unwrap();
+
+    match receiver.recv_from(&mut buf) {
+        Poll::Ready(Ok((5, addr))) => assert_eq!(addr, sender_addr),
+        other => panic!("unexpected receive result: {:?}", other),
+    }
+    assert_eq!(&buf[..5], b"hello");
+
+    // Nothing else was sent, and the receiver was re-armed for the next datagram.
+    assert!(receiver.recv_from(&mut buf).is_pending());
+    match sender.send_to(b"world", receiver_addr) {
+        Poll::Ready(Ok(5)) => {}
+        other => panic!("unexpected send result: {:?}", other),
+    }
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+
+    sender.deregister(&poller).unwrap();
+    receiver.deregister(&poller).unwrap();
+}
+
+#[test]
+fn accept_oneshot() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut acceptor = Acceptor::bind("127.0.0.1:0").unwrap();
+    let addr = acceptor.listener().local_addr().unwrap();
+    acceptor
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    let first = TcpStream::connect(addr).unwrap();
+    let second = TcpStream::connect(addr).unwrap();
+
+    // Each wakeup accepts one connection, and the listener is re-armed for the next.
+    let mut events = vec![];
+    for client in [&first, &second] {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(0)]);
+        acceptor.handle_event(&poller, events[0]).unwrap();
+
+        match acceptor.accept() {
+            Poll::Ready(Ok((_, peer))) => assert_eq!(peer, client.local_addr().unwrap()),
+            other => panic!("unexpected accept result: {:?}", other),
+        }
+    }
+
+    // No more connections.
+    assert!(acceptor.accept().is_pending());
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    acceptor.deregister(&poller).unwrap();
+}
+
+#[test]
+fn accept_level() {
+    let poller = Arc::new(Poller::new().unwrap());
+    if !poller.supports_level() {
+        return;
+    }
+
+    let mut acceptor = Acceptor::bind("127.0.0.1:0").unwrap();
+    let addr = acceptor.listener().local_addr().unwrap();
+    acceptor
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let _first = TcpStream::connect(addr).unwrap();
+    let _second = TcpStream::connect(addr).unwrap();
+
+    // A single wakeup accepts every pending connection.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    acceptor.handle_event(&poller, events[0]).unwrap();
+
+    let mut accepted = 0;
+    while let Poll::Ready(result) = acceptor.accept() {
+        result.unwrap();
+        accepted += 1;
+    }
+    assert_eq!(accepted, 2);
+
+    acceptor.deregister(&poller).unwrap();
+}
@@ -1,4 +1,4 @@
-use polling_utils::ping::Ping;
+use polling_utils::ping::{MultiPing, Notifier, Ping};
 use polling_utils::{Event, PollMode, Poller, Source};
 
 use std::sync::Arc;
@@ -59,3 +59,1071 @@ fn oneshot() {
         .unwrap();
     assert!(events.is_empty());
 }
+
+#[test]
+fn counter_mode() {
+    let poller = Arc::new(Poller::new().unwrap());
+    if !poller.supports_level() {
+        return;
+    }
+
+    let mut ping = Ping::with_semaphore(false).unwrap();
+    let notifier = ping.notifier();
+
+    // Register the source in the poller.
+    ping.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Notify many times.
+    for _ in 0..10 {
+        notifier.notify().unwrap();
+    }
+
+    // Wait for the event.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    ping.handle_event(&poller, events[0]).unwrap();
+
+    // A single event consumed every notification.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn wrong_key() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    notifier.notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    // An event for another key does not consume the notification.
+    ping.handle_event(&poller, Event::readable(1)).unwrap();
+    assert_eq!(ping.pending(), 1);
+
+    // The matching event does.
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 0);
+
+    ping.deregister(&poller).unwrap();
+}
+
+#[test]
+fn registered() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::registered(&poller, Event::readable(7), PollMode::Level).unwrap();
+    assert_eq!(
+        ping.registration(),
+        Some((Event::readable(7), PollMode::Level))
+    );
+
+    // The ping is ready to be notified right away.
+    ping.notifier().notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(7)]);
+    ping.handle_event(&poller, events[0]).unwrap();
+
+    ping.deregister(&poller).unwrap();
+}
+
+#[test]
+fn rearm_default() {
+    use polling_utils::ping::PingBuilder;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = PingBuilder::new()
+        .default_interest(Event::readable(4), PollMode::Oneshot)
+        .build()
+        .unwrap();
+    let notifier = ping.notifier();
+
+    // The first call registers the ping with its default interest.
+    ping.rearm_default(&poller).unwrap();
+    assert_eq!(
+        ping.registration(),
+        Some((Event::readable(4), PollMode::Oneshot))
+    );
+
+    let mut events = vec![];
+    for _ in 0..3 {
+        notifier.notify().unwrap();
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(4)]);
+        ping.handle_event(&poller, events[0]).unwrap();
+
+        // Later calls re-arm it without passing the interest again.
+        ping.rearm_default(&poller).unwrap();
+    }
+
+    // A ping without a default falls back to its last registration.
+    let mut plain = Ping::new().unwrap();
+    assert!(plain.rearm_default(&poller).is_err());
+    plain
+        .register(&poller, Event::readable(5), PollMode::Oneshot)
+        .unwrap();
+    plain.rearm_default(&poller).unwrap();
+
+    ping.deregister(&poller).unwrap();
+    plain.deregister(&poller).unwrap();
+}
+
+#[test]
+fn edge_mode() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+
+    // Edge mode is rejected by pollers that do not support it.
+    let result = ping.register(&poller, Event::readable(0), PollMode::Edge);
+    if !poller.supports_edge() {
+        assert!(result.is_err());
+        return;
+    }
+    result.unwrap();
+
+    // Notify twice.
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+
+    // Handling the event drains every notification, even in semaphore mode.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 0);
+
+    // No more events.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // A later notification fires again.
+    notifier.notify().unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 0);
+}
+
+#[test]
+fn two_pollers() {
+    let primary = Arc::new(Poller::new().unwrap());
+    let monitor = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+
+    // Register the same source in both pollers.
+    ping.register(&primary, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    ping.register(&monitor, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Notify once.
+    notifier.notify().unwrap();
+
+    // Both pollers wake up, each with its own key.
+    let mut primary_events = vec![];
+    primary
+        .wait(&mut primary_events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(primary_events, [Event::readable(0)]);
+
+    let mut monitor_events = vec![];
+    monitor
+        .wait(&mut monitor_events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(monitor_events, [Event::readable(1)]);
+
+    // An event is only handled along with the poller that reported it.
+    assert!(!ping
+        .handle_event_progress(&primary, monitor_events[0])
+        .unwrap());
+    assert_eq!(ping.pending(), 1);
+
+    // Handling the event in one of them consumes the notification.
+    assert!(ping
+        .handle_event_progress(&monitor, monitor_events[0])
+        .unwrap());
+    assert_eq!(ping.pending(), 0);
+
+    // That leaves the other poller's event with nothing to drain.
+    assert!(!ping
+        .handle_event_progress(&primary, primary_events[0])
+        .unwrap());
+    assert_eq!(ping.take_count(), 1);
+
+    ping.deregister(&primary).unwrap();
+    ping.deregister(&monitor).unwrap();
+}
+
+#[test]
+fn two_pollers_oneshot() {
+    let primary = Arc::new(Poller::new().unwrap());
+    let monitor = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::with_semaphore(true).unwrap();
+    let notifier = ping.notifier();
+
+    ping.register(&primary, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    ping.register(&monitor, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Notify twice, so that each event consumes one notification.
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+
+    let mut primary_events = vec![];
+    primary
+        .wait(&mut primary_events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(primary_events, [Event::readable(0)]);
+
+    let mut monitor_events = vec![];
+    monitor
+        .wait(&mut monitor_events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(monitor_events, [Event::readable(1)]);
+
+    // Handle the event in one poller and rearm it there.
+    assert!(ping
+        .handle_event_progress(&primary, primary_events[0])
+        .unwrap());
+    assert_eq!(ping.pending(), 1);
+    ping.reregister(&primary, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // The other poller's event is still recognized after its oneshot interest fired.
+    assert!(ping
+        .handle_event_progress(&monitor, monitor_events[0])
+        .unwrap());
+    assert_eq!(ping.pending(), 0);
+
+    ping.deregister(&primary).unwrap();
+    ping.deregister(&monitor).unwrap();
+}
+
+#[test]
+fn pending() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    assert_eq!(ping.pending(), 0);
+
+    // Register the source in the poller.
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Notify twice.
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+    assert_eq!(ping.pending(), 2);
+
+    // Handling the event consumes one notification.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 1);
+}
+
+#[test]
+fn waker() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let waker = ping.notifier().into_waker();
+
+    // Register the source in the poller.
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Waking the waker notifies the ping.
+    waker.wake_by_ref();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    ping.handle_event(&poller, events[0]).unwrap();
+}
+
+#[test]
+fn tuple() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut pings = (Ping::new().unwrap(), Ping::new().unwrap());
+    let notifiers = (pings.0.notifier(), pings.1.notifier());
+
+    // Register both pings at once.
+    pings
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Notify both.
+    notifiers.0.notify().unwrap();
+    notifiers.1.notify().unwrap();
+
+    let mut events = vec![];
+    while events.len() < 2 {
+        poller
+            .wait(&mut events, Some(Duration::from_millis(100)))
+            .unwrap();
+    }
+    assert_eq!(events, [Event::readable(0), Event::readable(0)]);
+
+    // One event is handled by both pings.
+    pings.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(pings.0.pending(), 0);
+    assert_eq!(pings.1.pending(), 0);
+
+    // When only one is notified, the shared event is spurious for the other.
+    pings
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    notifiers.1.notify().unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    pings.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(pings.0.take_count(), 1);
+    assert_eq!(pings.1.take_count(), 2);
+
+    // Deregister both and go again.
+    pings.deregister(&poller).unwrap();
+    notifiers.0.notify().unwrap();
+    notifiers.1.notify().unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn handle_events() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Notify twice and handle both notifications in one batch.
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+    ping.handle_events(&poller, &[Event::readable(0), Event::readable(0)])
+        .unwrap();
+    assert_eq!(ping.pending(), 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn raw_fd() {
+    use polling_utils::os::unix::PingExt;
+    use std::os::unix::io::AsRawFd;
+
+    let poller = Poller::new().unwrap();
+    let ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+
+    // Register the descriptor directly, bypassing `Source`.
+    let fd = ping.as_fd().as_raw_fd();
+    poller.add(fd, Event::readable(0)).unwrap();
+
+    // No events.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // The notifier makes the descriptor readable.
+    notifier.notify().unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    poller.delete(fd).unwrap();
+}
+
+#[test]
+fn notifier_identity() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    fn hash(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let ping = Ping::new().unwrap();
+    let other = Ping::new().unwrap();
+
+    // Clones of a notifier refer to the same ping.
+    let notifier = ping.notifier();
+    let clone = notifier.clone();
+    assert_eq!(notifier, clone);
+    assert_eq!(notifier, ping.notifier());
+    assert_eq!(notifier.id(), clone.id());
+    assert_eq!(hash(&notifier), hash(&clone));
+
+    // Another ping has a different notifier.
+    assert_ne!(notifier, other.notifier());
+    assert_ne!(notifier.id(), other.notifier().id());
+}
+
+#[test]
+fn notify_batch() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut pings = (0..5).map(|_| Ping::new().unwrap()).collect::<Vec<_>>();
+    for (key, ping) in pings.iter_mut().enumerate() {
+        ping.register(&poller, Event::readable(key), PollMode::Oneshot)
+            .unwrap();
+    }
+
+    // One ping has been notified already.
+    pings[0].notifier().notify().unwrap();
+    pings[0].notifier().notify().unwrap();
+
+    // Notify 500 notifiers that share a handful of pings.
+    let notifiers = (0..500)
+        .map(|i| pings[i % pings.len()].notifier())
+        .collect::<Vec<_>>();
+    Notifier::notify_batch(&notifiers).unwrap();
+
+    // Each ping is written to once, and the one that was pending is skipped.
+    assert_eq!(pings[0].pending(), 2);
+    for ping in &pings[1..] {
+        assert_eq!(ping.pending(), 1);
+    }
+
+    // Each ping is woken up, and its notifications are consumed one at a time.
+    let mut events = vec![];
+    while events.len() < pings.len() {
+        poller
+            .wait(&mut events, Some(Duration::from_millis(100)))
+            .unwrap();
+    }
+    for event in &events {
+        let ping = &mut pings[event.key];
+        ping.handle_event(&poller, *event).unwrap();
+        assert_eq!(ping.pending(), if event.key == 0 { 1 } else { 0 });
+    }
+}
+
+#[test]
+fn event_key() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    assert_eq!(ping.event_key(), None);
+
+    ping.register(&poller, Event::readable(7), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(ping.event_key(), Some(7));
+
+    // The key is kept after the oneshot registration fires.
+    ping.notifier().notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events[0].key, 7);
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.event_key(), Some(7));
+
+    ping.reregister(&poller, Event::readable(8), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(ping.event_key(), Some(8));
+
+    ping.deregister(&poller).unwrap();
+    assert_eq!(ping.event_key(), None);
+}
+
+#[test]
+fn rearm_without_draining() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+
+    // Nothing to re-arm before the ping is registered.
+    assert!(ping.rearm(&poller).is_err());
+
+    ping.register(&poller, Event::readable(4), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(
+        ping.registration(),
+        Some((Event::readable(4), PollMode::Oneshot))
+    );
+    ping.notifier().notify().unwrap();
+
+    // Re-arming without handling the event reports it again.
+    let mut events = vec![];
+    for _ in 0..3 {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(4)]);
+        ping.rearm(&poller).unwrap();
+    }
+    assert_eq!(ping.pending(), 1);
+
+    // Once the event is handled, the ping is quiet.
+    ping.handle_event(&poller, events[0]).unwrap();
+    ping.rearm(&poller).unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pipe_backend() {
+    use polling_utils::os::unix::PingExt;
+    use std::os::unix::io::AsRawFd;
+
+    // Asking for a pipe capacity forces the pipe backend, as if eventfds were unavailable.
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::with_pipe_capacity(4096).unwrap();
+    let fd = ping.as_fd().as_raw_fd();
+    let link = std::fs::read_link(format!("/proc/self/fd/{}", fd)).unwrap();
+    assert!(link.to_string_lossy().starts_with("pipe:"), "{:?}", link);
+
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    let notifier = ping.notifier();
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+
+    // Each event consumes one notification.
+    let mut events = vec![];
+    for remaining in [1, 0] {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(0)]);
+        ping.handle_event(&poller, events[0]).unwrap();
+        assert_eq!(ping.pending(), remaining);
+        ping.rearm(&poller).unwrap();
+    }
+
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn small_pipe_capacity() {
+    use polling_utils::os::unix::PingExt;
+    use rustix::pipe::fcntl_getpipe_size;
+
+    // The kernel rounds the capacity up to a power of two pages.
+    let ping = Ping::with_pipe_capacity(1).unwrap();
+    let page = fcntl_getpipe_size(ping.as_fd()).unwrap();
+    assert!(page.is_power_of_two(), "{}", page);
+    let ping = Ping::with_pipe_capacity(5 * page).unwrap();
+    assert_eq!(fcntl_getpipe_size(ping.as_fd()).unwrap(), 8 * page);
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::with_pipe_capacity(page).unwrap();
+    assert_eq!(fcntl_getpipe_size(ping.as_fd()).unwrap(), page);
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Notifications still go through.
+    notifier.notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 0);
+}
+
+#[test]
+fn multi_ping_notify_one() {
+    let first = Arc::new(Poller::new().unwrap());
+    let second = Arc::new(Poller::new().unwrap());
+    let mut ping = MultiPing::new();
+
+    ping.register(&first, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+    ping.register(&second, Event::readable(2), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(ping.len(), 2);
+
+    // Only one of the pollers is woken.
+    assert!(ping.notify_one().unwrap());
+    let mut first_events = vec![];
+    let mut second_events = vec![];
+    first
+        .wait(&mut first_events, Some(Duration::from_millis(100)))
+        .unwrap();
+    second
+        .wait(&mut second_events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(first_events.len() + second_events.len(), 1);
+
+    // Both are woken by `notify_all`.
+    let (woken, idle, mut events, key) = if first_events.is_empty() {
+        (&second, &first, second_events, 1)
+    } else {
+        (&first, &second, first_events, 2)
+    };
+    ping.handle_event(woken, events[0]).unwrap();
+    ping.rearm(woken).unwrap();
+    ping.notify_all().unwrap();
+    events.clear();
+    woken
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    events.clear();
+    idle.wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(key)]);
+
+    // Targets whose poller is dropped are pruned.
+    drop(second);
+    assert!(ping.notify_one().unwrap());
+    assert_eq!(ping.len(), 1);
+
+    // Deregistering twice is fine, like for a `Ping`.
+    ping.deregister(&first).unwrap();
+    ping.deregister(&first).unwrap();
+    assert_eq!(ping.len(), 0);
+}
+
+#[test]
+fn try_notify_full_pipe() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::with_pipe_capacity(4096).unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Fill the pipe until notifying would block.
+    let mut sent = 0;
+    while notifier.try_notify().unwrap() {
+        sent += 1;
+        assert!(sent < 1 << 20, "the pipe never filled up");
+    }
+    assert_eq!(ping.pending(), sent);
+
+    // The pipe is readable already, so further notifications are merged instead of failing.
+    notifier.notify().unwrap();
+    Notifier::notify_batch(&[notifier.clone(), notifier.clone()]).unwrap();
+    assert_eq!(ping.pending(), sent);
+
+    // Draining the pipe lets notifications through again.
+    let mut events = vec![];
+    while ping.pending() > 0 {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_millis(100)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(0)]);
+        ping.handle_event(&poller, events[0]).unwrap();
+    }
+    assert!(notifier.try_notify().unwrap());
+}
+
+#[test]
+fn take_count() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::with_semaphore(false).unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Three notifications are merged into one wakeup.
+    for _ in 0..3 {
+        notifier.notify().unwrap();
+    }
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    ping.handle_event(&poller, events[0]).unwrap();
+
+    assert_eq!(ping.take_count(), 3);
+    assert_eq!(ping.take_count(), 0);
+}
+
+#[test]
+fn wait_for_notify() {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct CountWakes(AtomicUsize);
+
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    let wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+    let waker = Waker::from(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    // Nothing has been notified yet.
+    let mut wait = polling_utils::ping::wait(&ping);
+    assert!(Pin::new(&mut wait).poll(&mut cx).is_pending());
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 0);
+
+    // Notifying wakes the task, and the future completes.
+    notifier.notify().unwrap();
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+    assert_eq!(Pin::new(&mut wait).poll(&mut cx), Poll::Ready(()));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn builder_backends() {
+    use polling_utils::os::unix::PingExt;
+    use polling_utils::ping::{PingBackend, PingBuilder};
+    use std::os::unix::io::AsRawFd;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    for (backend, kind) in [
+        (PingBackend::Pipe, "pipe:"),
+        (PingBackend::Eventfd, "anon_inode:[eventfd]"),
+    ] {
+        let mut ping = PingBuilder::new().backend(backend).build().unwrap();
+        let fd = ping.as_fd().as_raw_fd();
+        let link = std::fs::read_link(format!("/proc/self/fd/{}", fd)).unwrap();
+        assert!(link.to_string_lossy().starts_with(kind), "{:?}", link);
+
+        // The usual oneshot flow works on either backend.
+        ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+            .unwrap();
+        ping.notifier().notify().unwrap();
+        let mut events = vec![];
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(0)]);
+        ping.handle_event(&poller, events[0]).unwrap();
+        assert_eq!(ping.pending(), 0);
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_millis(50)))
+            .unwrap();
+        assert!(events.is_empty());
+        ping.deregister(&poller).unwrap();
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn eventfd_overflow() {
+    use polling_utils::os::unix::PingExt;
+    use polling_utils::ping::{PingBackend, PingBuilder};
+    use std::fs::File;
+    use std::io::Write;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = PingBuilder::new()
+        .backend(PingBackend::Eventfd)
+        .build()
+        .unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Fill the counter up to its maximum, so that the next write fails with `EAGAIN`.
+    let mut eventfd = File::from(ping.as_fd().try_clone_to_owned().unwrap());
+    eventfd.write_all(&(u64::MAX - 1).to_ne_bytes()).unwrap();
+    let err = eventfd.write(&1u64.to_ne_bytes()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+    // Notifying coalesces into the pending notification instead of failing.
+    notifier.notify().unwrap();
+    Notifier::notify_batch(&[notifier.clone(), notifier.clone()]).unwrap();
+    assert_eq!(ping.pending(), 0);
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    ping.deregister(&poller).unwrap();
+}
+
+#[cfg(all(windows, polling_utils_socket_ping))]
+#[test]
+fn socket_backend() {
+    use polling_utils::os::windows::PingExt;
+    use std::os::windows::io::AsRawSocket;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    assert_ne!(ping.as_socket().as_raw_socket(), 0);
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Each event consumes one of the bytes written to the socket.
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 1);
+
+    ping.reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 0);
+
+    // Nothing is left to report.
+    ping.reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+    ping.deregister(&poller).unwrap();
+}
+
+#[cfg(all(windows, not(polling_utils_socket_ping)))]
+#[test]
+fn iocp_coalesced_posts() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Many notifications only post a single packet.
+    for _ in 0..100 {
+        notifier.notify().unwrap();
+    }
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    // Handling it consumes one notification and posts one packet for the rest.
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.pending(), 99);
+
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    ping.deregister(&poller).unwrap();
+}
+
+#[cfg(all(windows, not(polling_utils_socket_ping)))]
+#[test]
+fn iocp_register_notified() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+
+    // Registering a ping that was notified beforehand posts a packet right away.
+    notifier.notify().unwrap();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    // The oneshot interest was used up by that packet.
+    notifier.notify().unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Re-registering in level mode posts one packet, and notifying meanwhile adds no more.
+    ping.reregister(&poller, Event::readable(1), PollMode::Level)
+        .unwrap();
+    notifier.notify().unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Handling it consumes one notification and posts a packet for the rest.
+    ping.handle_event(&poller, Event::readable(1)).unwrap();
+    assert_eq!(ping.pending(), 2);
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+    ping.deregister(&poller).unwrap();
+}
+
+#[cfg(feature = "local-ping")]
+#[test]
+fn local_ping() {
+    use polling_utils::dispatch;
+    use polling_utils::ping::LocalPing;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = LocalPing::new();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Nothing happens at first.
+    let mut events = vec![];
+    let timeout = Some(Duration::from_millis(10));
+    assert_eq!(
+        dispatch(&poller, &mut events, &mut [&mut ping], timeout).unwrap(),
+        0
+    );
+
+    // A notified ping is dispatched without blocking on the poller.
+    notifier.notify();
+    assert!(ping.is_pending());
+    assert_eq!(
+        dispatch(&poller, &mut events, &mut [&mut ping], None).unwrap(),
+        1
+    );
+    assert_eq!(events, [Event::readable(0)]);
+    assert!(!ping.is_pending());
+
+    // A oneshot ping has to be re-armed before it fires again.
+    notifier.notify();
+    assert_eq!(ping.pending_event(), None);
+    ping.rearm(&poller).unwrap();
+    assert_eq!(ping.pending_event(), Some(Event::readable(0)));
+}
+
+#[test]
+fn spurious_wakeup() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    ping.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // A real notification makes progress.
+    notifier.notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    assert!(ping.handle_event_progress(&poller, events[0]).unwrap());
+
+    // Handling the event again is a spurious wakeup, and is not an error.
+    assert!(!ping.handle_event_progress(&poller, events[0]).unwrap());
+    ping.handle_event(&poller, events[0]).unwrap();
+
+    // Neither is an event for another source.
+    notifier.notify().unwrap();
+    assert!(!ping
+        .handle_event_progress(&poller, Event::readable(1))
+        .unwrap());
+    assert_eq!(ping.pending(), 1);
+
+    ping.deregister(&poller).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn pool_reuses_ping() {
+    use polling_utils::ping::PingPool;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let pool = PingPool::new();
+
+    // Use a ping, leaving a notification behind.
+    let mut ping = pool.acquire().unwrap();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    let notifier = ping.notifier();
+    notifier.notify().unwrap();
+    notifier.notify().unwrap();
+    drop(notifier);
+    ping.deregister(&poller).unwrap();
+    drop(ping);
+    assert_eq!(pool.idle(), 1);
+
+    // The ping comes back without the old notifications.
+    let ping = pool.acquire().unwrap();
+    assert_eq!(ping.pending(), 0);
+    assert_eq!(pool.idle(), 0);
+
+    // A ping that could still be notified is not reused.
+    let notifier = ping.notifier();
+    drop(ping);
+    assert_eq!(pool.idle(), 0);
+    drop(notifier);
+
+    // Neither is one that is still registered.
+    let mut ping = pool.acquire().unwrap();
+    ping.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    drop(ping);
+    assert_eq!(pool.idle(), 0);
+}
@@ -0,0 +1,40 @@
+#![cfg(target_os = "linux")]
+
+use polling_utils::process::{ChildExit, Pid};
+use polling_utils::{Event, PollMode, Poller, Source};
+
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn child_exit() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut child = Command::new("/bin/true").spawn().unwrap();
+    let mut exit = ChildExit::new(Pid::from_child(&child)).unwrap();
+
+    // Register the source in the poller.
+    exit.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    assert!(exit.status().is_none());
+
+    // Wait for the child to exit.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+
+    // An event for another key doesn't reap the child.
+    exit.handle_event(&poller, Event::readable(1)).unwrap();
+    assert!(exit.status().is_none());
+
+    exit.handle_event(&poller, events[0]).unwrap();
+
+    let status = exit.status().unwrap();
+    assert!(status.success());
+
+    // The child has already been reaped.
+    assert!(child.wait().is_err());
+}
@@ -0,0 +1,591 @@
+use polling_utils::ping::Ping;
+use polling_utils::reactor::Reactor;
+use polling_utils::timer::{TimerDriver, TimerId, TimerWheel};
+use polling_utils::{dispatch, Dispatcher, Event, PollMode, Poller, Source};
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn ping_and_timer() {
+    let mut reactor = Reactor::new().unwrap();
+    let mut wheel = TimerWheel::new();
+
+    // Insert a ping and a timer.
+    let ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    let (ping, ping_count) = Counting::new(ping);
+    reactor
+        .insert(0, ping, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    let timer = wheel.interval(Duration::from_millis(50)).unwrap();
+    let (timer, timer_count) = Counting::new(timer);
+    reactor
+        .insert(1, timer, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Nothing happens at first.
+    assert_eq!(reactor.poll(Some(Duration::from_millis(10))).unwrap(), 0);
+
+    // Notifying the ping dispatches to it.
+    notifier.notify().unwrap();
+    assert_eq!(reactor.poll(Some(Duration::from_millis(100))).unwrap(), 1);
+    assert_eq!(ping_count.load(Ordering::SeqCst), 1);
+    assert_eq!(timer_count.load(Ordering::SeqCst), 0);
+
+    // Firing the timer dispatches to it.
+    thread::sleep(Duration::from_millis(60));
+    wheel.fire_timers().unwrap();
+    assert_eq!(reactor.poll(Some(Duration::from_millis(100))).unwrap(), 1);
+    assert_eq!(ping_count.load(Ordering::SeqCst), 1);
+    assert_eq!(timer_count.load(Ordering::SeqCst), 1);
+
+    // Removed sources are no longer dispatched.
+    assert!(reactor.remove(0).unwrap().is_some());
+    assert!(reactor.remove(0).unwrap().is_none());
+    notifier.notify().unwrap();
+    assert_eq!(reactor.poll(Some(Duration::from_millis(100))).unwrap(), 0);
+}
+
+#[test]
+fn fair_dispatch() {
+    let mut reactor = Reactor::new().unwrap();
+    if !reactor.poller().supports_level() {
+        return;
+    }
+    reactor.set_fair(true);
+
+    // Insert two pings that stay ready for every poll.
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for key in 0..2 {
+        let ping = Ping::new().unwrap();
+        for _ in 0..8 {
+            ping.notifier().notify().unwrap();
+        }
+
+        let ping = Logging {
+            inner: ping,
+            order: order.clone(),
+        };
+        reactor
+            .insert(key, ping, Event::readable(key), PollMode::Level)
+            .unwrap();
+    }
+
+    // Every poll services both, and each gets to go first.
+    let mut first = vec![];
+    for _ in 0..4 {
+        assert_eq!(reactor.poll(Some(Duration::from_millis(100))).unwrap(), 2);
+        let mut order = order.lock().unwrap();
+        first.push(order[0]);
+        order.clear();
+    }
+    assert!(first.contains(&0));
+    assert!(first.contains(&1));
+}
+
+#[test]
+fn shutdown() {
+    let mut reactor = Reactor::new().unwrap();
+    let poller = reactor.poller().clone();
+
+    // Insert two pings.
+    let mut notifiers = vec![];
+    for key in [1, 0] {
+        let ping = Ping::new().unwrap();
+        notifiers.push(ping.notifier());
+        reactor
+            .insert(key, ping, Event::readable(key), PollMode::Oneshot)
+            .unwrap();
+    }
+
+    // Both come back, and neither is registered anymore.
+    let sources = reactor.shutdown().unwrap();
+    assert_eq!(
+        sources.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+        [0, 1]
+    );
+    assert!(sources
+        .iter()
+        .all(|(_, source)| source.registration().is_none()));
+
+    for notifier in &notifiers {
+        notifier.notify().unwrap();
+    }
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn shutdown_error() {
+    let mut reactor = Reactor::new().unwrap();
+    let poller = reactor.poller().clone();
+    let ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    reactor
+        .insert(0, Stuck, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    reactor
+        .insert(1, ping, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // The error comes back once every source has been deregistered, including the ping after
+    // the source that failed.
+    match reactor.shutdown() {
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+        Ok(_) => panic!("shutting down succeeded"),
+    }
+    let mut events = vec![];
+    notifier.notify().unwrap();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn remove_error() {
+    let mut reactor = Reactor::new().unwrap();
+    reactor
+        .insert(0, Stuck, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // The source that fails to deregister stays in the reactor.
+    match reactor.remove(0) {
+        Err(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+        Ok(_) => panic!("removing the source succeeded"),
+    }
+    let err = reactor
+        .insert(0, Stuck, Event::readable(0), PollMode::Oneshot)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+}
+
+/// A source that cannot be deregistered.
+struct Stuck;
+
+impl Source for Stuck {
+    fn register(&mut self, _: &Arc<Poller>, _: Event, _: PollMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn reregister(&mut self, _: &Arc<Poller>, _: Event, _: PollMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn deregister(&mut self, _: &Poller) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "stuck"))
+    }
+
+    fn handle_event(&mut self, _: &Poller, _: Event) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A source that records the keys of the events it handles.
+struct Logging<S> {
+    inner: S,
+    order: Arc<Mutex<Vec<usize>>>,
+}
+
+impl<S: Source> Source for Logging<S> {
+    fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.register(poller, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> io::Result<()> {
+        self.inner.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> io::Result<()> {
+        self.order.lock().unwrap().push(event.key);
+        self.inner.handle_event(poller, event)
+    }
+}
+
+#[test]
+fn error_does_not_starve() {
+    let mut reactor = Reactor::new().unwrap();
+
+    // Three pings, the middle of which fails to handle its event.
+    let mut notifiers = vec![];
+    let mut counts = vec![];
+    for key in 0..3 {
+        let ping = Ping::new().unwrap();
+        notifiers.push(ping.notifier());
+        let (ping, count) = Counting::new(Failing {
+            inner: ping,
+            fail: key == 1,
+        });
+        counts.push(count);
+        reactor
+            .insert(key, ping, Event::readable(0), PollMode::Oneshot)
+            .unwrap();
+    }
+
+    for notifier in &notifiers {
+        notifier.notify().unwrap();
+    }
+
+    // The error is reported, but the other sources still saw their events.
+    let mut seen = 0;
+    while seen < 3 {
+        match reactor.poll(Some(Duration::from_secs(1))) {
+            Ok(n) => seen += n,
+            Err(err) => {
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+                seen = counts.iter().map(|c| c.load(Ordering::SeqCst)).sum();
+            }
+        }
+    }
+    for count in &counts {
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+}
+
+/// A source that can be made to fail when it handles an event.
+struct Failing<S> {
+    inner: S,
+    fail: bool,
+}
+
+impl<S: Source> Source for Failing<S> {
+    fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.register(poller, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> io::Result<()> {
+        self.inner.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> io::Result<()> {
+        self.inner.handle_event(poller, event)?;
+        if self.fail {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "failed on purpose",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn dispatch_to_slice() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut events = Vec::new();
+
+    let first = Ping::new().unwrap();
+    let first_notifier = first.notifier();
+    let (mut first, first_count) = Counting::new(first);
+    first
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let second = Ping::new().unwrap();
+    let second_notifier = second.notifier();
+    let (mut second, second_count) = Counting::new(second);
+    second
+        .register(&poller, Event::readable(1), PollMode::Level)
+        .unwrap();
+
+    // Nothing happens at first.
+    let timeout = Some(Duration::from_millis(10));
+    let handled = dispatch(
+        &poller,
+        &mut events,
+        &mut [&mut first, &mut second],
+        timeout,
+    );
+    assert_eq!(handled.unwrap(), 0);
+
+    // Each event goes to the source at its key.
+    second_notifier.notify().unwrap();
+    let timeout = Some(Duration::from_millis(100));
+    let handled = dispatch(
+        &poller,
+        &mut events,
+        &mut [&mut first, &mut second],
+        timeout,
+    );
+    assert_eq!(handled.unwrap(), 1);
+    assert_eq!(first_count.load(Ordering::SeqCst), 0);
+    assert_eq!(second_count.load(Ordering::SeqCst), 1);
+
+    first_notifier.notify().unwrap();
+    second_notifier.notify().unwrap();
+    let handled = dispatch(
+        &poller,
+        &mut events,
+        &mut [&mut first, &mut second],
+        timeout,
+    );
+    assert_eq!(handled.unwrap(), 2);
+    assert_eq!(first_count.load(Ordering::SeqCst), 1);
+    assert_eq!(second_count.load(Ordering::SeqCst), 2);
+
+    // Events with no source at their key are ignored.
+    second_notifier.notify().unwrap();
+    let handled = dispatch(&poller, &mut events, &mut [&mut first], timeout);
+    assert_eq!(handled.unwrap(), 0);
+}
+
+/// A source that counts the events it handles.
+struct Counting<S> {
+    inner: S,
+    count: Arc<AtomicUsize>,
+}
+
+impl<S> Counting<S> {
+    fn new(inner: S) -> (Self, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        (
+            Self {
+                inner,
+                count: count.clone(),
+            },
+            count,
+        )
+    }
+}
+
+impl<S: Source> Source for Counting<S> {
+    fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.register(poller, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> io::Result<()> {
+        self.inner.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> io::Result<()> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        self.inner.handle_event(poller, event)
+    }
+}
+
+#[test]
+fn dispatcher_reuses_buffer() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut pings = (0..4)
+        .map(|key| {
+            let mut ping = Ping::new().unwrap();
+            ping.register(&poller, Event::readable(key), PollMode::Level)
+                .unwrap();
+            ping
+        })
+        .collect::<Vec<_>>();
+    let notifiers = pings.iter().map(Ping::notifier).collect::<Vec<_>>();
+
+    let mut dispatcher = Dispatcher::new(&poller, 8);
+    let capacity = dispatcher.capacity();
+    let buffer = dispatcher.events().as_ptr();
+    assert!(capacity >= 8);
+
+    for _ in 0..100 {
+        for notifier in &notifiers {
+            notifier.notify().unwrap();
+        }
+
+        let mut sources = pings
+            .iter_mut()
+            .map(|ping| ping as &mut dyn Source)
+            .collect::<Vec<_>>();
+        let handled = dispatcher
+            .poll_and_dispatch(&mut sources, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(handled, 4);
+        assert_eq!(dispatcher.events().len(), 4);
+
+        // The buffer is never reallocated.
+        assert_eq!(dispatcher.capacity(), capacity);
+        assert_eq!(dispatcher.events().as_ptr(), buffer);
+    }
+
+    for ping in &mut pings {
+        assert_eq!(ping.pending(), 0);
+        ping.deregister(&poller).unwrap();
+    }
+}
+
+#[test]
+fn waker() {
+    use std::time::Instant;
+
+    let mut reactor = Reactor::new().unwrap();
+    reactor
+        .insert(0, Ping::new().unwrap(), Event::readable(0), PollMode::Level)
+        .unwrap();
+    let waker = reactor.waker();
+
+    // Wake the reactor up from another thread while it is blocked.
+    let wake = thread::spawn({
+        let waker = waker.clone();
+        move || {
+            thread::sleep(Duration::from_millis(50));
+            waker.wake().unwrap();
+        }
+    });
+
+    let start = Instant::now();
+    assert_eq!(reactor.poll(Some(Duration::from_secs(10))).unwrap(), 0);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    wake.join().unwrap();
+
+    // Waking after the reactor is gone does nothing.
+    drop(reactor);
+    waker.wake().unwrap();
+}
+
+#[test]
+fn secondary_keys() {
+    use polling_utils::future::{select, PollFuture};
+    use std::future::{pending, ready};
+
+    let mut reactor = Reactor::new().unwrap();
+    let a = PollFuture::new(pending::<()>()).unwrap();
+    let b = PollFuture::new(ready(())).unwrap();
+    reactor
+        .insert(3, select(a, b), Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Both halves of the select are routed to it.
+    let mut dispatched = 0;
+    while dispatched < 2 {
+        dispatched += reactor.poll(Some(Duration::from_secs(1))).unwrap();
+    }
+    assert_eq!(dispatched, 2);
+
+    // Secondary keys are reserved for composite sources.
+    let key = polling_utils::secondary_key(4);
+    let err = reactor
+        .insert(
+            key,
+            Ping::new().unwrap(),
+            Event::readable(0),
+            PollMode::Oneshot,
+        )
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn timer_driver() {
+    let mut reactor = Reactor::new().unwrap();
+    let mut driver = TimerDriver::new().unwrap();
+    let now = Instant::now();
+    let ids = [30, 10, 20]
+        .iter()
+        .map(|&delay| driver.at(now + Duration::from_millis(delay)))
+        .collect::<Vec<_>>();
+
+    let fired = Arc::new(Mutex::new(vec![]));
+    let driven = Driven {
+        inner: driver,
+        fired: fired.clone(),
+    };
+    reactor
+        .insert(0, driven, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // The driver wakes the poller up by itself, without a timeout.
+    while fired.lock().unwrap().len() < ids.len() {
+        assert!(
+            now.elapsed() < Duration::from_secs(5),
+            "timers did not fire"
+        );
+        reactor.poll(None).unwrap();
+    }
+
+    assert_eq!(*fired.lock().unwrap(), [ids[1], ids[2], ids[0]]);
+    assert!(now.elapsed() >= Duration::from_millis(30));
+}
+
+/// A timer driver that records the timers that fired.
+///
+/// It doesn't report the driver's pending event, so only the driver's wakeups reach it.
+struct Driven {
+    inner: TimerDriver,
+    fired: Arc<Mutex<Vec<TimerId>>>,
+}
+
+impl Source for Driven {
+    fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.register(poller, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.inner.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> io::Result<()> {
+        self.inner.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> io::Result<()> {
+        self.inner.handle_event(poller, event)?;
+        let inner = &mut self.inner;
+        let mut fired = self.fired.lock().unwrap();
+        fired.extend(std::iter::from_fn(|| inner.next_expired()));
+        Ok(())
+    }
+}
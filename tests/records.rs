@@ -0,0 +1,33 @@
+use polling_utils::RecordReader;
+
+#[test]
+fn partial_record_retained() {
+    let mut records = RecordReader::new();
+
+    // Two and a half four-byte records.
+    records.extend(&[1, 1, 1, 1, 2, 2, 2, 2, 3, 3]);
+    assert_eq!(records.next_record(|_| Some(4)), Some(&[1, 1, 1, 1][..]));
+    assert_eq!(records.next_record(|_| Some(4)), Some(&[2, 2, 2, 2][..]));
+    assert_eq!(records.next_record(|_| Some(4)), None);
+    assert_eq!(records.buffered(), &[3, 3]);
+
+    // The rest of the record completes it.
+    records.extend(&[3, 3]);
+    assert_eq!(records.next_record(|_| Some(4)), Some(&[3, 3, 3, 3][..]));
+    assert!(records.buffered().is_empty());
+}
+
+#[test]
+fn variable_length_records() {
+    let mut records = RecordReader::new();
+
+    // Each record starts with its own length.
+    let record_len = |bytes: &[u8]| bytes.first().map(|&len| len as usize);
+    records.extend(&[2, 0, 3]);
+    assert_eq!(records.next_record(record_len), Some(&[2, 0][..]));
+    assert_eq!(records.next_record(record_len), None);
+
+    records.extend(&[0, 0]);
+    assert_eq!(records.next_record(record_len), Some(&[3, 0, 0][..]));
+    assert_eq!(records.next_record(record_len), None);
+}
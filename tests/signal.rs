@@ -0,0 +1,41 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use polling_utils::signal::{Signal, Signals};
+use polling_utils::{Event, PollMode, Poller, Source};
+
+use nix::sys::signal::raise;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn sigusr1() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut signals = Signals::new(&[Signal::SIGUSR1]).unwrap();
+
+    // Register the source in the poller.
+    signals
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Raise the signal twice; it is only reported once.
+    raise(Signal::SIGUSR1).unwrap();
+    raise(Signal::SIGUSR1).unwrap();
+
+    // Wait for the event.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+
+    // An event for another key leaves the signals alone.
+    signals.handle_event(&poller, Event::readable(1)).unwrap();
+    assert_eq!(signals.next(), None);
+
+    signals.handle_event(&poller, events[0]).unwrap();
+
+    assert_eq!(signals.next(), Some(Signal::SIGUSR1));
+    assert_eq!(signals.next(), None);
+}
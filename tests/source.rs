@@ -1,6 +1,10 @@
-use polling_utils::{Event, PollMode, Poller, Socket, Source};
+use polling_utils::ping::Ping;
+use polling_utils::source::{self, AutoRearm, Remap};
+use polling_utils::{
+    dispatch, BoxSource, Event, NoopSource, PollMode, Poller, Registered, Socket, Source,
+};
 
-use std::io::prelude::*;
+use std::io::{self, prelude::*};
 use std::net::{TcpListener, TcpStream};
 use std::sync::Arc;
 use std::time::Duration;
@@ -123,6 +127,852 @@ fn level() {
     assert!(events.is_empty());
 }
 
+#[test]
+fn with_interest() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+    let _reader =
+        Socket::with_interest(reader, &poller, Event::readable(0), PollMode::Oneshot).unwrap();
+
+    // The socket is already registered.
+    writer.write_all(b"hello").unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+}
+
+#[test]
+fn double_register() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, _writer) = tcp_pipe();
+    let mut reader = Socket::new(reader);
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Registering again fails without touching the first registration.
+    let err = reader
+        .register(&poller, Event::readable(1), PollMode::Level)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(
+        reader.registration(),
+        Some((Event::readable(0), PollMode::Oneshot))
+    );
+
+    // After deregistering it can be registered again.
+    reader.deregister(&poller).unwrap();
+    reader
+        .register(&poller, Event::readable(1), PollMode::Level)
+        .unwrap();
+    reader.deregister(&poller).unwrap();
+}
+
+#[test]
+fn two_pollers() {
+    let worker = Arc::new(Poller::new().unwrap());
+    let monitor = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+    let mut reader = Socket::new(reader);
+
+    // Register the socket into both pollers under different keys.
+    reader
+        .register(&worker, Event::readable(0), PollMode::Level)
+        .unwrap();
+    reader
+        .register(&monitor, Event::readable(1), PollMode::Level)
+        .unwrap();
+    assert_eq!(
+        reader.registration_in(&worker),
+        Some((Event::readable(0), PollMode::Level))
+    );
+    assert_eq!(
+        reader.registration_in(&monitor),
+        Some((Event::readable(1), PollMode::Level))
+    );
+
+    // Both pollers report the event.
+    writer.write_all(b"hello").unwrap();
+    let mut events = vec![];
+    worker
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    events.clear();
+    monitor
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+
+    // Deregistering from one poller leaves the other registration alone.
+    reader.deregister(&worker).unwrap();
+    assert_eq!(reader.registration_in(&worker), None);
+
+    events.clear();
+    worker
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    events.clear();
+    monitor
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+    reader.deregister(&monitor).unwrap();
+    assert_eq!(reader.interest(), None);
+}
+
+#[test]
+fn toggle_interest() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+    let mut reader = Socket::new(reader);
+
+    // Toggling interest requires the socket to be registered.
+    assert!(reader.set_writable(&poller, true).is_err());
+    assert_eq!(reader.interest(), None);
+
+    // Register as readable, then add writable interest.
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    reader.set_writable(&poller, true).unwrap();
+    assert_eq!(reader.interest(), Some(Event::all(0)));
+
+    // The poller reports both.
+    writer.write_all(b"hello").unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::all(0));
+
+    // Deregistering clears the interest.
+    reader.deregister(&poller).unwrap();
+    assert_eq!(reader.interest(), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn shared_udp_socket() {
+    use std::net::UdpSocket;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let receiver = Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap());
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut socket = Socket::new(receiver.clone());
+
+    // Register the shared socket in the poller.
+    socket
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Send a datagram to it.
+    sender
+        .send_to(b"hello", receiver.local_addr().unwrap())
+        .unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+
+    socket.deregister(&poller).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn borrowed_socket() {
+    use polling_utils::Borrowed;
+    use std::net::UdpSocket;
+    use std::os::unix::io::{AsFd, BorrowedFd};
+
+    // A socket that only lends out its descriptor.
+    struct Lent(Arc<UdpSocket>);
+
+    impl AsFd for Lent {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.0.as_fd()
+        }
+    }
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let receiver = Arc::new(UdpSocket::bind("127.0.0.1:0").unwrap());
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut socket = Socket::new(Borrowed(Lent(receiver.clone())));
+
+    // Register the socket through its borrowed descriptor.
+    socket
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    sender
+        .send_to(b"hello", receiver.local_addr().unwrap())
+        .unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    socket.deregister(&poller).unwrap();
+}
+
+// `Arc` forwards `AsRawFd` on Unix, but not `AsRawSocket` on Windows.
+#[cfg(unix)]
+#[test]
+fn tuple_rollback() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, _writer) = tcp_pipe();
+    let reader = Arc::new(reader);
+
+    // Registering the same socket twice fails on the second element.
+    let mut sockets = (Socket::new(reader.clone()), Socket::new(reader));
+    assert!(sockets
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .is_err());
+
+    // The first element was rolled back, so it can be registered again.
+    assert_eq!(sockets.0.interest(), None);
+    sockets
+        .0
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    sockets.0.deregister(&poller).unwrap();
+}
+
+#[test]
+fn boxed() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+    let ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+
+    // Store both sources behind the same type.
+    let mut sources: Vec<BoxSource> = vec![Box::new(ping), Box::new(Socket::new(reader))];
+    for (key, source) in sources.iter_mut().enumerate() {
+        source
+            .register(&poller, Event::readable(key), PollMode::Oneshot)
+            .unwrap();
+    }
+
+    // Trigger both sources.
+    notifier.notify().unwrap();
+    writer.write_all(b"hello").unwrap();
+
+    // Both events are dispatched through the trait objects.
+    let mut events = vec![];
+    while events.len() < 2 {
+        poller
+            .wait(&mut events, Some(Duration::from_millis(100)))
+            .unwrap();
+    }
+    assert_eq!(events.len(), 2);
+    for event in &events {
+        sources[event.key].handle_event(&poller, *event).unwrap();
+    }
+
+    for source in &mut sources {
+        source.deregister(&poller).unwrap();
+    }
+}
+
+#[test]
+fn supports_mode() {
+    let poller = Arc::new(Poller::new().unwrap());
+
+    // Oneshot mode is always supported.
+    assert!(Socket::<TcpStream>::supports_mode(
+        &poller,
+        PollMode::Oneshot
+    ));
+    assert!(Ping::supports_mode(&poller, PollMode::Oneshot));
+
+    // The other modes depend on the poller.
+    assert_eq!(
+        Socket::<TcpStream>::supports_mode(&poller, PollMode::Level),
+        poller.supports_level()
+    );
+    assert_eq!(
+        Socket::<TcpStream>::supports_mode(&poller, PollMode::Edge),
+        poller.supports_edge()
+    );
+    if poller.supports_level() {
+        assert!(Ping::supports_mode(&poller, PollMode::Level));
+        assert!(<(Ping, Ping)>::supports_mode(&poller, PollMode::Level));
+    }
+
+    // Pointers to a source forward its restrictions.
+    assert!(OneshotOnly::supports_mode(&poller, PollMode::Oneshot));
+    assert!(!OneshotOnly::supports_mode(&poller, PollMode::Level));
+    assert!(!Box::<OneshotOnly>::supports_mode(&poller, PollMode::Level));
+    assert!(!<&mut OneshotOnly>::supports_mode(&poller, PollMode::Level));
+    assert!(<&mut OneshotOnly>::supports_mode(
+        &poller,
+        PollMode::Oneshot
+    ));
+}
+
+/// A source that can only be registered in oneshot mode.
+struct OneshotOnly;
+
+impl Source for OneshotOnly {
+    fn register(&mut self, _: &Arc<Poller>, _: Event, _: PollMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn reregister(&mut self, _: &Arc<Poller>, _: Event, _: PollMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn deregister(&mut self, _: &Poller) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn handle_event(&mut self, _: &Poller, _: Event) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn supports_mode(_: &Arc<Poller>, mode: PollMode) -> bool {
+        mode == PollMode::Oneshot
+    }
+}
+
+#[test]
+fn split_halves() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (mut writer, reader) = tcp_pipe();
+    let (mut read, mut write) = Socket::new(reader).split().unwrap();
+
+    // Register each half on its own key.
+    read.register(&poller, Event::all(0), PollMode::Oneshot)
+        .unwrap();
+    write
+        .register(&poller, Event::all(1), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(read.interest(), Some(Event::readable(0)));
+    assert_eq!(write.interest(), Some(Event::writable(1)));
+
+    // The halves share one registration, so the write half can't use another mode.
+    assert!(write
+        .reregister(&poller, Event::writable(1), PollMode::Level)
+        .is_err());
+    assert_eq!(write.interest(), Some(Event::writable(1)));
+
+    // The socket is writable. The event arrives under the read half's key, which hands it over
+    // to the write half.
+    let mut events = vec![];
+    let timeout = Some(Duration::from_millis(100));
+    let sources: &mut [&mut dyn Source] = &mut [&mut read, &mut write];
+    dispatch(&poller, &mut events, sources, timeout).unwrap();
+    assert_eq!(events, [Event::writable(0)]);
+    assert_eq!(write.pending_event(), Some(Event::writable(1)));
+    assert_eq!(read.pending_event(), None);
+
+    let sources: &mut [&mut dyn Source] = &mut [&mut read, &mut write];
+    dispatch(&poller, &mut events, sources, timeout).unwrap();
+    assert_eq!(events, [Event::writable(1)]);
+    assert_eq!(write.pending_event(), None);
+
+    // The write half's oneshot event doesn't disarm the read half.
+    writer.write_all(b"hello").unwrap();
+    let sources: &mut [&mut dyn Source] = &mut [&mut read, &mut write];
+    dispatch(&poller, &mut events, sources, timeout).unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    assert_eq!(write.pending_event(), None);
+
+    // Both halves are disarmed now.
+    let sources: &mut [&mut dyn Source] = &mut [&mut read, &mut write];
+    dispatch(&poller, &mut events, sources, timeout).unwrap();
+    assert!(events.is_empty());
+
+    // Without the read half, the registration moves to the write half's key.
+    read.deregister(&poller).unwrap();
+    write
+        .reregister(&poller, Event::writable(1), PollMode::Oneshot)
+        .unwrap();
+    let sources: &mut [&mut dyn Source] = &mut [&mut read, &mut write];
+    dispatch(&poller, &mut events, sources, timeout).unwrap();
+    assert_eq!(events, [Event::writable(1)]);
+    assert_eq!(write.pending_event(), None);
+
+    // Both halves share the socket.
+    let mut buf = [0u8; 5];
+    read.socket().read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+    write.socket().write_all(b"world").unwrap();
+
+    write.deregister(&poller).unwrap();
+}
+
+#[test]
+fn registered_drop() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+
+    // Register a duplicate of the socket through the guard, keeping the original open.
+    let registered = Registered::new(
+        Socket::new(reader.try_clone().unwrap()),
+        &poller,
+        Event::readable(0),
+        PollMode::Level,
+    )
+    .unwrap();
+    assert_eq!(registered.interest(), Some(Event::readable(0)));
+
+    // The source is armed while the guard is alive.
+    writer.write_all(b"hello").unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    // Dropping the guard deregisters the source, even though it is still readable.
+    drop(registered);
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // The socket can be registered again.
+    let mut reader = Socket::new(reader);
+    reader
+        .register(&poller, Event::readable(1), PollMode::Level)
+        .unwrap();
+    reader.deregister(&poller).unwrap();
+}
+
+#[test]
+fn poller_dropped() {
+    let (reader, _writer) = tcp_pipe();
+    let mut reader = Socket::new(reader);
+    let mut ping = Ping::new().unwrap();
+
+    // Register the sources, then drop the poller.
+    let poller = Arc::new(Poller::new().unwrap());
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    ping.register(&poller, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+    drop(poller);
+
+    // Deregistering has nothing left to do.
+    let poller = Arc::new(Poller::new().unwrap());
+    reader.deregister(&poller).unwrap();
+    ping.deregister(&poller).unwrap();
+    assert_eq!(reader.interest(), None);
+
+    // Re-registering a source whose poller was dropped fails.
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    drop(poller);
+    let poller = Arc::new(Poller::new().unwrap());
+    let err = reader
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotConnected);
+
+    // The source can be registered into the new poller.
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    reader.deregister(&poller).unwrap();
+}
+
+#[test]
+fn next_event() {
+    use futures_lite::future;
+    use std::thread;
+
+    let poller = Arc::new(Poller::new().unwrap());
+
+    // Dropping the future before the event arrives deregisters the source.
+    let (source, wait) = source::next_event(
+        Ping::new().unwrap(),
+        &poller,
+        Event::readable(0),
+        PollMode::Oneshot,
+    )
+    .unwrap();
+    assert!(source.registration().is_some());
+    drop(wait);
+    assert_eq!(source.registration(), None);
+
+    // A task on another thread awaits the event.
+    let ping = Ping::new().unwrap();
+    let notifier = ping.notifier();
+    let (mut source, wait) =
+        source::next_event(ping, &poller, Event::readable(0), PollMode::Oneshot).unwrap();
+    let task = thread::spawn(move || future::block_on(wait));
+
+    // Give the task time to start waiting.
+    thread::sleep(Duration::from_millis(50));
+
+    // The loop driving the poller hands the source its event, which wakes the task.
+    let mut events = vec![];
+    let mut other = Ping::new().unwrap();
+    other
+        .register(&poller, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+    other.notifier().notify().unwrap();
+    notifier.notify().unwrap();
+    let sources: &mut [&mut dyn Source] = &mut [&mut source, &mut other];
+    assert_eq!(
+        dispatch(&poller, &mut events, sources, Some(Duration::from_secs(1))).unwrap(),
+        2
+    );
+    assert_eq!(other.take_count(), 1);
+
+    // The future resolves with the first event, and the source is deregistered.
+    assert_eq!(task.join().unwrap(), Event::readable(0));
+    assert_eq!(source.registration(), None);
+    other.deregister(&poller).unwrap();
+}
+
+#[test]
+fn auto_rearm() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = AutoRearm::new(Ping::new().unwrap());
+    let notifier = ping.get_ref().notifier();
+
+    ping.register(&poller, Event::readable(3), PollMode::Oneshot)
+        .unwrap();
+
+    // The ping fires every time it is notified, without re-registering by hand.
+    let mut events = vec![];
+    for _ in 0..5 {
+        notifier.notify().unwrap();
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(3)]);
+        ping.handle_event(&poller, events[0]).unwrap();
+    }
+
+    // Nothing fires once the notifications are handled.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    ping.deregister(&poller).unwrap();
+}
+
+#[test]
+fn remap() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Remap::new(Ping::new().unwrap(), 0, 100);
+    let notifier = ping.get_ref().notifier();
+
+    // The ping is registered under the outer key 100, so its events are routed by that key.
+    ping.register(&poller, Event::readable(100), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(
+        ping.registration(),
+        Some((Event::readable(100), PollMode::Oneshot))
+    );
+    assert_eq!(
+        ping.get_ref().registration(),
+        Some((Event::readable(100), PollMode::Oneshot))
+    );
+
+    notifier.notify().unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(100)]);
+    assert_eq!(ping.map_event(events[0]), events[0]);
+    ping.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(ping.get_ref().pending(), 0);
+
+    // The inner key 0 is translated to the outer key on the way in.
+    assert_eq!(ping.map_event(Event::readable(0)), Event::readable(100));
+    ping.reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(
+        ping.registration(),
+        Some((Event::readable(100), PollMode::Oneshot))
+    );
+
+    // Events with the inner key reach the ping as well.
+    notifier.notify().unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(100)]);
+    ping.handle_event(&poller, Event::readable(0)).unwrap();
+    assert_eq!(ping.get_ref().pending(), 0);
+
+    ping.deregister(&poller).unwrap();
+}
+
+#[test]
+fn noop_source() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut source: BoxSource = Box::new(NoopSource);
+
+    source
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    source
+        .reregister(&poller, Event::all(0), PollMode::Level)
+        .unwrap();
+    source.handle_event(&poller, Event::readable(0)).unwrap();
+
+    // Nothing was registered, so nothing fires.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    source.deregister(&poller).unwrap();
+}
+
+#[test]
+fn borrowed_poller() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (mut writer, reader) = tcp_pipe();
+    let mut reader = Socket::new(reader);
+    let mut ping = Ping::new().unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    ping.register(&poller, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Events are handled and sources deregistered through a plain reference.
+    let borrowed: &Poller = &poller;
+    writer.write_all(&[1]).unwrap();
+    ping.notifier().notify().unwrap();
+    let mut events = vec![];
+    while events.len() < 2 {
+        borrowed
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+    }
+    for &event in &events {
+        reader.handle_event(borrowed, event).unwrap();
+        ping.handle_event(borrowed, event).unwrap();
+    }
+    assert_eq!(ping.pending(), 0);
+
+    reader.deregister(borrowed).unwrap();
+    ping.deregister(borrowed).unwrap();
+    assert_eq!(reader.interest(), None);
+}
+
+#[test]
+fn mut_ref_source() {
+    fn register_and_fire(mut source: impl Source, poller: &Arc<Poller>) -> Vec<Event> {
+        source
+            .register(poller, Event::readable(5), PollMode::Oneshot)
+            .unwrap();
+
+        let mut events = vec![];
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        for &event in &events {
+            source.handle_event(poller, event).unwrap();
+        }
+        events
+    }
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut ping = Ping::new().unwrap();
+    ping.notifier().notify().unwrap();
+
+    // The ping is only borrowed, so it can still be used afterwards.
+    let events = register_and_fire(&mut ping, &poller);
+    assert_eq!(events, [Event::readable(5)]);
+    assert_eq!(ping.pending(), 0);
+    ping.deregister(&poller).unwrap();
+}
+
+#[test]
+fn from_registered() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+
+    // Register the socket without going through `Socket`.
+    poller
+        .add_with_mode(&reader, Event::readable(0), PollMode::Level)
+        .unwrap();
+    let mut reader = Socket::from_registered(reader, &poller, Event::readable(0), PollMode::Level);
+    assert_eq!(reader.key(), Some(0));
+
+    // Deregistering removes it from the poller.
+    reader.deregister(&poller).unwrap();
+    writer.write_all(&[1]).unwrap();
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // So it can be added again.
+    poller
+        .add_with_mode(reader.socket(), Event::readable(1), PollMode::Level)
+        .unwrap();
+    poller.delete(reader.socket()).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn owned_fd() {
+    use std::os::unix::io::OwnedFd;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+    let mut reader = Socket::new(OwnedFd::from(reader));
+
+    // A plain descriptor registers like any other socket.
+    reader
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    writer.write_all(&[1]).unwrap();
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    reader.deregister(&poller).unwrap();
+}
+
+#[test]
+fn try_handle_events() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut pings = (Ping::new().unwrap(), Ping::new().unwrap());
+    pings.0.notifier().notify().unwrap();
+    pings.1.notifier().notify().unwrap();
+
+    // The second event fails, since the ping was not registered under its key.
+    pings
+        .0
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    pings
+        .1
+        .register(&poller, Event::readable(2), PollMode::Oneshot)
+        .unwrap();
+    let events = [Event::readable(0), Event::readable(1), Event::readable(2)];
+    let mut handled = vec![];
+    let errors = {
+        let mut source = Recording {
+            pings: &mut pings,
+            handled: &mut handled,
+        };
+        source.try_handle_events(&poller, &events)
+    };
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, Event::readable(1));
+    assert_eq!(handled, [0, 2]);
+    assert_eq!(pings.0.pending(), 0);
+    assert_eq!(pings.1.pending(), 0);
+}
+
+/// Routes events to one of two pings by key, failing for any other key.
+struct Recording<'a> {
+    pings: &'a mut (Ping, Ping),
+    handled: &'a mut Vec<usize>,
+}
+
+impl Source for Recording<'_> {
+    fn register(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pings.register(poller, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poller: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pings.reregister(poller, interest, mode)
+    }
+
+    fn deregister(&mut self, poller: &Poller) -> io::Result<()> {
+        self.pings.deregister(poller)
+    }
+
+    fn handle_event(&mut self, poller: &Poller, event: Event) -> io::Result<()> {
+        match event.key {
+            0 => self.pings.0.handle_event(poller, event)?,
+            2 => self.pings.1.handle_event(poller, event)?,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "unknown key")),
+        }
+        self.handled.push(event.key);
+        Ok(())
+    }
+}
+
+#[test]
+fn level_to_oneshot() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (reader, mut writer) = tcp_pipe();
+    let mut reader = Socket::new(reader);
+    reader
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+    writer.write_all(b"hello").unwrap();
+
+    // In level mode the unread data keeps firing.
+    let mut events = vec![];
+    for _ in 0..2 {
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(0)]);
+    }
+
+    // After switching to oneshot mode it fires once.
+    reader
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(
+        reader.registration(),
+        Some((Event::readable(0), PollMode::Oneshot))
+    );
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    reader.deregister(&poller).unwrap();
+}
+
 fn tcp_pipe() -> (TcpStream, TcpStream) {
     let listener = TcpListener::bind("127.0.0.1:0").unwrap();
     let addr = listener.local_addr().unwrap();
@@ -1,10 +1,16 @@
-use polling_utils::threadpool::UnblockFn;
+use polling_utils::threadpool::{
+    UnblockFn, UnblockInterval, UnblockReader, UnblockSeek, UnblockWriter,
+};
+use polling_utils::timer::TimerWheel;
 use polling_utils::{Event, PollMode, Poller, Source};
 
-use std::sync::{mpsc, Arc};
+use std::fs::{self, File};
+use std::io::{self, prelude::*, IoSlice, SeekFrom};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::task::Poll;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[test]
 fn unblock_fn() {
@@ -63,3 +69,441 @@ fn unblock_fn() {
     // Resolved now.
     assert_eq!(unblocked.result(), Poll::Ready(5));
 }
+
+#[test]
+fn unblock_fn_cancel() {
+    let (send, recv) = mpsc::channel();
+    let unblocked = UnblockFn::new(move || {
+        // Send the thread handle back and start waiting.
+        send.send(thread::current()).unwrap();
+        thread::park();
+        5
+    })
+    .unwrap();
+    let handle = recv.recv().unwrap();
+
+    // The closure is still running, so there is no result.
+    assert_eq!(unblocked.cancel(), None);
+    handle.unpark();
+}
+
+#[test]
+fn unblock_fn_detach() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let unblocked = UnblockFn::new({
+        let flag = flag.clone();
+        move || {
+            thread::sleep(Duration::from_millis(50));
+            flag.store(true, Ordering::SeqCst);
+        }
+    })
+    .unwrap();
+
+    // The function keeps running after the handle is gone.
+    unblocked.detach();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !flag.load(Ordering::SeqCst) {
+        assert!(Instant::now() < deadline, "the detached function never ran");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn unblock_writer_flush() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let buffer = SharedBuffer::default();
+    let mut writer = UnblockWriter::new(buffer.clone()).unwrap();
+
+    // Register the source in the poller.
+    writer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Write some data and flush it.
+    let written = poll_until(&poller, &mut writer, |w| w.write(b"hello")).unwrap();
+    assert_eq!(written, 5);
+    poll_until(&poller, &mut writer, |w| w.poll_flush()).unwrap();
+    assert_eq!(&*buffer.0.lock().unwrap(), b"hello");
+
+    // Closing is idempotent, and writes error afterwards.
+    poll_until(&poller, &mut writer, |w| w.poll_close()).unwrap();
+    poll_until(&poller, &mut writer, |w| w.poll_close()).unwrap();
+    assert!(poll_until(&poller, &mut writer, |w| w.write(b"world")).is_err());
+}
+
+#[test]
+fn unblock_writer_close_eof() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let (send, recv) = mpsc::channel();
+    let mut writer = UnblockWriter::new(ChannelWriter(send)).unwrap();
+    writer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let mut offset = 0;
+    poll_until(&poller, &mut writer, |w| {
+        w.poll_write_all(b"bye", &mut offset)
+    })
+    .unwrap();
+    poll_until(&poller, &mut writer, |w| w.poll_close()).unwrap();
+
+    // The other end sees every byte, then EOF, while the `UnblockWriter` is still alive.
+    let mut received = vec![];
+    loop {
+        match recv.recv_timeout(Duration::from_secs(5)) {
+            Ok(chunk) => received.extend(chunk),
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            Err(mpsc::RecvTimeoutError::Timeout) => panic!("the writer was never closed"),
+        }
+    }
+    assert_eq!(received, b"bye");
+    drop(writer);
+}
+
+#[test]
+fn unblock_writer_vectored() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let buffer = SharedBuffer::default();
+    let mut writer = UnblockWriter::new(buffer.clone()).unwrap();
+    writer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Write from two buffers at once.
+    let expected = b"hello world";
+    let bufs = [IoSlice::new(&expected[..6]), IoSlice::new(&expected[6..])];
+    let mut written = poll_until(&poller, &mut writer, |w| w.write_vectored(&bufs)).unwrap();
+    assert!(written > 0);
+
+    // Vectored writes may be short, so write out the rest.
+    while written < expected.len() {
+        written += poll_until(&poller, &mut writer, |w| w.write(&expected[written..])).unwrap();
+    }
+
+    poll_until(&poller, &mut writer, |w| w.poll_flush()).unwrap();
+    assert_eq!(&*buffer.0.lock().unwrap(), expected);
+}
+
+#[test]
+fn unblock_fn_join() {
+    let unblocked = UnblockFn::new(|| {
+        thread::sleep(Duration::from_millis(10));
+        42
+    })
+    .unwrap();
+
+    // No poller is needed.
+    assert_eq!(unblocked.join(), 42);
+}
+
+#[test]
+fn unblock_fn_spawner() {
+    let poller = Arc::new(Poller::new().unwrap());
+
+    // Run the function on a dedicated, named thread instead of the global pool.
+    let mut handle = None;
+    let mut unblocked = UnblockFn::with_spawner(
+        |job| {
+            let thread = thread::Builder::new()
+                .name("custom-pool".into())
+                .spawn(job)
+                .unwrap();
+            handle = Some(thread);
+        },
+        || thread::current().name().map(String::from),
+    )
+    .unwrap();
+    unblocked
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let name = poll_until(&poller, &mut unblocked, |u| u.result());
+    assert_eq!(name.as_deref(), Some("custom-pool"));
+    handle.unwrap().join().unwrap();
+}
+
+#[test]
+fn unblock_interval() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let interval = Duration::from_millis(20);
+    let start = Instant::now();
+
+    let mut runs = 0;
+    let mut task = UnblockInterval::new(
+        move || {
+            runs += 1;
+            runs
+        },
+        &mut wheel,
+        interval,
+    )
+    .unwrap();
+    task.register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let mut outputs = vec![];
+    let mut keys = vec![];
+    let mut events = vec![];
+    while outputs.len() < 3 {
+        if let Poll::Ready(output) = task.poll() {
+            outputs.push(output);
+            continue;
+        }
+
+        let next = wheel.fire_timers().unwrap();
+        events.clear();
+        poller.wait(&mut events, next).unwrap();
+        for event in &events {
+            keys.push(event.key);
+            task.handle_event(&poller, *event).unwrap();
+        }
+        task.handle_wheel(&mut wheel).unwrap();
+    }
+
+    // The first run starts right away, and the others wait for the interval.
+    assert_eq!(outputs, [1, 2, 3]);
+    assert!(start.elapsed() >= interval * 2);
+
+    // The timer reports its events under the secondary key.
+    keys.sort_unstable();
+    keys.dedup();
+    assert_eq!(keys, [0, polling_utils::secondary_key(0)]);
+}
+
+#[test]
+fn unblock_reader_to_end() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let data: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+
+    // A small pipe capacity means the data arrives over several wakeups.
+    let mut reader = UnblockReader::with_capacity(io::Cursor::new(data.clone()), 256).unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Each call reports the bytes it appended, after what was already in the buffer.
+    let mut buf = b"prefix".to_vec();
+    poll_until(&poller, &mut reader, |reader| {
+        let start = buf.len();
+        let poll = reader.read_to_end(&mut buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            assert_eq!(n, buf.len() - start);
+        }
+        poll
+    })
+    .unwrap();
+    assert_eq!(&buf[..6], b"prefix");
+    assert_eq!(&buf[6..], &*data);
+}
+
+#[test]
+fn unblock_into_inner() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut reader = UnblockReader::new(io::Cursor::new(b"hello".to_vec())).unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Read everything, then take the cursor back.
+    let mut buf = [0; 8];
+    let n = poll_until(&poller, &mut reader, |r| r.read(&mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"hello");
+    reader.deregister(&poller).unwrap();
+    let cursor = reader.into_inner_blocking();
+    assert_eq!(cursor.position(), 5);
+    assert_eq!(cursor.into_inner(), b"hello");
+
+    // Everything written is flushed into the recovered writer.
+    let mut writer = UnblockWriter::new(io::Cursor::new(Vec::new())).unwrap();
+    writer
+        .register(&poller, Event::readable(1), PollMode::Level)
+        .unwrap();
+    let mut offset = 0;
+    poll_until(&poller, &mut writer, |w| {
+        w.poll_write_all(b"world", &mut offset)
+    })
+    .unwrap();
+    writer.deregister(&poller).unwrap();
+    let cursor = writer.into_inner_blocking();
+    assert_eq!(cursor.into_inner(), b"world");
+}
+
+#[test]
+fn unblock_reader_fill() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut reader = UnblockReader::new(io::Cursor::new(b"hello world".to_vec())).unwrap();
+    reader
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Peek at the data without consuming it.
+    let filled = poll_until(&poller, &mut reader, |r| {
+        r.poll_fill().map_ok(<[u8]>::to_vec)
+    });
+    assert_eq!(filled.unwrap(), b"hello world");
+    let filled = poll_until(&poller, &mut reader, |r| {
+        r.poll_fill().map_ok(<[u8]>::to_vec)
+    });
+    assert_eq!(filled.unwrap(), b"hello world");
+
+    // Consume part of it, and the rest is still buffered.
+    reader.consume(6);
+    let filled = poll_until(&poller, &mut reader, |r| {
+        r.poll_fill().map_ok(<[u8]>::to_vec)
+    });
+    assert_eq!(filled.unwrap(), b"world");
+
+    // Reads are served from the buffer first.
+    let mut buf = [0; 3];
+    let n = poll_until(&poller, &mut reader, |r| r.read(&mut buf)).unwrap();
+    assert_eq!(&buf[..n], b"wor");
+
+    // Once everything is consumed, EOF is an empty slice.
+    reader.consume(2);
+    let filled = poll_until(&poller, &mut reader, |r| {
+        r.poll_fill().map_ok(<[u8]>::to_vec)
+    });
+    assert!(filled.unwrap().is_empty());
+}
+
+#[test]
+fn unblock_seek() {
+    let path = std::env::temp_dir().join(format!("polling-utils-seek-{}", std::process::id()));
+    fs::write(&path, b"hello world").unwrap();
+
+    // Cloned file handles share the same cursor.
+    let mut file = File::open(&path).unwrap();
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut seeker = UnblockSeek::new(file.try_clone().unwrap()).unwrap();
+    seeker
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Seek to an offset.
+    let pos = poll_until(&poller, &mut seeker, |s| s.seek(SeekFrom::Start(6))).unwrap();
+    assert_eq!(pos, 6);
+
+    // Read from there.
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "world");
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn unblock_writer_write_all() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let buffer = SharedBuffer::default();
+    let data: Vec<u8> = (0..4096).map(|i| i as u8).collect();
+
+    // A small pipe capacity means the data is written over several wakeups.
+    let mut writer = UnblockWriter::with_capacity(buffer.clone(), 256).unwrap();
+    writer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let mut offset = 0;
+    let mut wakeups = 0;
+    let mut events = vec![];
+    loop {
+        match writer.poll_write_all(&data, &mut offset) {
+            Poll::Ready(result) => break result.unwrap(),
+            Poll::Pending => assert!(offset < data.len()),
+        }
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert!(!events.is_empty(), "timed out waiting for an event");
+        for event in &events {
+            writer.handle_event(&poller, *event).unwrap();
+        }
+        wakeups += 1;
+    }
+    assert_eq!(offset, data.len());
+    assert!(wakeups > 1);
+
+    poll_until(&poller, &mut writer, |w| w.poll_flush()).unwrap();
+    assert_eq!(&*buffer.0.lock().unwrap(), &data);
+}
+
+#[test]
+fn unblock_writer_drained() {
+    let poller = Arc::new(Poller::new().unwrap());
+    let buffer = SharedBuffer::default();
+    let data: Vec<u8> = (0..1024 * 1024).map(|i| i as u8).collect();
+
+    let mut writer = UnblockWriter::with_capacity(buffer.clone(), 4096).unwrap();
+    writer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let mut offset = 0;
+    poll_until(&poller, &mut writer, |w| {
+        w.poll_write_all(&data, &mut offset)
+    })
+    .unwrap();
+
+    // Once drained, the destination has everything and the writer can be dropped.
+    poll_until(&poller, &mut writer, |w| w.poll_drained()).unwrap();
+    assert_eq!(buffer.0.lock().unwrap().len(), data.len());
+    assert_eq!(*buffer.0.lock().unwrap(), data);
+    drop(writer);
+}
+
+/// A writer that appends to a shared buffer.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer that sends every chunk written to it over a channel.
+struct ChannelWriter(mpsc::Sender<Vec<u8>>);
+
+impl io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Poll the source until the function returns a value, handling events in between.
+fn poll_until<S: Source, T>(
+    poller: &Arc<Poller>,
+    source: &mut S,
+    mut f: impl FnMut(&mut S) -> Poll<T>,
+) -> T {
+    let mut events = vec![];
+    loop {
+        if let Poll::Ready(value) = f(source) {
+            return value;
+        }
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert!(!events.is_empty(), "timed out waiting for an event");
+        for event in &events {
+            source.handle_event(poller, *event).unwrap();
+        }
+    }
+}
@@ -0,0 +1,749 @@
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn timerfd() {
+    use polling_utils::timer::TimerFd;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let start = Instant::now();
+    let mut timer = TimerFd::after(Duration::from_millis(50)).unwrap();
+
+    // Register the source in the poller.
+    timer
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Wait for the timer to fire, without a timer wheel.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    timer.handle_event(&poller, events[0]).unwrap();
+    assert_eq!(timer.expirations(), 1);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[test]
+fn timerfd_two_pollers() {
+    use polling_utils::timer::TimerFd;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let first = Arc::new(Poller::new().unwrap());
+    let second = Arc::new(Poller::new().unwrap());
+    let mut timer = TimerFd::after(Duration::from_millis(10)).unwrap();
+    timer
+        .register(&first, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    timer
+        .register(&second, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Both pollers report the same expiration.
+    let mut first_events = vec![];
+    first
+        .wait(&mut first_events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(first_events, [Event::readable(0)]);
+    let mut second_events = vec![];
+    second
+        .wait(&mut second_events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(second_events, [Event::readable(1)]);
+
+    // An event from one poller isn't taken for the other one's.
+    timer.handle_event(&second, first_events[0]).unwrap();
+    assert_eq!(timer.expirations(), 0);
+
+    timer.handle_event(&first, first_events[0]).unwrap();
+    assert_eq!(timer.expirations(), 1);
+
+    // The expiration was already read, so the duplicate event finds nothing.
+    timer.handle_event(&second, second_events[0]).unwrap();
+    assert_eq!(timer.expirations(), 0);
+}
+
+#[test]
+fn at_fires_once() {
+    use polling_utils::timer::TimerWheel;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let mut timer = wheel.at(deadline).unwrap();
+    timer
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Wait for the timer to fire.
+    let mut events = vec![];
+    while events.is_empty() {
+        let next = wheel.fire_timers().unwrap();
+        assert!(next.is_some() || Instant::now() >= deadline);
+        poller.wait(&mut events, next).unwrap();
+    }
+    assert_eq!(events, [Event::readable(0)]);
+    assert!(Instant::now() >= deadline);
+    timer.handle_event(&poller, events[0]).unwrap();
+    timer.handle_wheel(&mut wheel).unwrap();
+
+    // The timer does not fire again.
+    assert_eq!(wheel.fire_timers().unwrap(), None);
+    timer
+        .reregister(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn remaining() {
+    use polling_utils::timer::{Timer, TimerWheel};
+    use std::time::Duration;
+
+    let mut wheel = TimerWheel::new();
+    let timer = wheel.after(Duration::from_millis(100)).unwrap();
+    let remaining = timer.remaining().unwrap();
+    assert!(remaining > Duration::ZERO);
+    assert!(remaining <= Duration::from_millis(100));
+    assert!(!timer.is_expired());
+
+    // A timer that never fires has no remaining time.
+    let never = Timer::never().unwrap();
+    assert_eq!(never.remaining(), None);
+    assert!(!never.is_expired());
+}
+
+#[test]
+fn hierarchical_stress() {
+    use polling_utils::timer::HierarchicalTimerWheel;
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    type Callback = Box<dyn Fn() -> io::Result<()>>;
+
+    const COUNT: usize = 100_000;
+
+    let fired = Rc::new(RefCell::new(Vec::with_capacity(COUNT)));
+    let mut wheel = HierarchicalTimerWheel::<Callback>::default();
+    let start = Instant::now();
+
+    // Scatter the deadlines out of order over half a second.
+    let deadlines = (0..COUNT)
+        .map(|i| start + Duration::from_micros(((i * 7919) % COUNT) as u64 * 5))
+        .collect::<Vec<_>>();
+    for (i, &deadline) in deadlines.iter().enumerate() {
+        let fired = fired.clone();
+        wheel.insert_at(
+            deadline,
+            Box::new(move || {
+                fired.borrow_mut().push(i);
+                Ok(())
+            }),
+        );
+    }
+
+    let mut fired_at = vec![None; COUNT];
+    let mut seen = 0;
+    let give_up = Instant::now() + Duration::from_secs(10);
+    loop {
+        let before = Instant::now();
+        let next = wheel.fire_timers().unwrap();
+        let after = Instant::now();
+
+        // Timers never fire early, and no due timer is left behind.
+        for &i in &fired.borrow()[seen..] {
+            assert!(fired_at[i].is_none());
+            fired_at[i] = Some(after);
+        }
+        seen = fired.borrow().len();
+        for (i, &deadline) in deadlines.iter().enumerate() {
+            match fired_at[i] {
+                Some(at) => assert!(deadline <= at),
+                None => assert!(deadline + Duration::from_millis(2) > before),
+            }
+        }
+
+        if seen == COUNT {
+            assert_eq!(next, None);
+            break;
+        }
+        assert!(
+            Instant::now() < give_up,
+            "{} timers never fired",
+            COUNT - seen
+        );
+        std::thread::sleep(next.unwrap_or_default().min(Duration::from_millis(5)));
+    }
+
+    // Timers fire in the order of their deadlines, to the millisecond.
+    let fired = fired.borrow();
+    for pair in fired.windows(2) {
+        assert!(deadlines[pair[0]] < deadlines[pair[1]] + Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn hierarchical_fire_order() {
+    use polling_utils::timer::HierarchicalTimerWheel;
+    use std::cell::RefCell;
+    use std::io;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+
+    type Callback = Box<dyn Fn() -> io::Result<()>>;
+
+    let fired = Rc::new(RefCell::new(vec![]));
+    let mut wheel = HierarchicalTimerWheel::<Callback>::default();
+    let start = Instant::now();
+
+    // Timers spread over several coarse slots come due in a single call.
+    let deadlines = (0..1000)
+        .map(|i| start + Duration::from_micros((i * 7919) % 1000 * 200))
+        .collect::<Vec<_>>();
+    for (i, &deadline) in deadlines.iter().enumerate() {
+        let fired = fired.clone();
+        wheel.insert_at(
+            deadline,
+            Box::new(move || {
+                fired.borrow_mut().push(i);
+                Ok(())
+            }),
+        );
+    }
+    std::thread::sleep(Duration::from_millis(250));
+    let outcome = wheel.fire_expired().unwrap();
+    assert_eq!(outcome.fired, 1000);
+    assert_eq!(outcome.next, None);
+
+    // They are still fired in the order of their deadlines.
+    let fired = fired.borrow();
+    for pair in fired.windows(2) {
+        assert!(deadlines[pair[0]] < deadlines[pair[1]] + Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn hierarchical_far_future() {
+    use polling_utils::timer::HierarchicalTimerWheel;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = HierarchicalTimerWheel::new();
+
+    // Three years is past the end of the wheel.
+    let far = Duration::from_secs(3 * 365 * 24 * 60 * 60);
+    let mut timer = wheel.after(far).unwrap();
+    timer
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    let mut soon = wheel.after(Duration::from_millis(10)).unwrap();
+    soon.register(&poller, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    std::thread::sleep(Duration::from_millis(20));
+    let next = wheel.fire_timers().unwrap().unwrap();
+    assert!(next > Duration::from_secs(365 * 24 * 60 * 60));
+
+    let mut events = vec![];
+    poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+    assert_eq!(events, [Event::readable(1)]);
+}
+
+#[test]
+fn interval_policy_after_stall() {
+    use polling_utils::timer::{IntervalPolicy, TimerWheel};
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let interval = Duration::from_millis(20);
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut wheel = TimerWheel::new();
+    let mut rate = wheel
+        .interval_with_policy(interval, IntervalPolicy::FixedRate)
+        .unwrap();
+    let mut delay = wheel
+        .interval_with_policy(interval, IntervalPolicy::FixedDelay)
+        .unwrap();
+    rate.register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    delay
+        .register(&poller, Event::readable(1), PollMode::Oneshot)
+        .unwrap();
+
+    // Stall for several intervals before handling the first fire.
+    std::thread::sleep(interval * 5);
+    wheel.fire_timers().unwrap();
+    let mut events = vec![];
+    while events.len() < 2 {
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+    }
+    rate.handle_event(&poller, Event::readable(0)).unwrap();
+    delay.handle_event(&poller, Event::readable(1)).unwrap();
+
+    // The fixed-rate timer is still behind and is due again right away.
+    assert!(rate.is_expired());
+
+    // The fixed-delay timer waits a whole interval from when it was handled.
+    assert!(!delay.is_expired());
+    assert!(delay.remaining().unwrap() > interval / 2);
+}
+
+#[test]
+fn oneshot() {
+    use polling_utils::timer::OneShot;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let start = Instant::now();
+    let mut timer = OneShot::after(Duration::from_millis(50)).unwrap();
+    timer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // The timer fires on its own, without a timer wheel.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    assert!(!timer.has_fired());
+    timer.handle_event(&poller, events[0]).unwrap();
+    assert!(timer.has_fired());
+
+    // It only fires once.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(100)))
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn oneshot_other_key() {
+    use polling_utils::timer::OneShot;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut timer = OneShot::after(Duration::from_millis(10)).unwrap();
+    timer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+
+    // An event for another source doesn't fire the expired timer.
+    timer.handle_event(&poller, Event::readable(1)).unwrap();
+    assert!(!timer.has_fired());
+    timer.handle_event(&poller, events[0]).unwrap();
+    assert!(timer.has_fired());
+}
+
+#[test]
+fn closure_target() {
+    use polling_utils::timer::TimerWheel;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    type Callback = Box<dyn Fn() -> io::Result<()>>;
+
+    let fired = Arc::new(Mutex::new(vec![]));
+    let mut wheel = TimerWheel::<Callback>::default();
+    let now = Instant::now();
+
+    // Each callback records the ID it was given.
+    let mut ids = vec![];
+    for delay in [30, 10, 20] {
+        let fired = fired.clone();
+        let index = ids.len();
+        let callback: Callback = Box::new(move || {
+            fired.lock().unwrap().push(index);
+            Ok(())
+        });
+        ids.push(wheel.insert_at(now + Duration::from_millis(delay), callback));
+    }
+    let late = {
+        let fired = fired.clone();
+        wheel.insert_at(
+            now + Duration::from_secs(60),
+            Box::new(move || {
+                fired.lock().unwrap().push(usize::MAX);
+                Ok(())
+            }),
+        )
+    };
+    assert!(!ids.contains(&late));
+
+    // Fire the first three, in deadline order.
+    std::thread::sleep(Duration::from_millis(50));
+    let next = wheel.fire_timers().unwrap().unwrap();
+    assert_eq!(*fired.lock().unwrap(), [1, 2, 0]);
+    assert!(next > Duration::from_secs(30));
+}
+
+#[test]
+fn never_without_fd() {
+    use polling_utils::timer::TimerWheel;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // More timers than there are file descriptors to go around.
+    let mut wheel = TimerWheel::new();
+    let timers = (0..30_000)
+        .map(|_| wheel.after(Duration::MAX).unwrap())
+        .collect::<Vec<_>>();
+    assert!(timers.iter().all(|timer| timer.remaining().is_none()));
+
+    // The timer can still be registered, but never fires.
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut timer = wheel.after(Duration::MAX).unwrap();
+    timer
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+    assert_eq!(wheel.fire_timers().unwrap(), None);
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+    timer.deregister(&poller).unwrap();
+}
+
+#[test]
+fn interval_once_per_fire() {
+    use polling_utils::timer::TimerWheel;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let poller = Arc::new(Poller::new().unwrap());
+    if !poller.supports_level() {
+        return;
+    }
+
+    let interval = Duration::from_millis(30);
+    let mut wheel = TimerWheel::new();
+    let mut timer = wheel.interval(interval).unwrap();
+
+    // Edge-triggered modes would merge fires.
+    assert_eq!(
+        timer
+            .register(&poller, Event::readable(0), PollMode::Edge)
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+    timer
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Each interval produces exactly one event, without re-arming the timer.
+    let mut events = vec![];
+    for _ in 0..3 {
+        if let Some(wait) = wheel.fire_timers().unwrap() {
+            std::thread::sleep(wait);
+            wheel.fire_timers().unwrap();
+        }
+
+        events.clear();
+        poller
+            .wait(&mut events, Some(Duration::from_secs(1)))
+            .unwrap();
+        assert_eq!(events, [Event::readable(0)]);
+        timer.handle_event(&poller, events[0]).unwrap();
+        timer.handle_wheel(&mut wheel).unwrap();
+
+        // Handling the event leaves nothing behind.
+        events.clear();
+        poller.wait(&mut events, Some(Duration::ZERO)).unwrap();
+        assert!(events.is_empty());
+    }
+}
+
+#[test]
+fn shared_wheel() {
+    use polling_utils::timer::SharedTimerWheel;
+    use polling_utils::{Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // More timers than there are file descriptors to go around, all sharing one ping.
+    let mut wheel = SharedTimerWheel::new().unwrap();
+    let late = (0..29_900)
+        .map(|_| wheel.after(Duration::from_secs(60)))
+        .collect::<Vec<_>>();
+    let soon = (0..100)
+        .map(|_| wheel.after(Duration::from_millis(50)))
+        .collect::<Vec<_>>();
+    assert_eq!(wheel.len(), 30_000);
+
+    let poller = Arc::new(Poller::new().unwrap());
+    wheel
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    // Nothing fires at first.
+    assert!(wheel.fire_timers().unwrap().is_some());
+    assert_eq!(wheel.next_expired(), None);
+
+    // The first batch fires and notifies the wheel once.
+    std::thread::sleep(Duration::from_millis(60));
+    let next = wheel.fire_timers().unwrap().unwrap();
+    assert!(next > Duration::from_secs(30));
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    wheel.handle_event(&poller, events[0]).unwrap();
+
+    let fired = std::iter::from_fn(|| wheel.next_expired()).collect::<Vec<_>>();
+    assert_eq!(fired, soon);
+    assert_eq!(wheel.len(), late.len());
+
+    // Handling the event consumed the notification.
+    events.clear();
+    poller
+        .wait(&mut events, Some(Duration::from_millis(50)))
+        .unwrap();
+    assert!(events.is_empty());
+
+    // Cancelled timers are removed.
+    assert!(wheel.cancel(late[0]));
+    assert!(!wheel.cancel(late[0]));
+    assert!(!wheel.cancel(soon[0]));
+    assert_eq!(wheel.len(), late.len() - 1);
+    wheel.deregister(&poller).unwrap();
+}
+
+#[test]
+fn clear() {
+    use polling_utils::timer::TimerWheel;
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    type Callback = Box<dyn Fn() -> io::Result<()>>;
+
+    let fired = Arc::new(AtomicUsize::new(0));
+    let mut wheel = TimerWheel::<Callback>::with_id_start(100);
+    let now = Instant::now();
+
+    // Populate the wheel with timers that have already expired.
+    let ids = (0..10)
+        .map(|_| {
+            let fired = fired.clone();
+            wheel.insert_at(
+                now,
+                Box::new(move || {
+                    fired.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(ids[0], 100);
+
+    // Nothing fires after clearing.
+    wheel.clear();
+    assert_eq!(wheel.fire_timers().unwrap(), None);
+    assert_eq!(fired.load(Ordering::SeqCst), 0);
+
+    // IDs are not reused.
+    let id = wheel.insert_at(now + Duration::from_secs(60), Box::new(|| Ok(())));
+    assert_eq!(id, 110);
+}
+
+#[test]
+fn fire_outcome() {
+    use polling_utils::timer::{FireOutcome, TimerWheel};
+    use std::io;
+    use std::time::{Duration, Instant};
+
+    type Callback = Box<dyn Fn() -> io::Result<()>>;
+
+    let mut wheel = TimerWheel::<Callback>::default();
+    let now = Instant::now();
+    let late = now + Duration::from_secs(60);
+
+    // Five timers have already expired, and two are due later.
+    for _ in 0..5 {
+        wheel.insert_at(now, Box::new(|| Ok(())));
+    }
+    wheel.insert_at(late, Box::new(|| Ok(())));
+    wheel.insert_at(late + Duration::from_secs(1), Box::new(|| Ok(())));
+
+    let outcome = wheel.fire_expired().unwrap();
+    assert_eq!(
+        outcome,
+        FireOutcome {
+            fired: 5,
+            next: Some(late)
+        }
+    );
+
+    // Nothing else has expired.
+    let outcome = wheel.fire_expired().unwrap();
+    assert_eq!(outcome.fired, 0);
+    assert_eq!(outcome.next, Some(late));
+}
+
+#[test]
+fn driver() {
+    use polling_utils::timer::TimerDriver;
+    use polling_utils::{dispatch, Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut driver = TimerDriver::new().unwrap();
+    driver
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+    assert_eq!(driver.timeout(), None);
+
+    // Every timer shares the driver's single registration.
+    let now = Instant::now();
+    let ids = [30, 10, 20]
+        .iter()
+        .map(|&delay| driver.at(now + Duration::from_millis(delay)))
+        .collect::<Vec<_>>();
+    let cancelled = driver.after(Duration::from_millis(15));
+    assert!(driver.cancel(cancelled));
+    assert_eq!(driver.len(), 3);
+
+    // Wait on the poller for as long as the driver asks, until every timer has fired.
+    let mut fired = vec![];
+    let mut events = vec![];
+    while fired.len() < ids.len() {
+        assert!(
+            now.elapsed() < Duration::from_secs(5),
+            "timers did not fire"
+        );
+        let timeout = driver.timeout();
+        dispatch(&poller, &mut events, &mut [&mut driver], timeout).unwrap();
+        fired.extend(std::iter::from_fn(|| driver.next_expired()));
+    }
+
+    assert_eq!(fired, [ids[1], ids[2], ids[0]]);
+    assert!(now.elapsed() >= Duration::from_millis(30));
+    assert!(driver.is_empty());
+    assert_eq!(driver.timeout(), None);
+    driver.deregister(&poller).unwrap();
+}
+
+#[test]
+fn interval_jitter() {
+    use polling_utils::timer::TimerWheel;
+    use std::time::Duration;
+
+    let mut wheel = TimerWheel::new();
+    let jitter = Duration::from_millis(500);
+    let remaining = (0..16)
+        .map(|_| {
+            wheel
+                .interval_with_jitter(Duration::from_secs(1), jitter)
+                .unwrap()
+                .remaining()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    // Every first deadline is within the jitter window.
+    assert!(remaining.iter().all(|&remaining| remaining < jitter));
+
+    // And they are spread out across it.
+    let min = remaining.iter().min().unwrap();
+    let max = remaining.iter().max().unwrap();
+    assert!(*max - *min > jitter / 4, "{:?}", remaining);
+
+    // No jitter fires right away, like a plain interval.
+    let timer = wheel
+        .interval_with_jitter(Duration::from_secs(1), Duration::ZERO)
+        .unwrap();
+    assert!(timer.is_expired());
+}
+
+#[test]
+fn driver_payloads() {
+    use polling_utils::timer::TimerDriver;
+    use polling_utils::{dispatch, Event, PollMode, Poller, Source};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut driver = TimerDriver::<String>::with_payloads().unwrap();
+    driver
+        .register(&poller, Event::readable(0), PollMode::Level)
+        .unwrap();
+
+    let late = driver.after_with(Duration::from_millis(20), "late".to_string());
+    let soon = driver.after_with(Duration::from_millis(10), "soon".to_string());
+    let tick = driver.interval_with(Duration::from_millis(25), "tick".to_string());
+    let plain = driver.after(Duration::from_millis(5));
+
+    // Run until the interval has fired a few times.
+    let start = Instant::now();
+    let mut fired = vec![];
+    let mut events = vec![];
+    while fired.iter().filter(|(id, _)| *id == tick).count() < 3 {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "timers did not fire"
+        );
+        let timeout = driver.timeout();
+        dispatch(&poller, &mut events, &mut [&mut driver], timeout).unwrap();
+        fired.extend(driver.drain_fired());
+    }
+
+    // One-shot timers hand their payload back exactly once.
+    let payloads = |wanted| {
+        fired
+            .iter()
+            .filter(|(id, _)| *id == wanted)
+            .map(|(_, payload)| payload.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(payloads(soon), ["soon"]);
+    assert_eq!(payloads(late), ["late"]);
+    assert_eq!(payloads(tick), ["tick"; 3]);
+
+    // Timers without a payload are skipped.
+    assert!(payloads(plain).is_empty());
+    assert!(driver.cancel(tick));
+    driver.deregister(&poller).unwrap();
+}
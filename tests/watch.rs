@@ -0,0 +1,85 @@
+#![cfg(any(target_os = "linux", target_os = "android"))]
+
+use polling_utils::watch::{Inotify, WatchFlags};
+use polling_utils::{Event, PollMode, Poller, Source};
+
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn create_file() {
+    let dir = std::env::temp_dir().join(format!("polling-utils-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut inotify = Inotify::new().unwrap();
+    let watch = inotify.add_watch(&dir, WatchFlags::CREATE).unwrap();
+
+    // Register the source in the poller.
+    inotify
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Create a file in the watched directory.
+    fs::write(dir.join("created"), b"hello").unwrap();
+
+    // Wait for the event.
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0], Event::readable(0));
+
+    // An event for another key leaves the file events alone.
+    inotify.handle_event(&poller, Event::readable(1)).unwrap();
+    assert!(inotify.read_event().is_none());
+
+    inotify.handle_event(&poller, events[0]).unwrap();
+    let event = inotify.read_event().unwrap();
+    assert_eq!(event.watch, watch);
+    assert!(event.mask.contains(WatchFlags::CREATE));
+    assert_eq!(event.name.as_deref(), Some("created".as_ref()));
+    assert!(inotify.read_event().is_none());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn watch_removed() {
+    let path = std::env::temp_dir().join(format!(
+        "polling-utils-watch-removed-{}",
+        std::process::id()
+    ));
+    fs::write(&path, b"hello").unwrap();
+
+    let poller = Arc::new(Poller::new().unwrap());
+    let mut inotify = Inotify::new().unwrap();
+    let watch = inotify.add_watch(&path, WatchFlags::DELETE_SELF).unwrap();
+    inotify
+        .register(&poller, Event::readable(0), PollMode::Oneshot)
+        .unwrap();
+
+    // Deleting the file removes the watch.
+    fs::remove_file(&path).unwrap();
+
+    let mut events = vec![];
+    poller
+        .wait(&mut events, Some(Duration::from_secs(1)))
+        .unwrap();
+    assert_eq!(events, [Event::readable(0)]);
+    inotify.handle_event(&poller, events[0]).unwrap();
+
+    let event = inotify.read_event().unwrap();
+    assert_eq!(event.watch, watch);
+    assert!(event.mask.contains(WatchFlags::DELETE_SELF));
+    assert!(!event.is_watch_removed());
+
+    // The watch is then removed, which is only reported in the raw mask.
+    let event = inotify.read_event().unwrap();
+    assert_eq!(event.watch, watch);
+    assert!(event.is_watch_removed());
+    assert!(!event.is_queue_overflow());
+    assert!(inotify.read_event().is_none());
+}